@@ -0,0 +1,175 @@
+use std::time::Duration;
+use chrono::NaiveDate;
+use tauri::{AppHandle, Emitter};
+use crate::db::Database;
+use crate::models::{CalendarEvent, CalendarEventKind, CalendarRange, Movie};
+use crate::tmdb::TmdbClient;
+
+/// TMDB dates are fetched again once they are this old.
+const REFRESH_INTERVAL_HOURS: i64 = 24;
+/// How often the background task looks whether a refresh is due.
+const CHECK_INTERVAL_SECS: u64 = 60 * 60;
+const REFRESHED_AT_KEY: &str = "calendar_refreshed_at";
+
+/// The YYYY-MM-DD part of a date or timestamp, if it is a real date.
+fn day(value: &str) -> Option<String> {
+    let date = value.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some(date.to_string())
+}
+
+fn event(movie: &Movie, kind: CalendarEventKind, date: String) -> CalendarEvent {
+    CalendarEvent {
+        movie_id: movie.id,
+        title: movie.title.clone(),
+        kind,
+        date,
+        name: None,
+        season: None,
+        episode: None,
+        region: None,
+        release_type: None,
+    }
+}
+
+/// Season premieres, and every episode of the season that is airing.
+async fn tv_events(tmdb: &TmdbClient, movie: &Movie, tmdb_id: u64) -> Result<Vec<CalendarEvent>, String> {
+    let schedule = tmdb.tv_schedule(tmdb_id).await?;
+    let mut events = Vec::new();
+    // Season 0 holds the specials, it has no premiere of its own
+    for season in schedule.seasons.iter().filter(|s| s.season_number > 0) {
+        if let Some(date) = season.air_date.as_deref().and_then(day) {
+            events.push(CalendarEvent {
+                name: season.name.clone(),
+                season: Some(season.season_number),
+                ..event(movie, CalendarEventKind::SeasonPremiere, date)
+            });
+        }
+    }
+
+    if let Some(next) = schedule.next_episode_to_air {
+        let episodes = match tmdb.season(tmdb_id, next.season_number).await {
+            Ok(season) => season.episodes,
+            Err(e) => {
+                eprintln!("[Calendar] Season {} of {} failed: {}", next.season_number, movie.title, e);
+                vec![next.clone()]
+            }
+        };
+        for episode in episodes {
+            if let Some(date) = episode.air_date.as_deref().and_then(day) {
+                events.push(CalendarEvent {
+                    name: episode.name,
+                    season: Some(next.season_number),
+                    episode: Some(episode.episode_number),
+                    ..event(movie, CalendarEventKind::Episode, date)
+                });
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Release dates in the configured regions.
+async fn release_events(tmdb: &TmdbClient, movie: &Movie, tmdb_id: u64) -> Result<Vec<CalendarEvent>, String> {
+    let mut events: Vec<CalendarEvent> = Vec::new();
+    for country in tmdb.release_dates(tmdb_id).await? {
+        for release in country.release_dates {
+            let Some(date) = release.release_date.as_deref().and_then(day) else { continue };
+            // Releases differing only in their note
+            let duplicate = events.iter().any(|e| {
+                e.date == date && e.release_type == release.release_type && e.region.as_deref() == Some(country.iso_3166_1.as_str())
+            });
+            if !duplicate {
+                events.push(CalendarEvent {
+                    region: Some(country.iso_3166_1.clone()),
+                    release_type: release.release_type,
+                    ..event(movie, CalendarEventKind::RegionalRelease, date)
+                });
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Fetch the air dates of the library's TV shows and the regional release
+/// dates of movies not made yet. Returns how many titles were updated; a
+/// title that fails keeps its previous dates, one no longer on the calendar
+/// loses them.
+pub async fn refresh(db: &Database, tmdb: &TmdbClient) -> Result<usize, String> {
+    let config = db.get_config();
+    if !config.tmdb_offline {
+        config.tmdb_api_key.as_ref().filter(|k| !k.trim().is_empty()).ok_or("TMDB API Key not set")?;
+    }
+
+    let mut refreshed = 0;
+    for movie in db.get_movies() {
+        let result = match movie.tmdb_id {
+            Some(tmdb_id) if movie.category.as_deref() == Some("tv") => tv_events(tmdb, &movie, tmdb_id).await,
+            Some(tmdb_id) if movie.production_status.as_deref() == Some("unmade") => release_events(tmdb, &movie, tmdb_id).await,
+            // No longer on the calendar, drop the dates it had
+            _ => {
+                db.replace_calendar_events(movie.id, &[]).map_err(|e| e.to_string())?;
+                continue;
+            }
+        };
+        match result {
+            Ok(events) => {
+                db.replace_calendar_events(movie.id, &events).map_err(|e| e.to_string())?;
+                refreshed += 1;
+            }
+            Err(e) => eprintln!("[Calendar] Dates of {} failed: {}", movie.title, e),
+        }
+    }
+    db.save_setting(REFRESHED_AT_KEY, &chrono::Utc::now().to_rfc3339()).map_err(|e| e.to_string())?;
+    Ok(refreshed)
+}
+
+/// Have the background task fetch the TMDB dates again on its next check,
+/// e.g. after a restore dropped them.
+pub fn invalidate(db: &Database) -> Result<(), String> {
+    db.save_setting(REFRESHED_AT_KEY, "").map_err(|e| e.to_string())
+}
+
+fn is_due(db: &Database) -> bool {
+    db.get_setting(REFRESHED_AT_KEY)
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+        .is_none_or(|t| chrono::Utc::now().signed_duration_since(t) >= chrono::Duration::hours(REFRESH_INTERVAL_HOURS))
+}
+
+/// Refresh the TMDB dates once a day for as long as the app runs, sending
+/// `calendar-updated` with the number of titles after each refresh.
+pub fn spawn_daily_refresh(app: AppHandle, db: Database, tmdb: TmdbClient) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if is_due(&db) {
+                match refresh(&db, &tmdb).await {
+                    Ok(count) => app.emit("calendar-updated", count).unwrap_or(()),
+                    Err(e) => eprintln!("[Calendar] Refresh failed: {}", e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// Every dated event in `range`: release, viewing and production deadline
+/// dates of the library, and the stored TMDB air and release dates.
+pub fn events(db: &Database, range: &CalendarRange) -> Result<Vec<CalendarEvent>, String> {
+    let start = day(&range.start).ok_or("Invalid start date")?;
+    let end = day(&range.end).ok_or("Invalid end date")?;
+    let mut events = db.get_calendar_events(&start, &end).map_err(|e| e.to_string())?;
+    for movie in db.get_movies() {
+        let own = [
+            (CalendarEventKind::Release, &movie.release_date),
+            (CalendarEventKind::Viewing, &movie.viewing_date),
+            (CalendarEventKind::Deadline, &movie.production_deadline),
+        ];
+        for (kind, date) in own {
+            if let Some(date) = date.as_deref().and_then(day).filter(|d| *d >= start && *d <= end) {
+                events.push(event(&movie, kind, date));
+            }
+        }
+    }
+    events.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok(events)
+}
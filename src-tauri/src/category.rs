@@ -0,0 +1,158 @@
+use regex::{Regex, RegexBuilder};
+use crate::glob;
+use crate::paths;
+use crate::models::{AppConfig, CategoryRule, CategoryRuleTest};
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    path: Option<Regex>,
+    path_regex: Option<Regex>,
+    file_type: Option<String>,
+    file_name: Option<Regex>,
+    category: Option<String>,
+    tags: Vec<String>,
+}
+
+impl CompiledRule {
+    fn compile(rule: &CategoryRule) -> Result<Self, String> {
+        let case_insensitive = glob::default_case_insensitive();
+        let compile_glob = |g: &Option<String>| -> Result<Option<Regex>, String> {
+            match g.as_deref().map(str::trim).filter(|g| !g.is_empty()) {
+                Some(g) => glob::compile(&g.replace('\\', "/"), case_insensitive)
+                    .map(Some)
+                    .map_err(|e| format!("invalid glob '{}': {}", g, e)),
+                None => Ok(None),
+            }
+        };
+
+        let path_regex = match rule.path_regex.as_deref().filter(|r| !r.is_empty()) {
+            Some(r) => Some(
+                RegexBuilder::new(r)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|e| format!("invalid regex '{}': {}", r, e))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            path: compile_glob(&rule.path)?,
+            path_regex,
+            file_type: rule.file_type.clone().filter(|t| !t.is_empty()),
+            file_name: compile_glob(&rule.file_name)?,
+            category: rule.category.clone().filter(|c| !c.is_empty()),
+            tags: rule.tags.clone(),
+        })
+    }
+
+    /// `path` uses `/` separators. Conditions that are not set always match.
+    fn matches(&self, path: &str, file_type: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.path.as_ref().is_none_or(|re| re.is_match(path))
+            && self.path_regex.as_ref().is_none_or(|re| re.is_match(path))
+            && self.file_type.as_ref().is_none_or(|t| t.eq_ignore_ascii_case(file_type))
+            && self.file_name.as_ref().is_none_or(|re| re.is_match(name))
+    }
+}
+
+/// Category and tags a rule set assigns to one file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolution {
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// Indexes of the rules that matched, in order
+    pub matched_rules: Vec<usize>,
+}
+
+/// The monitor folder lists plus the material category rules from
+/// [`AppConfig`]. Both auto-match and refresh resolve categories here.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryRules {
+    /// (rule index, compiled rule); rules that failed to compile are left out
+    rules: Vec<(usize, CompiledRule)>,
+    /// (normalized folder, category), most specific folder first
+    folders: Vec<(String, String)>,
+}
+
+fn folder_categories(config: &AppConfig) -> Vec<(String, String)> {
+    let mut folders: Vec<(String, String)> = config.monitor_folders_source.iter()
+        .map(|f| (f, "source"))
+        .chain(config.monitor_folders_finished.iter().map(|f| (f, "finished")))
+        .map(|(f, cat)| (paths::normalize(f), cat.to_string()))
+        .collect();
+    folders.sort_by_key(|(f, _)| std::cmp::Reverse(f.len()));
+    folders
+}
+
+impl CategoryRules {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let (rules, errors) = Self::compile(&config.category_rules);
+        for e in errors {
+            eprintln!("[CATEGORY] Skipping rule: {}", e);
+        }
+
+        Self { rules, folders: folder_categories(config) }
+    }
+
+    fn compile(rules: &[CategoryRule]) -> (Vec<(usize, CompiledRule)>, Vec<String>) {
+        let mut compiled = Vec::new();
+        let mut errors = Vec::new();
+        for (i, rule) in rules.iter().enumerate() {
+            if !rule.enabled {
+                continue;
+            }
+            match CompiledRule::compile(rule) {
+                Ok(r) => compiled.push((i, r)),
+                Err(e) => errors.push(format!("rule {}: {}", i + 1, e)),
+            }
+        }
+        (compiled, errors)
+    }
+
+    /// A file in a source or finished monitor folder takes that folder's
+    /// category. Elsewhere the first matching rule with a category sets it.
+    /// Tags are collected from every matching rule either way.
+    pub fn resolve(&self, path: &str, file_type: &str) -> Resolution {
+        let path = paths::normalize(path);
+        let mut resolution = Resolution {
+            category: self.folders.iter()
+                .find(|(folder, _)| paths::is_within(&path, folder))
+                .map(|(_, cat)| cat.clone()),
+            ..Default::default()
+        };
+
+        for (i, rule) in &self.rules {
+            if !rule.matches(&path, file_type) {
+                continue;
+            }
+            resolution.matched_rules.push(*i);
+            if resolution.category.is_none() {
+                resolution.category = rule.category.clone();
+            }
+            for tag in &rule.tags {
+                if !resolution.tags.contains(tag) {
+                    resolution.tags.push(tag.clone());
+                }
+            }
+        }
+
+        resolution
+    }
+}
+
+/// Evaluate `rules` (or the configured rules) against one path, reporting
+/// rules that don't compile instead of silently skipping them.
+pub fn test_rules(config: &AppConfig, rules: Option<Vec<CategoryRule>>, path: &str, file_type: &str) -> CategoryRuleTest {
+    let rules = rules.unwrap_or_else(|| config.category_rules.clone());
+    let (compiled, errors) = CategoryRules::compile(&rules);
+    let engine = CategoryRules { rules: compiled, folders: folder_categories(config) };
+
+    let resolution = engine.resolve(path, file_type);
+    CategoryRuleTest {
+        file_type: file_type.to_string(),
+        category: resolution.category,
+        tags: resolution.tags,
+        matched_rules: resolution.matched_rules,
+        errors,
+    }
+}
@@ -1,6 +1,11 @@
 use tauri::State;
 use crate::db::Database;
-use crate::models::AppConfig;
+use crate::models::{AppConfig, CategoryRule, CategoryRuleTest};
+use crate::calendar;
+use crate::category;
+use crate::jobs::JobRegistry;
+use crate::commands::files::file_type_of;
+use crate::commands::images::spawn_image_gc;
 
 #[tauri::command]
 pub fn get_app_info(state: State<Database>) -> crate::models::AppInfo {
@@ -27,9 +32,25 @@ pub fn save_config(state: State<Database>, config: AppConfig) -> Result<(), Stri
     state.save_config(config).map_err(|e| e.to_string())
 }
 
+/// Try category rules against a path. Pass `rules` to test unsaved edits,
+/// leave it out to test the saved ones.
+#[tauri::command]
+pub fn test_category_rules(state: State<Database>, path: String, rules: Option<Vec<CategoryRule>>) -> CategoryRuleTest {
+    let file_type = file_type_of(std::path::Path::new(&path)).unwrap_or("other");
+    category::test_rules(&state.get_config(), rules, &path, file_type)
+}
+
+/// Ask a running background job to stop. Returns false if it already finished.
+#[tauri::command]
+pub fn cancel_job(jobs: State<JobRegistry>, job_id: String) -> bool {
+    jobs.cancel(&job_id)
+}
+
 #[tauri::command]
 pub fn clear_data(state: State<Database>) -> Result<(), String> {
-    state.clear_all_data().map_err(|e| e.to_string())
+    state.clear_all_data().map_err(|e| e.to_string())?;
+    spawn_image_gc(state.inner().clone());
+    Ok(())
 }
 
 #[tauri::command]
@@ -39,7 +60,11 @@ pub fn backup_database(state: State<Database>, path: String) -> Result<(), Strin
 
 #[tauri::command]
 pub fn restore_database(state: State<Database>, path: String) -> Result<(), String> {
-    state.restore(&path).map_err(|e| e.to_string())
+    state.restore(&path).map_err(|e| e.to_string())?;
+    calendar::invalidate(&state)?;
+    // The restored movies' images are counted again by now, only the others go
+    spawn_image_gc(state.inner().clone());
+    Ok(())
 }
 
 #[tauri::command]
@@ -0,0 +1,19 @@
+use tauri::State;
+use crate::calendar;
+use crate::db::Database;
+use crate::models::{CalendarEvent, CalendarRange};
+use crate::tmdb::TmdbClient;
+
+/// Release, viewing, deadline, episode and season dates in `range`, oldest
+/// first. TMDB dates are those of the last daily refresh.
+#[tauri::command]
+pub fn get_calendar_events(state: State<Database>, range: CalendarRange) -> Result<Vec<CalendarEvent>, String> {
+    calendar::events(&state, &range)
+}
+
+/// Fetch the TMDB air and release dates now instead of waiting for the
+/// daily refresh. Returns how many titles were updated.
+#[tauri::command]
+pub async fn refresh_calendar(state: State<'_, Database>, tmdb: State<'_, TmdbClient>) -> Result<usize, String> {
+    calendar::refresh(&state, &tmdb).await
+}
@@ -1,15 +1,44 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
 use tauri::State;
 use crate::db::Database;
-use crate::models::MatchedFile;
-use walkdir::WalkDir;
+use crate::models::{DuplicateFile, DuplicateGroup, MatchedFile, Movie, MovieImportProposal, OrphanFile, OrphanGroup, OrphanSuggestion, ScannedFile};
+use crate::models::tmdb::TmdbMovie;
+use crate::tmdb::TmdbClient;
+use crate::fingerprint::fingerprint_cached;
+use crate::matching::{explain_path, explain_prepared, score_name, score_prepared, to_percent, MatchTarget, PreparedPath};
+use crate::matching::release::{self, ParsedName};
+use crate::ignore::ScanFilter;
+use crate::paths;
 use strsim;
-use crate::commands::usn::search_usn_internal;
+use crate::search::{self, SEARCH_LIMIT};
 use base64::{Engine as _, engine::general_purpose};
 use mime_guess;
 
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "ts"];
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "wav", "m4a"];
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "gif", "tif", "tiff", "svg"];
+pub const DOC_EXTENSIONS: &[&str] = &["pdf", "doc", "docx", "txt", "nfo", "md", "epub", "mobi", "azw3"];
+
+/// Material type of a file by extension: video, audio, image or doc.
+pub fn file_type_of(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    let ext = ext.as_str();
+    if VIDEO_EXTENSIONS.contains(&ext) {
+        Some("video")
+    } else if AUDIO_EXTENSIONS.contains(&ext) {
+        Some("audio")
+    } else if IMAGE_EXTENSIONS.contains(&ext) {
+        Some("image")
+    } else if DOC_EXTENSIONS.contains(&ext) {
+        Some("doc")
+    } else {
+        None
+    }
+}
+
 #[tauri::command]
 pub fn read_image(path: String) -> Result<String, String> {
     let content = fs::read(&path).map_err(|e| e.to_string())?;
@@ -88,14 +117,15 @@ pub async fn open_directory(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn scan_directories(paths: Vec<String>, titles: Option<Vec<String>>, threshold: Option<f64>) -> Result<Vec<MatchedFile>, String> {
+pub async fn scan_directories(state: State<'_, Database>, paths: Vec<String>, titles: Option<Vec<String>>, threshold: Option<f64>, year: Option<u16>) -> Result<Vec<MatchedFile>, String> {
     let mut threshold = threshold.unwrap_or(0.8);
     if threshold > 1.0 {
         threshold = threshold / 100.0;
     }
+    let filter = ScanFilter::from_config(&state.get_config());
 
     tauri::async_runtime::spawn_blocking(move || {
-        scan_paths_internal(paths, titles, threshold)
+        scan_paths_internal(paths, titles, year, threshold, &filter)
     }).await.map_err(|e| e.to_string())
 }
 
@@ -130,37 +160,314 @@ pub fn rename_file_direct(path: String, new_name: String) -> Result<(), String>
     fs::rename(path, new_path).map_err(|e| e.to_string())
 }
 
+/// Bulk import: find the titles in the chosen folders and propose a TMDB
+/// match for each, so a whole archive can be added at once.
 #[tauri::command]
-pub fn scan_for_movies(_paths: Vec<String>) -> Result<Vec<MatchedFile>, String> {
-    // Stub
-    Ok(Vec::new())
+pub async fn scan_for_movies(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, paths: Vec<String>) -> Result<Vec<MovieImportProposal>, String> {
+    let config = state.get_config();
+    if !config.tmdb_offline {
+        config.tmdb_api_key.as_ref().ok_or("TMDB API Key not set")?;
+    }
+    let filter = ScanFilter::from_config(&config);
+    let library_ids: HashSet<u64> = state.get_movies().iter().filter_map(|m| m.tmdb_id).collect();
+
+    let candidates = tauri::async_runtime::spawn_blocking(move || collect_title_candidates(&paths, &filter))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Sequential on purpose: the client's rate limiter paces the lookups anyway
+    let mut proposals = Vec::with_capacity(candidates.len());
+    for file in candidates {
+        let mut proposal = propose_movie(&tmdb, file).await;
+        proposal.already_in_library = proposal.candidate.as_ref().is_some_and(|c| library_ids.contains(&c.id));
+        proposals.push(proposal);
+    }
+    Ok(proposals)
 }
 
-pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, threshold: f64) -> Vec<MatchedFile> {
-    let video_extensions = ["mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "ts"];
-    let audio_extensions = ["mp3", "flac", "wav", "m4a"];
-    let image_extensions = ["jpg", "jpeg", "png", "webp", "bmp", "gif", "tif", "tiff", "svg"];
-    let doc_extensions = ["pdf", "doc", "docx", "txt", "nfo", "md", "epub", "mobi", "azw3"];
+/// One entry per title: a lone video is its own title, while a folder of
+/// episodes (or of parts with the same parsed title) is one title.
+fn collect_title_candidates(paths: &[String], filter: &ScanFilter) -> Vec<ScannedFile> {
+    let mut candidates = Vec::new();
+
+    for root_str in paths {
+        let root = Path::new(root_str);
+        if !root.exists() { continue; }
+
+        let mut videos_by_dir: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+        for entry in filter.walk(root) {
+            if !entry.file_type().is_file() || file_type_of(entry.path()) != Some("video") {
+                continue;
+            }
+            let stem = entry.path().file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+            if stem == "sample" || stem.ends_with("-sample") || stem.ends_with(".sample") {
+                continue;
+            }
+            let dir = entry.path().parent().unwrap_or(root).to_path_buf();
+            match videos_by_dir.iter_mut().find(|(d, _)| *d == dir) {
+                Some((_, files)) => files.push(entry.into_path()),
+                None => videos_by_dir.push((dir, vec![entry.into_path()])),
+            }
+        }
+
+        for (dir, files) in videos_by_dir {
+            let parsed: Vec<ParsedName> = files.iter()
+                .map(|f| release::parse(&f.file_stem().unwrap_or_default().to_string_lossy()))
+                .collect();
+            let is_series = parsed.iter().any(|p| p.episode.is_some());
+            let same_title = parsed.windows(2).all(|w| w[0].title.eq_ignore_ascii_case(&w[1].title));
+
+            if dir != root && files.len() > 1 && (is_series || same_title) {
+                let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let mut query = release::parse(&name).title;
+                if query.is_empty() {
+                    query = parsed[0].title.clone();
+                }
+                candidates.push(ScannedFile {
+                    path: dir.to_string_lossy().to_string(),
+                    parent_folder: dir.parent().unwrap_or(&dir).to_string_lossy().to_string(),
+                    name,
+                    search_query: query,
+                });
+                continue;
+            }
+
+            for (file, parsed) in files.iter().zip(parsed) {
+                candidates.push(ScannedFile {
+                    path: file.to_string_lossy().to_string(),
+                    name: file.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                    parent_folder: dir.to_string_lossy().to_string(),
+                    search_query: parsed.title,
+                });
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Look the title up on TMDB and rank the results by title similarity,
+/// year agreement and movie/TV type.
+async fn propose_movie(tmdb: &TmdbClient, file: ScannedFile) -> MovieImportProposal {
+    let mut proposal = MovieImportProposal {
+        file,
+        candidate: None,
+        movie: None,
+        confidence: 0,
+        alternatives: Vec::new(),
+        already_in_library: false,
+        error: None,
+    };
+
+    let results = match tmdb.search_multi(&proposal.file.search_query, 1).await {
+        Ok(r) => r,
+        Err(e) => {
+            proposal.error = Some(e);
+            return proposal;
+        }
+    };
+
+    let parsed = release::parse(&proposal.file.name);
+    let looks_like_tv = parsed.episode.is_some() || parsed.season.is_some() || Path::new(&proposal.file.path).is_dir();
+
+    let mut scored: Vec<(f64, TmdbMovie)> = results.into_iter()
+        .map(|r| {
+            let titles: Vec<String> = [r.display_title(), r.display_original_title()]
+                .into_iter().flatten().map(|s| s.to_string()).collect();
+            let year = r.display_date().and_then(release::year_of);
+            let target = MatchTarget::new(&titles, year);
+            let mut score = score_name(&proposal.file.name, &target).max(score_name(&proposal.file.search_query, &target));
+            if r.media_type.as_deref() == Some(if looks_like_tv { "tv" } else { "movie" }) {
+                score = (score + 0.02).min(1.0);
+            }
+            (score, r)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut ranked = scored.into_iter();
+    if let Some((score, best)) = ranked.next() {
+        let mut movie = best.to_movie();
+        movie.local_video_path = Some(proposal.file.path.clone());
+        movie.matched_folders = vec![proposal.file.parent_folder.clone()];
+        proposal.confidence = to_percent(score);
+        proposal.movie = Some(movie);
+        proposal.candidate = Some(best);
+        proposal.alternatives = ranked.take(5).map(|(_, r)| r).collect();
+    }
+
+    proposal
+}
+
+/// Group files in the monitored folders that have identical content.
+///
+/// Files are bucketed by size first, then by the partial fingerprint; with
+/// `full_hash` the candidates are confirmed by hashing the whole file.
+#[tauri::command]
+pub async fn find_duplicate_files(state: State<'_, Database>, full_hash: Option<bool>) -> Result<Vec<DuplicateGroup>, String> {
+    let config = state.get_config();
+    let folders = config.monitored_folders();
+    let filter = ScanFilter::from_config(&config);
+    let db = state.inner().clone();
+    let with_full = full_hash.unwrap_or(false);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for folder in &folders {
+            let root = Path::new(folder);
+            if !root.exists() { continue; }
+            for entry in filter.walk(root) {
+                if !entry.file_type().is_file() { continue; }
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                // Nested monitor folders would otherwise report a file as its own duplicate
+                if size > 0 && seen.insert(paths::normalize(&entry.path().to_string_lossy())) {
+                    by_size.entry(size).or_default().push(entry.into_path());
+                }
+            }
+        }
+
+        let mut linked: HashMap<String, Vec<u64>> = HashMap::new();
+        for movie in db.get_movies() {
+            for m in &movie.materials {
+                linked.entry(paths::normalize(&m.path)).or_default().push(movie.id);
+            }
+        }
+
+        let mut groups = Vec::new();
+        for (size, paths) in by_size {
+            if paths.len() < 2 { continue; }
+
+            let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+            for path in paths {
+                match fingerprint_cached(&db, &path, with_full) {
+                    Ok(fp) => {
+                        let key = if with_full { fp.full.unwrap_or(fp.partial) } else { fp.partial };
+                        by_hash.entry(key).or_default().push(path);
+                    }
+                    Err(e) => eprintln!("Failed to fingerprint {}: {}", path.display(), e),
+                }
+            }
+
+            for (fingerprint, paths) in by_hash {
+                if paths.len() < 2 { continue; }
+                let files = paths.into_iter().map(|p| {
+                    let path = p.to_string_lossy().to_string();
+                    DuplicateFile {
+                        modified_time: fs::metadata(&p).ok().and_then(|m| m.modified().ok()).map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                        linked_movie_ids: linked.get(&paths::normalize(&path)).cloned().unwrap_or_default(),
+                        path,
+                    }
+                }).collect();
+                groups.push(DuplicateGroup { fingerprint, size, files });
+            }
+        }
+
+        // Largest wasted space first
+        groups.sort_by_key(|g| std::cmp::Reverse(g.size * g.files.len() as u64));
+        groups
+    }).await.map_err(|e| e.to_string())
+}
+
+/// Below this a best-guess movie is more noise than help.
+const ORPHAN_SUGGESTION_MIN: f64 = 0.6;
+
+/// Files in the monitored folders that no movie links, either as a material
+/// or as its local video, grouped by folder and type. Each file carries the
+/// movie the title matcher thinks it belongs to, so it can be bulk-assigned.
+#[tauri::command]
+pub async fn find_orphan_files(state: State<'_, Database>) -> Result<Vec<OrphanGroup>, String> {
+    let config = state.get_config();
+    let folders = config.monitored_folders();
+    let filter = ScanFilter::from_config(&config);
+    let movies = state.get_movies();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut linked = HashSet::new();
+        for movie in &movies {
+            linked.extend(movie.materials.iter().map(|m| paths::normalize(&m.path)));
+            if let Some(p) = &movie.local_video_path {
+                linked.insert(paths::normalize(p));
+            }
+        }
+
+        let targets: Vec<(&Movie, MatchTarget)> = movies.iter().map(|m| {
+            let titles: Vec<String> = [Some(m.title.clone()), m.original_title.clone()].into_iter().flatten()
+                .chain(m.aliases.iter().flatten().cloned())
+                .collect();
+            (m, MatchTarget::new(&titles, m.release_date.as_deref().and_then(release::year_of)))
+        }).collect();
+
+        let mut groups: Vec<OrphanGroup> = Vec::new();
+        let mut seen = HashSet::new();
+        for folder in &folders {
+            let root = Path::new(folder);
+            if !root.exists() { continue; }
+            for entry in filter.walk(root) {
+                if !entry.file_type().is_file() { continue; }
+                let path = entry.path();
+                let Some(file_type) = file_type_of(path) else { continue };
+                let key = paths::normalize(&path.to_string_lossy());
+                if linked.contains(&key) || !seen.insert(key) { continue; }
+
+                let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                let dirs: Vec<String> = path.strip_prefix(root).ok()
+                    .and_then(|rel| rel.parent())
+                    .map(|parent| parent.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect())
+                    .unwrap_or_default();
+
+                let prepared = PreparedPath::new(&name, &dirs);
+                let mut best: Option<(f64, &(&Movie, MatchTarget))> = None;
+                for candidate in &targets {
+                    let score = score_prepared(&prepared, &candidate.1);
+                    if score >= ORPHAN_SUGGESTION_MIN && best.is_none_or(|(b, _)| score > b) {
+                        best = Some((score, candidate));
+                    }
+                }
+                // Only the winner is explained, the detail is costly for every movie
+                let suggestion = best.map(|(score, (movie, target))| OrphanSuggestion {
+                    movie_id: movie.id,
+                    title: movie.title.clone(),
+                    similarity: to_percent(score),
+                    match_detail: explain_prepared(&prepared, target).1,
+                });
+
+                let metadata = entry.metadata().ok();
+                let file = OrphanFile {
+                    path: path.to_string_lossy().to_string(),
+                    name,
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified_time: metadata.and_then(|m| m.modified().ok()).map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                    suggestion,
+                };
+
+                let parent = path.parent().unwrap_or(root).to_string_lossy().to_string();
+                match groups.iter_mut().find(|g| g.folder == parent && g.file_type == file_type) {
+                    Some(group) => group.files.push(file),
+                    None => groups.push(OrphanGroup { folder: parent, file_type: file_type.to_string(), files: vec![file] }),
+                }
+            }
+        }
+
+        groups.sort_by(|a, b| a.folder.cmp(&b.folder).then_with(|| a.file_type.cmp(&b.file_type)));
+        groups
+    }).await.map_err(|e| e.to_string())
+}
+
+pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, year: Option<u16>, threshold: f64, filter: &ScanFilter) -> Vec<MatchedFile> {
+    let video_extensions = VIDEO_EXTENSIONS;
+    let audio_extensions = AUDIO_EXTENSIONS;
+    let image_extensions = IMAGE_EXTENSIONS;
+    let doc_extensions = DOC_EXTENSIONS;
     let mut local_results = Vec::new();
+    let target = titles.as_ref().map(|t| MatchTarget::new(t, year));
 
     for path_str in &paths {
         let path = Path::new(path_str);
         if !path.exists() { continue; }
         
-        let walker = WalkDir::new(path).into_iter().filter_entry(|e| {
-            let name = e.file_name().to_string_lossy();
-            // Filter out hidden files/dirs and specific system directories
-            if name.starts_with('.') || 
-               name == "System Volume Information" || 
-               name == "$RECYCLE.BIN" || 
-               name == "node_modules" || 
-               name == ".git" {
-                return false;
-            }
-            true
-        });
-
-        for entry in walker.filter_map(|e| e.ok()) {
+        // Hidden/system folders, .zqignore rules and the configured excludes/limits
+        for entry in filter.walk(path) {
             if entry.file_type().is_file() {
                 let path = entry.path();
                 if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -182,43 +489,24 @@ pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, thre
                         };
                         let file_name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
                         
-                        let mut similarity = 0.0;
-                        
-                        if let Some(titles) = &titles {
-                            for title in titles {
-                                let title_lower = title.to_lowercase();
-                                let file_name_lower = file_name.to_lowercase();
-                                let mut sim = if file_name_lower == title_lower {
-                                    1.0
-                                } else if file_name_lower.contains(&title_lower) || title_lower.contains(&file_name_lower) {
-                                    0.95
-                                } else {
-                                    strsim::jaro_winkler(&file_name_lower, &title_lower)
-                                };
-
-                                if sim < 1.0 {
-                                    if let Ok(rel_path) = path.strip_prefix(Path::new(path_str)) {
-                                        if let Some(parent) = rel_path.parent() {
-                                            for comp in parent.components() {
-                                                let dir_name = comp.as_os_str().to_string_lossy().to_string();
-                                                let dir_name_lower = dir_name.to_lowercase();
-                                                let dir_sim = if dir_name_lower == title_lower {
-                                                    1.0
-                                                } else if dir_name_lower.contains(&title_lower) || title_lower.contains(&dir_name_lower) {
-                                                    0.95
-                                                } else {
-                                                    strsim::jaro_winkler(&dir_name_lower, &title_lower)
-                                                };
-                                                if dir_sim > sim { sim = dir_sim; }
-                                            }
-                                        }
-                                    }
-                                }
-                                if sim > similarity { similarity = sim; }
+                        let (similarity, match_detail) = match &target {
+                            Some(target) => {
+                                let dirs: Vec<String> = path
+                                    .strip_prefix(Path::new(path_str))
+                                    .ok()
+                                    .and_then(|rel| rel.parent())
+                                    .map(|parent| {
+                                        parent
+                                            .components()
+                                            .map(|c| c.as_os_str().to_string_lossy().to_string())
+                                            .collect()
+                                    })
+                                    .unwrap_or_default();
+                                let (score, detail) = explain_path(&file_name, &dirs, target);
+                                (score, Some(detail))
                             }
-                        } else {
-                            similarity = 1.0; 
-                        }
+                            None => (1.0, None),
+                        };
 
                         if similarity >= threshold {
                             let path_str_lossy = path.to_string_lossy();
@@ -229,10 +517,11 @@ pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, thre
                                 name: file_name,
                                 path: path_str_lossy.to_string(),
                                 size: entry.metadata().map(|m| m.len().to_string()).unwrap_or_else(|_| "0".to_string()),
-                                similarity: (similarity * 100.0) as u8,
+                                similarity: to_percent(similarity),
                                 file_type: file_type.to_string(),
                                 category,
                                 modified_time: entry.metadata().ok().and_then(|m| m.modified().ok()).map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                                match_detail,
                             });
                         }
                     }
@@ -270,26 +559,13 @@ pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, thre
                  for title in titles {
                      println!("[SCAN DEBUG] USN searching for title: '{}' on volume '{}'", title, volume_path);
                      // Try USN search for this title on this volume
-                     match search_usn_internal(&volume_path, title) {
+                     match search::search(None, Path::new(&volume_path), title, SEARCH_LIMIT) {
                         Ok(usn_results) => {
                             println!("[SCAN DEBUG] USN found {} raw results for '{}' on '{}'", usn_results.len(), title, volume_path);
                             for full_path_str in usn_results {
                                 // Filter: Must be within one of the requested paths
                                     // Since USN search is volume-wide, we must filter results to be inside the requested folders
-                                    let is_in_scope = paths.iter().any(|scope| {
-                                        let scope_normalized = scope.replace("\\", "/").to_lowercase();
-                                        let path_normalized = full_path_str.replace("\\", "/").to_lowercase();
-                                        
-                                        // Handle trailing slashes in scope to ensure accurate prefix matching
-                                        let scope_clean = scope_normalized.trim_end_matches('/');
-                                        
-                                        let in_scope = path_normalized.starts_with(scope_clean);
-                                        
-                                        if !in_scope {
-                                            // println!("[SCAN DEBUG] Result '{}' not in scope '{}' (norm: {} vs {})", full_path_str, scope, path_normalized, scope_clean);
-                                        }
-                                        in_scope
-                                    });
+                                    let is_in_scope = paths.iter().any(|scope| crate::paths::is_within(&full_path_str, scope));
                                 
                                 if !is_in_scope {
                                 println!("[SCAN DEBUG] Ignored result (out of scope): {}", full_path_str);
@@ -333,7 +609,7 @@ pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, thre
     
                                     if file_type != "unknown" {
                                             // Check if we already have this file in results to avoid duplicates
-                                            let exists = local_results.iter().any(|r| r.path == full_path_str);
+                                            let exists = local_results.iter().any(|r| crate::paths::same_path(&r.path, &full_path_str));
                                             if !exists {
                                                 println!("[SCAN DEBUG] Added match: {}", full_path_str);
                                                 local_results.push(MatchedFile {
@@ -345,6 +621,7 @@ pub fn scan_paths_internal(paths: Vec<String>, titles: Option<Vec<String>>, thre
                                                     file_type: file_type.to_string(),
                                                     category: Some("USN Result".to_string()),
                                                 modified_time: fs::metadata(path).ok().and_then(|m| m.modified().ok()).map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                                                match_detail: None,
                                             });
                                             } else {
                                                 println!("[SCAN DEBUG] Duplicate skipped: {}", full_path_str);
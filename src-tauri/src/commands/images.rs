@@ -0,0 +1,154 @@
+use tauri::State;
+use crate::db::Database;
+use crate::images;
+use crate::models::tmdb::TmdbImage;
+use crate::models::{ImageDownloadOptions, ImageKind, Movie, MovieImage};
+use crate::tmdb::{image_url, TmdbClient};
+
+/// Remote URLs of the images to download for one kind, best voted first.
+async fn tmdb_urls(tmdb: &TmdbClient, movie: &Movie, tmdb_id: u64, kind: ImageKind, options: &ImageDownloadOptions) -> Result<Vec<String>, String> {
+    let size = options.size.as_str();
+    let is_tv = movie.category.as_deref() == Some("tv");
+    let paths: Vec<String> = match kind {
+        ImageKind::Still if is_tv => {
+            let season = tmdb.season(tmdb_id, options.season.unwrap_or(1)).await?;
+            season.episodes.into_iter().filter_map(|e| e.still_path).collect()
+        }
+        ImageKind::Poster | ImageKind::Backdrop | ImageKind::Logo => {
+            let found = tmdb.images(tmdb_id, if is_tv { "tv" } else { "movie" }).await?;
+            let mut list: Vec<TmdbImage> = match kind {
+                ImageKind::Poster => found.posters,
+                ImageKind::Backdrop => found.backdrops,
+                _ => found.logos,
+            };
+            list.sort_by(|a, b| b.vote_average.unwrap_or(0.0).partial_cmp(&a.vote_average.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal));
+            list.into_iter().map(|i| i.file_path).collect()
+        }
+        _ => Vec::new(),
+    };
+    Ok(paths.iter().take(options.limit).map(|p| image_url(size, p)).collect())
+}
+
+/// Point the movie's poster, backdrop or logo at a stored image. Only fills
+/// fields that are empty or still remote unless `primary`.
+fn attach(movie: &mut Movie, kind: ImageKind, path: &str, primary: bool) {
+    let is_remote = |p: &Option<String>| p.as_deref().is_none_or(|p| p.starts_with("http"));
+    let field = match kind {
+        ImageKind::Poster => {
+            if !movie.posters.iter().any(|p| p == path) {
+                movie.posters.push(path.to_string());
+            }
+            &mut movie.poster_path
+        }
+        ImageKind::Backdrop => &mut movie.backdrop_path,
+        ImageKind::Logo => &mut movie.logo_path,
+        ImageKind::Still | ImageKind::Profile => return,
+    };
+    if primary || is_remote(field) {
+        *field = Some(path.to_string());
+    }
+}
+
+/// Download posters, backdrops, logos and (TV) episode stills of a movie
+/// from TMDB into the image store. The best voted image of each kind
+/// becomes the movie's own unless it already has a local one.
+#[tauri::command]
+pub async fn download_movie_images(
+    state: State<'_, Database>,
+    tmdb: State<'_, TmdbClient>,
+    movie_id: u64,
+    options: Option<ImageDownloadOptions>,
+) -> Result<Vec<MovieImage>, String> {
+    let options = options.unwrap_or_default();
+    let movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    let tmdb_id = movie.tmdb_id.ok_or("Movie has no TMDB ID")?;
+
+    let mut saved = Vec::new();
+    for &kind in &options.kinds {
+        for url in tmdb_urls(&tmdb, &movie, tmdb_id, kind, &options).await? {
+            match images::fetch(&state, &url).await {
+                Ok(image) => {
+                    state.add_image_ref(movie_id, &image.hash, kind).map_err(|e| e.to_string())?;
+                    saved.push(MovieImage { kind, image });
+                }
+                Err(e) => eprintln!("[Images] Failed to download {}: {}", url, e),
+            }
+        }
+    }
+
+    // Downloads take a while, attach to a fresh copy
+    let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    for saved in &saved {
+        attach(&mut movie, saved.kind, &saved.image.path, false);
+    }
+    state.update_movie(movie).map_err(|e| e.to_string())?;
+    Ok(saved)
+}
+
+/// Download one image (e.g. picked from `get_metadata_images`) for a movie;
+/// with `primary` it replaces the movie's current one of that kind.
+#[tauri::command]
+pub async fn save_movie_image(state: State<'_, Database>, movie_id: u64, url: String, kind: ImageKind, primary: Option<bool>) -> Result<Movie, String> {
+    let image = images::fetch(&state, &url).await?;
+    state.add_image_ref(movie_id, &image.hash, kind).map_err(|e| e.to_string())?;
+    let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    attach(&mut movie, kind, &image.path, primary.unwrap_or(false));
+    state.update_movie(movie.clone()).map_err(|e| e.to_string())?;
+    Ok(movie)
+}
+
+#[tauri::command]
+pub fn get_movie_images(state: State<Database>, movie_id: u64) -> Result<Vec<MovieImage>, String> {
+    state.get_movie_images(movie_id).map_err(|e| e.to_string())
+}
+
+/// Make one of the movie's stored images its poster, backdrop or logo.
+#[tauri::command]
+pub fn set_primary_image(state: State<Database>, movie_id: u64, kind: ImageKind, path: String) -> Result<Movie, String> {
+    let known = state.get_movie_images(movie_id).map_err(|e| e.to_string())?
+        .iter()
+        .any(|i| i.kind == kind && i.image.path == path);
+    if !known {
+        return Err("Image not found".to_string());
+    }
+    let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    attach(&mut movie, kind, &path, true);
+    state.update_movie(movie.clone()).map_err(|e| e.to_string())?;
+    Ok(movie)
+}
+
+/// Remove an image from a movie. Fields showing it fall back to another
+/// stored image of the same kind; the file goes once no movie uses it.
+#[tauri::command]
+pub fn remove_movie_image(state: State<Database>, movie_id: u64, hash: String) -> Result<Movie, String> {
+    let images = state.get_movie_images(movie_id).map_err(|e| e.to_string())?;
+    let path = images.iter().find(|i| i.image.hash == hash).map(|i| i.image.path.clone()).ok_or("Image not found")?;
+    state.remove_image_refs(movie_id, Some(&hash)).map_err(|e| e.to_string())?;
+
+    let remaining = |kind: ImageKind| images.iter()
+        .find(|i| i.kind == kind && i.image.hash != hash)
+        .map(|i| i.image.path.clone());
+    let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    movie.posters.retain(|p| *p != path);
+    for (kind, field) in [
+        (ImageKind::Poster, &mut movie.poster_path),
+        (ImageKind::Backdrop, &mut movie.backdrop_path),
+        (ImageKind::Logo, &mut movie.logo_path),
+    ] {
+        if field.as_deref() == Some(path.as_str()) {
+            *field = remaining(kind);
+        }
+    }
+    state.update_movie(movie.clone()).map_err(|e| e.to_string())?;
+    spawn_image_gc(state.inner().clone());
+    Ok(movie)
+}
+
+/// Delete stored images no movie uses any more, off the command thread.
+pub fn spawn_image_gc(db: Database) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = images::collect_garbage(&db) {
+            eprintln!("[Images] Garbage collection failed: {}", e);
+        }
+    });
+}
@@ -4,6 +4,8 @@ use std::fs;
 use tauri::{State, AppHandle, Emitter};
 use tauri_plugin_shell::ShellExt;
 use crate::db::Database;
+use crate::models::MaterialPreview;
+use crate::preview;
 use regex::Regex;
 use serde_json;
 use serde::{Serialize, Deserialize};
@@ -82,6 +84,22 @@ pub async fn check_ffmpeg(app: AppHandle, state: State<'_, Database>) -> Result<
         .unwrap_or(false))
 }
 
+/// Previews of a material (thumbnail and sprite, waveform, downscaled image
+/// or text snippet), generated on first request and cached under the data root.
+#[tauri::command]
+pub async fn get_material_preview(app: AppHandle, state: State<'_, Database>, path: String) -> Result<MaterialPreview, String> {
+    preview::get_or_create(&app, &state, &path).await
+}
+
+/// Remove previews of unlinked or changed files. Returns how many files were deleted.
+#[tauri::command]
+pub async fn clean_preview_cache(state: State<'_, Database>) -> Result<usize, String> {
+    let db = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || preview::collect_garbage(&db))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 #[tauri::command]
 pub async fn get_media_info(app: AppHandle, state: State<'_, Database>, path: String) -> Result<String, String> {
     let (success, stdout, stderr) = run_media_tool(&app, &state, "ffprobe", &["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams", &path])
@@ -1,4 +1,3 @@
-pub mod common;
 pub mod files;
 pub mod media;
 pub mod movie;
@@ -9,6 +8,12 @@ pub mod usn;
 pub mod audio;
 pub mod ai;
 pub mod whisper;
+pub mod scripts;
+pub mod providers;
+pub mod title_list;
+pub mod images;
+pub mod people;
+pub mod calendar;
 
 pub use files::*;
 pub use media::*;
@@ -20,3 +25,9 @@ pub use usn::*;
 pub use audio::*;
 pub use ai::*;
 pub use whisper::*;
+pub use scripts::*;
+pub use providers::*;
+pub use title_list::*;
+pub use images::*;
+pub use people::*;
+pub use calendar::*;
@@ -1,8 +1,23 @@
-use tauri::State;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, State};
 use crate::db::Database;
-use crate::models::{Movie, Material};
-use crate::commands::common::download_and_save_image;
-use crate::commands::files::scan_paths_internal;
+use crate::models::{AutoMatchMovieResult, AutoMatchProgress, AutoMatchReport, ExportFormat, ImageKind, MatchedFile, Movie, Material, MaterialAssignment, MaterialFilter, MaterialListItem, MovieFilter};
+use crate::commands::files::{file_type_of, scan_paths_internal};
+use crate::commands::images::spawn_image_gc;
+use crate::matching::release::year_of;
+use crate::matching::{explain_prepared, score_prepared, to_percent, MatchTarget, PreparedPath};
+use crate::jobs::JobRegistry;
+use crate::paths;
+use crate::ignore::ScanFilter;
+use crate::category::CategoryRules;
+use crate::images;
+use crate::fingerprint::{ensure_material_fingerprint, fingerprint_cached, merge_material, MergeOutcome};
+use crate::library;
+use crate::metadata;
+use crate::preview;
+use crate::providers::{changed_fields, fill_missing, tmdb_metadata};
+use crate::tmdb::TmdbClient;
 
 #[tauri::command]
 pub fn get_movies(state: State<Database>) -> Result<Vec<Movie>, String> {
@@ -10,26 +25,42 @@ pub fn get_movies(state: State<Database>) -> Result<Vec<Movie>, String> {
 }
 
 #[tauri::command]
-pub async fn add_movie(state: State<'_, Database>, movie: Movie) -> Result<Movie, String> {
+pub fn filter_movies(state: State<Database>, filter: MovieFilter) -> Result<Vec<Movie>, String> {
+    Ok(library::filter_movies(state.get_movies(), &filter))
+}
+
+/// Write the library (or the movies matching `filter`) to `path` as CSV or
+/// JSON. Returns how many movies were written.
+#[tauri::command]
+pub fn export_movies(state: State<Database>, path: String, format: Option<ExportFormat>, filter: Option<MovieFilter>) -> Result<usize, String> {
+    let movies = library::filter_movies(state.get_movies(), &filter.unwrap_or_default());
+    let content = library::export(&movies, format.unwrap_or_default())?;
+    std::fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(movies.len())
+}
+
+#[tauri::command]
+pub async fn add_movie(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, movie: Movie) -> Result<Movie, String> {
     // 1. Add movie to database FIRST to get an ID and return immediately
     // This stores remote URLs initially, which frontend can display
     let added_movie = state.add_movie(movie).map_err(|e| e.to_string())?;
 
-    // 2. Clone state (Database) for background task
-    let db = state.inner().clone();
-    
-    // 3. Clone movie data needed for download
-    let mut movie_to_process = added_movie.clone();
+    // 2. Download the images and fetch the details in the background
+    spawn_post_add(state.inner().clone(), tmdb.inner().clone(), added_movie.clone());
 
-    // 4. Spawn background task
+    Ok(added_movie)
+}
+
+/// Replace the remote images of a movie that was just added by local
+/// copies, and fill in what search results don't carry from TMDB details.
+pub fn spawn_post_add(db: Database, tmdb: TmdbClient, mut movie_to_process: Movie) {
     tauri::async_runtime::spawn(async move {
-        let config = db.get_config();
-        let db_root = db.get_root_dir();
+        let movie_id = movie_to_process.id;
         let mut updated = false;
 
         // Poster
         if let Some(url) = &movie_to_process.poster_path {
-             if let Some(local) = download_and_save_image(url, "posters", &config, &db_root).await {
+             if let Some(local) = images::save_for_movie(&db, movie_id, url, ImageKind::Poster).await {
                  movie_to_process.poster_path = Some(local);
                  updated = true;
              }
@@ -38,7 +69,7 @@ pub async fn add_movie(state: State<'_, Database>, movie: Movie) -> Result<Movie
         // Actors
         for actor in &mut movie_to_process.actors {
             if let Some(url) = &actor.profile_path {
-                if let Some(local) = download_and_save_image(url, "actors", &config, &db_root).await {
+                if let Some(local) = images::save_for_movie(&db, movie_id, url, ImageKind::Profile).await {
                     actor.profile_path = Some(local);
                     updated = true;
                 }
@@ -48,7 +79,7 @@ pub async fn add_movie(state: State<'_, Database>, movie: Movie) -> Result<Movie
         // Directors
         for director in &mut movie_to_process.directors {
              if let Some(url) = &director.profile_path {
-                if let Some(local) = download_and_save_image(url, "directors", &config, &db_root).await {
+                if let Some(local) = images::save_for_movie(&db, movie_id, url, ImageKind::Profile).await {
                     director.profile_path = Some(local);
                     updated = true;
                 }
@@ -71,43 +102,58 @@ pub async fn add_movie(state: State<'_, Database>, movie: Movie) -> Result<Movie
                 println!("Background image download completed for movie: {}", movie_to_process.title);
             }
         }
+
+        // Alternative and translated titles help matching files named in other
+        // regions; search results also lack external IDs, runtime and the like
+        if let Some(tmdb_id) = movie_to_process.tmdb_id {
+            let media_type = movie_to_process.category.clone().filter(|c| c == "tv").unwrap_or_else(|| "movie".to_string());
+            match tmdb.details(tmdb_id, &media_type).await {
+                Ok(detail) => {
+                    if let Some(mut movie) = db.get_movie(movie_to_process.id) {
+                        let meta = tmdb_metadata(tmdb_id, &media_type, detail);
+                        if fill_missing(&mut movie, &meta) {
+                            if let Err(e) = db.update_movie(movie) {
+                                eprintln!("Failed to save TMDB details for movie {}: {}", movie_to_process.id, e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => println!("Could not fetch TMDB details for {}: {}", movie_to_process.title, e),
+            }
+        }
     });
+}
 
-    Ok(added_movie)
+/// Auto-match only links exact title matches, fuzzy ones would be false positives.
+const AUTO_MATCH_THRESHOLD: f64 = 1.0;
+/// Files handled between two `auto-match-progress` events.
+const PROGRESS_EVERY: usize = 50;
+
+fn material_from_match(file: MatchedFile, rules: &CategoryRules) -> Material {
+    let resolution = rules.resolve(&file.path, &file.file_type);
+    Material {
+        id: file.key,
+        name: file.name,
+        path: file.path,
+        size: file.size,
+        file_type: file.file_type,
+        category: resolution.category,
+        add_time: chrono::Utc::now().to_rfc3339(),
+        modified_time: file.modified_time,
+        fingerprint: None,
+        tags: resolution.tags,
+        metadata: None,
+    }
 }
 
 #[tauri::command]
-pub async fn auto_match_movie(state: State<'_, Database>, movie_id: u64) -> Result<(), String> {
+pub async fn auto_match_movie(app: AppHandle, state: State<'_, Database>, movie_id: u64) -> Result<(), String> {
     let movie = state.get_movie(movie_id).ok_or("Movie not found")?;
 
     // Perform auto-match
     let config = state.get_config();
-    let mut paths = config.default_monitor_folders.clone();
-    
-    // Track which folder belongs to which category for later association
-    // Use normalized paths (lowercase, backslashes) for robust matching
-    let mut folder_map = std::collections::HashMap::new();
-    
-    let normalize_path = |p: &str| -> String {
-        let s = p.replace("/", "\\").to_lowercase();
-        if !s.ends_with('\\') {
-            s + "\\"
-        } else {
-            s
-        }
-    };
-
-    for p in &config.default_monitor_folders {
-        folder_map.insert(normalize_path(p), None::<String>);
-    }
-    for p in &config.monitor_folders_source {
-        paths.push(p.clone());
-        folder_map.insert(normalize_path(p), Some("source".to_string()));
-    }
-    for p in &config.monitor_folders_finished {
-        paths.push(p.clone());
-        folder_map.insert(normalize_path(p), Some("finished".to_string()));
-    }
+    let paths = config.monitored_folders();
+    let rules = CategoryRules::from_config(&config);
 
     if paths.is_empty() {
         return Ok(());
@@ -118,48 +164,26 @@ pub async fn auto_match_movie(state: State<'_, Database>, movie_id: u64) -> Resu
         movie.original_title.clone()
     ].into_iter().flatten().filter(|t| !t.trim().is_empty()).collect::<Vec<_>>();
 
-    // Use shared scan logic (which includes USN search)
-    // For auto-match, we want strict matching (essentially 100%) to avoid false positives
-    let threshold = 1.0;
+    // Walk the monitored folders with the shared scan logic
+    let threshold = AUTO_MATCH_THRESHOLD;
+    let year = movie.release_date.as_deref().and_then(year_of);
+    let filter = ScanFilter::from_config(&config);
+    let db = state.inner().clone();
     println!("[AUTO MATCH] Starting scan for movie_id: {}", movie_id);
     let scan_results = tauri::async_runtime::spawn_blocking(move || {
-        let matched_files = scan_paths_internal(paths, Some(titles), threshold);
+        let matched_files = scan_paths_internal(paths, Some(titles), year, threshold, &filter);
         println!("[AUTO MATCH] scan_paths_internal returned {} results", matched_files.len());
         
         let mut materials = Vec::new();
         for file in matched_files {
-            // Determine category based on path prefix
-            let mut category = None;
-            let file_path_norm = file.path.replace("/", "\\").to_lowercase();
-            for (folder, cat) in &folder_map {
-                if file_path_norm.starts_with(folder) {
-                    category = cat.clone();
-                    break; 
-                }
-            }
-            
-            // If it's a USN result (no full path or category already set), keep existing category
-            if file.category.is_some() {
-                category = file.category;
-            }
-
-            // Skip USN results if they don't have a full path (i.e. path == name)
-            // Unless we implement path reconstruction later.
-            // For now, if path doesn't look like an absolute path, we can't really use it for materials
+            // Materials are located by their absolute path
             if !std::path::Path::new(&file.path).is_absolute() {
                  continue;
             }
 
-            materials.push(Material {
-                id: file.key,
-                name: file.name,
-                path: file.path,
-                size: file.size,
-                file_type: file.file_type,
-                category: category,
-                add_time: chrono::Utc::now().to_rfc3339(),
-                modified_time: file.modified_time,
-            });
+            let mut material = material_from_match(file, &rules);
+            ensure_material_fingerprint(&db, &mut material);
+            materials.push(material);
         }
         materials
     }).await.map_err(|e| e.to_string())?;
@@ -167,24 +191,199 @@ pub async fn auto_match_movie(state: State<'_, Database>, movie_id: u64) -> Resu
     if !scan_results.is_empty() {
         // Update the movie in DB with new materials ONLY
         state.add_materials(movie_id, scan_results).map_err(|e| e.to_string())?;
+        metadata::spawn_enrich(app, state.inner().clone(), vec![movie_id]);
     }
 
     Ok(())
 }
 
+/// Match every file in the monitored folders against every movie in one pass
+/// and link the files that reach the auto-match threshold. With `dry_run`
+/// the proposed links are only reported. Progress is sent as
+/// `auto-match-progress` events; pass the job id to `cancel_job` to stop.
+#[tauri::command]
+pub async fn auto_match_all(
+    app: AppHandle,
+    state: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
+    dry_run: Option<bool>,
+    job_id: Option<String>,
+) -> Result<AutoMatchReport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let config = state.get_config();
+    let folders = config.monitored_folders();
+    let filter = ScanFilter::from_config(&config);
+    let rules = CategoryRules::from_config(&config);
+    let db = state.inner().clone();
+    let registry = jobs.inner().clone();
+    let (job_id, cancel) = registry.start(job_id);
+    let enrich_app = app.clone();
+    let enrich_db = db.clone();
+
+    let id = job_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let progress = |phase: &str, processed: usize, total: usize, current: Option<String>| {
+            let event = AutoMatchProgress { job_id: id.clone(), phase: phase.to_string(), processed, total, current };
+            app.emit("auto-match-progress", event).unwrap_or(());
+        };
+        let mut report = AutoMatchReport {
+            job_id: id.clone(),
+            dry_run,
+            cancelled: false,
+            files_scanned: 0,
+            movies: Vec::new(),
+        };
+
+        // 1. Walk the monitored folders once
+        let mut files: Vec<(MatchedFile, PreparedPath)> = Vec::new();
+        let mut seen = HashSet::new();
+        for folder in &folders {
+            let root = Path::new(folder);
+            if !root.exists() { continue; }
+            for entry in filter.walk(root) {
+                if cancel.is_cancelled() {
+                    report.cancelled = true;
+                    return report;
+                }
+                if !entry.file_type().is_file() { continue; }
+                let path = entry.path();
+                let Some(file_type) = file_type_of(path) else { continue };
+                let path_str = path.to_string_lossy().to_string();
+                if !seen.insert(paths::normalize(&path_str)) { continue; }
+
+                let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                let dirs: Vec<String> = path.strip_prefix(root).ok()
+                    .and_then(|rel| rel.parent())
+                    .map(|parent| parent.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect())
+                    .unwrap_or_default();
+                let metadata = entry.metadata().ok();
+                let prepared = PreparedPath::new(&name, &dirs);
+                files.push((MatchedFile {
+                    key: path_str.clone(),
+                    name,
+                    path: path_str,
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or(0).to_string(),
+                    similarity: 0,
+                    file_type: file_type.to_string(),
+                    category: None,
+                    modified_time: metadata.and_then(|m| m.modified().ok()).map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                    match_detail: None,
+                }, prepared));
+
+                if files.len().is_multiple_of(PROGRESS_EVERY) {
+                    progress("scanning", files.len(), 0, Some(path.to_string_lossy().to_string()));
+                }
+            }
+        }
+        report.files_scanned = files.len();
+
+        // 2. Score every file against the titles and aliases of every movie
+        let movies = db.get_movies();
+        let targets: Vec<MatchTarget> = movies.iter().map(|m| {
+            let mut titles = vec![m.title.clone()];
+            titles.extend(m.original_title.clone());
+            titles.extend(m.aliases.clone().unwrap_or_default());
+            MatchTarget::new(&titles, m.release_date.as_deref().and_then(year_of))
+        }).collect();
+
+        let mut proposals: Vec<Vec<MatchedFile>> = vec![Vec::new(); movies.len()];
+        for (i, (file, prepared)) in files.iter().enumerate() {
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                return report;
+            }
+            for (m, movie) in movies.iter().enumerate() {
+                let score = score_prepared(prepared, &targets[m]);
+                if score < AUTO_MATCH_THRESHOLD || movie.materials.iter().any(|mat| paths::same_path(&mat.path, &file.path)) {
+                    continue;
+                }
+                let mut matched = file.clone();
+                matched.similarity = to_percent(score);
+                matched.category = rules.resolve(&file.path, &file.file_type).category;
+                matched.match_detail = Some(explain_prepared(prepared, &targets[m]).1);
+                proposals[m].push(matched);
+            }
+            if (i + 1).is_multiple_of(PROGRESS_EVERY) || i + 1 == files.len() {
+                progress("matching", i + 1, files.len(), Some(file.path.clone()));
+            }
+        }
+
+        // 3. Link, one movie at a time so a cancel leaves whole movies done
+        let total = proposals.iter().filter(|p| !p.is_empty()).count();
+        for (movie, files) in movies.iter().zip(proposals) {
+            if files.is_empty() { continue; }
+            if cancel.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+
+            let mut linked = 0;
+            if !dry_run {
+                let materials: Vec<Material> = files.iter().map(|f| {
+                    let mut material = material_from_match(f.clone(), &rules);
+                    ensure_material_fingerprint(&db, &mut material);
+                    material
+                }).collect();
+                match db.add_materials(movie.id, materials) {
+                    Ok(()) => linked = files.len(),
+                    Err(e) => eprintln!("[AUTO MATCH] Failed to link materials for movie {}: {}", movie.id, e),
+                }
+            }
+
+            report.movies.push(AutoMatchMovieResult {
+                movie_id: movie.id,
+                title: movie.title.clone(),
+                linked,
+                files,
+            });
+            progress("linking", report.movies.len(), total, Some(movie.title.clone()));
+        }
+
+        report
+    }).await.map_err(|e| e.to_string());
+
+    registry.finish(&job_id);
+    if let Ok(report) = &result {
+        let linked = report.movies.iter().filter(|m| m.linked > 0).map(|m| m.movie_id).collect();
+        metadata::spawn_enrich(enrich_app, enrich_db, linked);
+    }
+    result
+}
+
 #[tauri::command]
 pub fn delete_movie(state: State<Database>, id: u64) -> Result<(), String> {
-    state.delete_movie(id).map_err(|e| e.to_string())
+    state.delete_movie(id).map_err(|e| e.to_string())?;
+    spawn_preview_gc(state.inner().clone());
+    spawn_image_gc(state.inner().clone());
+    Ok(())
 }
 
+/// Drop the previews of materials that were just unlinked, off the command thread.
+fn spawn_preview_gc(db: Database) {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = preview::collect_garbage(&db) {
+            eprintln!("[PREVIEW] Garbage collection failed: {}", e);
+        }
+    });
+}
+
+/// Save a movie. With `edited_by_user` (the edit dialog) the metadata
+/// fields that differ from the stored movie are locked against refreshes.
 #[tauri::command]
-pub async fn update_movie(state: State<'_, Database>, mut movie: Movie) -> Result<(), String> {
-    let config = state.get_config();
-    let db_root = state.get_root_dir();
+pub async fn update_movie(state: State<'_, Database>, mut movie: Movie, edited_by_user: Option<bool>) -> Result<(), String> {
+    let movie_id = movie.id;
+
+    // Locks are only ever lifted through `unlock_movie_fields`
+    if let Some(stored) = state.get_movie(movie.id) {
+        if edited_by_user.unwrap_or(false) {
+            movie.user_edited_fields.extend(changed_fields(&stored, &movie));
+        }
+        movie.user_edited_fields.extend(stored.user_edited_fields);
+    }
     
     // Poster
     if let Some(url) = &movie.poster_path {
-        if let Some(local) = download_and_save_image(url, "posters", &config, &db_root).await {
+        if let Some(local) = images::save_for_movie(&state, movie_id, url, ImageKind::Poster).await {
             movie.poster_path = Some(local);
         }
     }
@@ -192,7 +391,7 @@ pub async fn update_movie(state: State<'_, Database>, mut movie: Movie) -> Resul
     // Actors
     for actor in &mut movie.actors {
         if let Some(url) = &actor.profile_path {
-            if let Some(local) = download_and_save_image(url, "actors", &config, &db_root).await {
+            if let Some(local) = images::save_for_movie(&state, movie_id, url, ImageKind::Profile).await {
                 actor.profile_path = Some(local);
             }
         }
@@ -201,7 +400,7 @@ pub async fn update_movie(state: State<'_, Database>, mut movie: Movie) -> Resul
     // Directors
     for director in &mut movie.directors {
          if let Some(url) = &director.profile_path {
-            if let Some(local) = download_and_save_image(url, "directors", &config, &db_root).await {
+            if let Some(local) = images::save_for_movie(&state, movie_id, url, ImageKind::Profile).await {
                 director.profile_path = Some(local);
             }
         }
@@ -221,31 +420,11 @@ pub fn get_movie_details(state: State<Database>, id: u64) -> Result<Option<Movie
 }
 
 #[tauri::command]
-pub async fn refresh_movie_materials(state: State<'_, Database>, movie_id: u64) -> Result<Vec<Material>, String> {
+pub async fn refresh_movie_materials(app: AppHandle, state: State<'_, Database>, movie_id: u64) -> Result<Vec<Material>, String> {
     let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
 
-    // Build folder config for category detection
     let config = state.get_config();
-    let mut folder_list: Vec<(String, Option<String>)> = Vec::new();
-    let normalize_path = |p: &str| -> String {
-        let s = p.replace("/", "\\").to_lowercase();
-        if !s.ends_with('\\') {
-            s + "\\"
-        } else {
-            s
-        }
-    };
-    for p in &config.default_monitor_folders {
-        folder_list.push((normalize_path(p), None));
-    }
-    for p in &config.monitor_folders_source {
-        folder_list.push((normalize_path(p), Some("source".to_string())));
-    }
-    for p in &config.monitor_folders_finished {
-        folder_list.push((normalize_path(p), Some("finished".to_string())));
-    }
-    // Sort by length descending to match most specific folder first
-    folder_list.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    let rules = CategoryRules::from_config(&config);
 
     let paths = movie.matched_folders.clone();
     
@@ -263,78 +442,165 @@ pub async fn refresh_movie_materials(state: State<'_, Database>, movie_id: u64)
     let titles: Vec<String> = titles.into_iter().filter(|t| !t.trim().is_empty()).collect();
     
     let threshold = 0.8;
+    let year = movie.release_date.as_deref().and_then(year_of);
+    let filter = ScanFilter::from_config(&config);
+    let known_paths: HashSet<String> = movie.materials.iter().map(|m| paths::normalize(&m.path)).collect();
+    let db = state.inner().clone();
     
-    let matched_files = tauri::async_runtime::spawn_blocking(move || {
-        scan_paths_internal(paths, Some(titles), threshold)
+    let (matched_files, mut fingerprints) = tauri::async_runtime::spawn_blocking(move || {
+        let files = scan_paths_internal(paths, Some(titles), year, threshold, &filter);
+        // Fingerprint unknown files so moved/renamed materials can be recognised
+        let fingerprints: HashMap<String, String> = files.iter()
+            .filter(|f| !known_paths.contains(&paths::normalize(&f.path)))
+            .filter_map(|f| {
+                fingerprint_cached(&db, Path::new(&f.path), false)
+                    .ok()
+                    .map(|fp| (f.path.clone(), fp.partial))
+            })
+            .collect();
+        (files, fingerprints)
     }).await.map_err(|e| e.to_string())?;
 
     let mut new_materials = Vec::new();
+    let mut moved = Vec::new();
     let mut updated = false;
 
     for file in matched_files {
-        // Determine category first
-        let mut category = file.category.clone();
-        if category.is_none() {
-             let file_path_norm = file.path.replace("/", "\\").to_lowercase();
-             
-             // 1. Check against configured folders
-             for (folder, cat) in &folder_list {
-                 if file_path_norm.starts_with(folder) {
-                     category = cat.clone();
-                     break; 
-                 }
-             }
-             
-             // 2. Check for "成片" or "finished" in path components (subdirectories)
-             if category.is_none() {
-                 if file_path_norm.contains("\\成片\\") || file_path_norm.contains("\\finished\\") {
-                     category = Some("finished".to_string());
-                 }
-             }
-        }
+        let resolution = rules.resolve(&file.path, &file.file_type);
+        let category = file.category.clone().or(resolution.category);
+        let tags = resolution.tags;
 
-        if let Some(existing_mat) = movie.materials.iter_mut().find(|m| m.path == file.path) {
+        if let Some(existing_mat) = movie.materials.iter_mut().find(|m| paths::same_path(&m.path, &file.path)) {
             // Update existing material if category changed
             if existing_mat.category != category {
                 existing_mat.category = category.clone();
                 updated = true;
             }
+            if existing_mat.tags != tags {
+                existing_mat.tags = tags;
+                updated = true;
+            }
             // Update other fields if needed
             if existing_mat.size != file.size {
                 existing_mat.size = file.size.clone();
                 updated = true;
             }
         } else {
-            // Add new material
+            // Add new material, or relink an existing one that was moved/renamed
             let mat = Material {
                 id: file.key,
                 name: file.name,
+                fingerprint: fingerprints.remove(&file.path),
                 path: file.path,
                 size: file.size,
                 file_type: file.file_type,
                 category: category,
                 add_time: chrono::Utc::now().to_rfc3339(),
                 modified_time: file.modified_time,
+                tags,
+                metadata: None,
             };
-            movie.materials.push(mat.clone());
-            new_materials.push(mat);
-            updated = true;
+            let path = mat.path.clone();
+            match merge_material(&mut movie.materials, mat) {
+                MergeOutcome::Added => {
+                    new_materials.extend(movie.materials.last().cloned());
+                    updated = true;
+                }
+                MergeOutcome::Moved(material_id) => {
+                    moved.push((material_id, path));
+                    updated = true;
+                }
+                MergeOutcome::AlreadyLinked => {}
+            }
         }
     }
 
     if updated {
         state.update_movie(movie).map_err(|e| e.to_string())?;
     }
+    for (material_id, path) in moved {
+        state.move_script_text_path(movie_id, &material_id, &path).map_err(|e| e.to_string())?;
+    }
+    // Also re-probes files that changed since they were linked
+    metadata::spawn_enrich(app, state.inner().clone(), vec![movie_id]);
     
     Ok(new_materials)
 }
 
 #[tauri::command]
-pub fn add_material_to_movie(state: State<Database>, movie_id: u64, material: Material) -> Result<(), String> {
-    state.add_material(movie_id, material).map_err(|e| e.to_string())
+pub fn add_material_to_movie(app: AppHandle, state: State<Database>, movie_id: u64, mut material: Material) -> Result<(), String> {
+    ensure_material_fingerprint(&state, &mut material);
+    state.add_material(movie_id, material).map_err(|e| e.to_string())?;
+    metadata::spawn_enrich(app, state.inner().clone(), vec![movie_id]);
+    Ok(())
+}
+
+/// Link many files at once, e.g. the accepted suggestions of the orphan report.
+/// Returns how many files were linked.
+#[tauri::command]
+pub async fn assign_materials(app: AppHandle, state: State<'_, Database>, assignments: Vec<MaterialAssignment>) -> Result<usize, String> {
+    let rules = CategoryRules::from_config(&state.get_config());
+    let db = state.inner().clone();
+    let movie_ids: Vec<u64> = assignments.iter().map(|a| a.movie_id).collect();
+
+    let linked = tauri::async_runtime::spawn_blocking(move || {
+        let mut linked = 0;
+        for assignment in assignments {
+            let mut materials = Vec::new();
+            for path_str in assignment.paths {
+                let path = Path::new(&path_str);
+                let metadata = std::fs::metadata(path).map_err(|e| format!("{}: {}", path_str, e))?;
+                let file_type = file_type_of(path).unwrap_or("other");
+                let resolution = rules.resolve(&path_str, file_type);
+                let mut material = Material {
+                    id: path_str.clone(),
+                    name: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                    size: metadata.len().to_string(),
+                    file_type: file_type.to_string(),
+                    category: resolution.category,
+                    add_time: chrono::Utc::now().to_rfc3339(),
+                    modified_time: metadata.modified().ok().map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+                    fingerprint: None,
+                    tags: resolution.tags,
+                    metadata: None,
+                    path: path_str,
+                };
+                ensure_material_fingerprint(&db, &mut material);
+                materials.push(material);
+            }
+            linked += materials.len();
+            db.add_materials(assignment.movie_id, materials).map_err(|e| e.to_string())?;
+        }
+        Ok::<usize, String>(linked)
+    }).await.map_err(|e| e.to_string())??;
+
+    metadata::spawn_enrich(app, state.inner().clone(), movie_ids);
+    Ok(linked)
 }
 
 #[tauri::command]
 pub fn remove_material_from_movie(state: State<Database>, movie_id: u64, material_id: String) -> Result<(), String> {
-    state.remove_material(movie_id, material_id).map_err(|e| e.to_string())
+    state.remove_material(movie_id, material_id).map_err(|e| e.to_string())?;
+    spawn_preview_gc(state.inner().clone());
+    Ok(())
+}
+
+/// Probe the materials of one movie, or of every movie without `movie_id`.
+/// Only missing or outdated metadata unless `force`. Returns how many were probed.
+#[tauri::command]
+pub async fn refresh_material_metadata(app: AppHandle, state: State<'_, Database>, movie_id: Option<u64>, force: Option<bool>) -> Result<usize, String> {
+    let ids = match movie_id {
+        Some(id) => vec![id],
+        None => state.get_movies().into_iter().map(|m| m.id).collect(),
+    };
+    let mut probed = 0;
+    for id in ids {
+        probed += metadata::enrich_movie(&app, &state, id, force.unwrap_or(false)).await?;
+    }
+    Ok(probed)
+}
+
+#[tauri::command]
+pub fn filter_materials(state: State<Database>, filter: MaterialFilter) -> Result<Vec<MaterialListItem>, String> {
+    Ok(metadata::filter_materials(state.get_movies(), &filter))
 }
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use tauri::State;
+use crate::db::Database;
+use crate::models::tmdb::TmdbPerson;
+use crate::models::{FilmographyEntry, LibraryPerson, PersonCredit, PersonFilmography};
+use crate::tmdb::TmdbClient;
+
+const DEFAULT_PEOPLE_LIMIT: usize = 200;
+
+/// Actors and directors of library titles, most titles first. Only people
+/// with a TMDB ID are listed.
+#[tauri::command]
+pub fn get_people(state: State<Database>, query: Option<String>, limit: Option<usize>) -> Result<Vec<LibraryPerson>, String> {
+    state.get_people(query.as_deref(), limit.unwrap_or(DEFAULT_PEOPLE_LIMIT)).map_err(|e| e.to_string())
+}
+
+/// The library titles a person (TMDB person ID) is credited on.
+#[tauri::command]
+pub fn get_person_movies(state: State<Database>, person_id: u64) -> Result<Vec<PersonCredit>, String> {
+    state.get_person_credits(person_id).map_err(|e| e.to_string())
+}
+
+/// A person's movies and TV shows on TMDB, each marked with the library
+/// entry it matches. With `only_missing` the titles already in the library
+/// are left out.
+#[tauri::command]
+pub async fn get_person_filmography(
+    state: State<'_, Database>,
+    tmdb: State<'_, TmdbClient>,
+    person_id: u64,
+    only_missing: Option<bool>,
+) -> Result<PersonFilmography, String> {
+    let detail = tmdb.person(person_id).await?;
+    let library: HashMap<(u64, bool), u64> = state.get_movies().iter()
+        .filter_map(|m| Some(((m.tmdb_id?, m.category.as_deref() == Some("tv")), m.id)))
+        .collect();
+
+    let credits = detail.combined_credits.map(|c| (c.cast, c.crew)).unwrap_or_default();
+    let mut entries: Vec<FilmographyEntry> = Vec::new();
+    let mut index: HashMap<(u64, bool), usize> = HashMap::new();
+    for credit in credits.0.into_iter().chain(credits.1) {
+        let is_tv = credit.movie.media_type.as_deref() == Some("tv");
+        let key = (credit.movie.id, is_tv);
+        let at = *index.entry(key).or_insert_with(|| {
+            entries.push(FilmographyEntry {
+                movie: credit.movie,
+                characters: Vec::new(),
+                jobs: Vec::new(),
+                library_movie_id: library.get(&key).copied(),
+            });
+            entries.len() - 1
+        });
+        let entry = &mut entries[at];
+        if let Some(character) = credit.character.filter(|c| !c.is_empty() && !entry.characters.contains(c)) {
+            entry.characters.push(character);
+        }
+        if let Some(job) = credit.job.filter(|j| !entry.jobs.contains(j)) {
+            entry.jobs.push(job);
+        }
+    }
+
+    let in_library = entries.iter().filter(|e| e.library_movie_id.is_some()).count();
+    if only_missing.unwrap_or(false) {
+        entries.retain(|e| e.library_movie_id.is_none());
+    }
+    // Announced titles without a date first, then newest first
+    entries.sort_by(|a, b| match (a.movie.display_date(), b.movie.display_date()) {
+        (Some(x), Some(y)) => y.cmp(x),
+        (x, y) => x.is_some().cmp(&y.is_some()),
+    });
+
+    Ok(PersonFilmography {
+        person: TmdbPerson {
+            id: detail.id,
+            name: detail.name,
+            original_name: None,
+            profile_path: detail.profile_path,
+            known_for_department: detail.known_for_department,
+        },
+        biography: detail.biography.filter(|b| !b.is_empty()),
+        birthday: detail.birthday,
+        deathday: detail.deathday,
+        entries,
+        in_library,
+    })
+}
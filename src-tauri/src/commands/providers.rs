@@ -0,0 +1,162 @@
+use tauri::{AppHandle, Emitter, State};
+use crate::db::Database;
+use crate::jobs::JobRegistry;
+use crate::models::{
+    MetadataField, MetadataRefreshFilter, MetadataRefreshProgress, MetadataRefreshReport, MetadataRefreshResult,
+    Movie, MovieMetadataDiff, ProviderHit, ProviderId, ProviderImage,
+};
+use crate::providers;
+use crate::tmdb::TmdbClient;
+
+/// Update a movie from every enabled metadata provider (TMDB, Douban, NFO),
+/// taking each field from the provider the merge policy prefers.
+#[tauri::command]
+pub async fn refresh_movie_metadata(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, movie_id: u64) -> Result<MetadataRefreshResult, String> {
+    providers::refresh_movie(&state, &tmdb, movie_id).await
+}
+
+/// Search one provider, or all enabled ones. Fails only if every provider did.
+#[tauri::command]
+pub async fn search_metadata(
+    state: State<'_, Database>,
+    tmdb: State<'_, TmdbClient>,
+    query: String,
+    provider: Option<ProviderId>,
+) -> Result<Vec<ProviderHit>, String> {
+    let mut hits = Vec::new();
+    let mut errors = Vec::new();
+    for p in providers::enabled(&state, &tmdb).iter().filter(|p| provider.is_none_or(|id| p.id() == id)) {
+        match p.search(&query).await {
+            Ok(mut found) => hits.append(&mut found),
+            Err(e) => errors.push(format!("{:?}: {}", p.id(), e)),
+        }
+    }
+    if hits.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(hits)
+}
+
+/// Posters, backdrops and logos of a movie from every enabled provider.
+#[tauri::command]
+pub async fn get_metadata_images(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, movie_id: u64) -> Result<Vec<ProviderImage>, String> {
+    let movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    let mut images = Vec::new();
+    for p in providers::enabled(&state, &tmdb) {
+        match p.images(&movie).await {
+            Ok(mut found) => images.append(&mut found),
+            Err(e) => eprintln!("[Providers] {:?} images for {} failed: {}", p.id(), movie.title, e),
+        }
+    }
+    Ok(images)
+}
+
+fn matches_filter(movie: &Movie, filter: &MetadataRefreshFilter) -> bool {
+    let added: String = movie.add_time.chars().take(10).collect();
+    movie.tmdb_id.is_some()
+        && filter.category.as_ref().is_none_or(|c| movie.category.as_ref() == Some(c))
+        && filter.added_after.as_ref().is_none_or(|d| added >= *d)
+        && filter.added_before.as_ref().is_none_or(|d| added < *d)
+}
+
+/// Re-fetch the metadata of every movie with a TMDB ID (optionally only some
+/// of them) from the enabled providers; Douban only for titles with a Douban
+/// ID (or an NFO file giving one). Fields edited by hand are never touched.
+/// With `dry_run` the report only holds the diffs for review, see
+/// `apply_metadata_changes`. Progress is sent per movie as
+/// `metadata-refresh-progress` events; pass the job id to `cancel_job` to stop.
+#[tauri::command]
+pub async fn refresh_library_metadata(
+    app: AppHandle,
+    state: State<'_, Database>,
+    tmdb: State<'_, TmdbClient>,
+    jobs: State<'_, JobRegistry>,
+    filter: Option<MetadataRefreshFilter>,
+    dry_run: Option<bool>,
+    job_id: Option<String>,
+) -> Result<MetadataRefreshReport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let filter = filter.unwrap_or_default();
+    let policy = state.get_config().metadata_merge_policy;
+    let mut enabled = providers::enabled(&state, &tmdb);
+    if let Some(only) = &filter.providers {
+        enabled.retain(|p| only.contains(&p.id()));
+    }
+    let registry = jobs.inner().clone();
+    let (job_id, cancel) = registry.start(job_id);
+
+    let movies: Vec<Movie> = state.get_movies().into_iter().filter(|m| matches_filter(m, &filter)).collect();
+    let mut report = MetadataRefreshReport {
+        job_id: job_id.clone(),
+        dry_run,
+        cancelled: false,
+        checked: 0,
+        movies: Vec::new(),
+    };
+
+    // TMDB and Douban requests wait for their rate limiters, so this paces itself
+    for movie in &movies {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let (results, errors) = providers::fetch_all(&mut enabled, movie, false).await;
+
+        // Merge into a fresh copy, the movie may have been edited meanwhile
+        let Some(current) = state.get_movie(movie.id) else { continue };
+        let (merged, mut diff) = providers::diff(&current, &results, &policy);
+        diff.errors = errors;
+        let has_updates = !diff.changes.is_empty() || !diff.new_aliases.is_empty() || merged.ratings != current.ratings;
+        if !dry_run && has_updates {
+            match state.update_movie(merged) {
+                Ok(()) => diff.applied = true,
+                Err(e) => diff.errors.push(e.to_string()),
+            }
+        }
+
+        report.checked += 1;
+        let event = MetadataRefreshProgress {
+            job_id: job_id.clone(),
+            processed: report.checked,
+            total: movies.len(),
+            movie_id: movie.id,
+            title: movie.title.clone(),
+            changed_fields: diff.changes.len(),
+            error: diff.errors.first().cloned(),
+        };
+        app.emit("metadata-refresh-progress", event).unwrap_or(());
+
+        if !diff.changes.is_empty() || !diff.skipped_fields.is_empty() || !diff.errors.is_empty() {
+            report.movies.push(diff);
+        }
+    }
+
+    registry.finish(&job_id);
+    Ok(report)
+}
+
+/// Apply the diffs of a dry-run refresh the user accepted (possibly with
+/// some changes removed). Returns how many movies were updated.
+#[tauri::command]
+pub fn apply_metadata_changes(state: State<Database>, diffs: Vec<MovieMetadataDiff>) -> Result<usize, String> {
+    let mut updated = 0;
+    for diff in &diffs {
+        if providers::apply_diff(&state, diff)? {
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// Let refreshes update fields that were edited by hand again; all of them
+/// without `fields`.
+#[tauri::command]
+pub fn unlock_movie_fields(state: State<Database>, movie_id: u64, fields: Option<Vec<MetadataField>>) -> Result<Movie, String> {
+    let mut movie = state.get_movie(movie_id).ok_or("Movie not found")?;
+    match fields {
+        Some(fields) => movie.user_edited_fields.retain(|f| !fields.contains(f)),
+        None => movie.user_edited_fields.clear(),
+    }
+    state.update_movie(movie.clone()).map_err(|e| e.to_string())?;
+    Ok(movie)
+}
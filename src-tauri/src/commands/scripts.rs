@@ -0,0 +1,51 @@
+use std::path::Path;
+use tauri::State;
+use crate::db::Database;
+use crate::metadata::document;
+use crate::metadata::script::{narration_seconds, snippet};
+use crate::models::{ScriptDetail, ScriptHit};
+
+/// Most scripts one search returns unless asked for another limit.
+const SCRIPT_SEARCH_LIMIT: usize = 200;
+
+/// Find linked scripts (document materials) by their text. Without a query
+/// every indexed script is listed, e.g. to compare narration lengths.
+#[tauri::command]
+pub fn search_scripts(
+    state: State<Database>,
+    query: Option<String>,
+    movie_id: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<ScriptHit>, String> {
+    let query = query.map(|q| q.trim().to_string()).filter(|q| !q.is_empty());
+    let cpm = state.get_config().narration_chars_per_minute;
+    let rows = state
+        .search_scripts(query.as_deref(), movie_id, limit.unwrap_or(SCRIPT_SEARCH_LIMIT))
+        .map_err(|e| e.to_string())?;
+
+    // Ebooks indexed before they were recognised as unreadable, until re-probed
+    let rows = rows.into_iter().filter(|row| document::is_supported(Path::new(&row.path)));
+    Ok(rows.map(|row| ScriptHit {
+        snippet: query.as_deref().and_then(|q| snippet(&row.text, q)),
+        estimated_seconds: narration_seconds(row.char_count, cpm),
+        movie_id: row.movie_id,
+        material_id: row.material_id,
+        path: row.path,
+        char_count: row.char_count,
+    }).collect())
+}
+
+/// The extracted text of one script, with its narration estimate.
+#[tauri::command]
+pub fn get_script(state: State<Database>, movie_id: u64, material_id: String) -> Result<Option<ScriptDetail>, String> {
+    let cpm = state.get_config().narration_chars_per_minute;
+    Ok(state.get_script_text(movie_id, &material_id).map(|row| ScriptDetail {
+        estimated_seconds: narration_seconds(row.char_count, cpm),
+        movie_id: row.movie_id,
+        material_id: row.material_id,
+        path: row.path,
+        text: row.text,
+        char_count: row.char_count,
+        extracted_at: row.extracted_at,
+    }))
+}
@@ -0,0 +1,407 @@
+use std::sync::OnceLock;
+use regex::Regex;
+use tauri::State;
+use crate::commands::movie::spawn_post_add;
+use crate::db::Database;
+use crate::matching::{score_name, to_percent, MatchTarget};
+use crate::matching::release::year_of;
+use crate::models::tmdb::TmdbMovie;
+use crate::models::{BatchAddResult, BatchAddSkip, Movie, ResolvedTitle, TitleCandidate, TitleListEntry, TitleResolution};
+use crate::providers::{self, fetch_subject, parse_subject_id, tmdb_metadata};
+use crate::tmdb::TmdbClient;
+
+/// Below this the best candidate is held for a manual choice.
+const RESOLVE_THRESHOLD: f64 = 0.9;
+/// A runner-up this close to the best candidate makes the row ambiguous,
+/// e.g. a remake when the list gives no year.
+const AMBIGUITY_MARGIN: f64 = 0.05;
+/// Candidates kept per row for the manual choice.
+const MAX_CANDIDATES: usize = 6;
+
+struct Patterns {
+    ids: Regex,
+    year: Regex,
+    trailing_year: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        ids: Regex::new(r"^(?:(?P<imdb>tt\d{6,})|(?:tmdb:|.*themoviedb\.org/)(?:(?P<kind>movie|tv)[:/])?(?P<tmdb>\d+))").unwrap(),
+        year: Regex::new(r"^(18[7-9]\d|19\d\d|20\d\d)$").unwrap(),
+        // Only in brackets, "Blade Runner 2049" is a title
+        trailing_year: Regex::new(r"^(?P<title>.+?)\s*[(（\[](?P<year>18[7-9]\d|19\d\d|20\d\d)[)）\]]$").unwrap(),
+    })
+}
+
+/// Cells of a CSV or tab-separated line; double quotes may wrap a cell.
+fn split_cells(line: &str) -> Vec<String> {
+    let separator = if line.contains('\t') { '\t' } else { ',' };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+fn is_header(cells: &[String]) -> bool {
+    let first = cells.first().map(|c| c.trim_start_matches('\u{feff}').to_lowercase()).unwrap_or_default();
+    ["title", "name", "标题", "片名", "名称"].contains(&first.as_str())
+}
+
+/// Fill `entry` from a cell holding an ID, a year or a type. Returns false
+/// for anything else, which is taken as the title.
+fn read_cell(entry: &mut TitleListEntry, cell: &str) -> bool {
+    let Patterns { ids, year, .. } = patterns();
+    let lower = cell.to_lowercase();
+    if lower.contains("douban.com") || lower.starts_with("douban:") {
+        entry.douban_id = Some(parse_subject_id(cell.trim_start_matches("douban:")));
+        return true;
+    }
+    if let Some(caps) = ids.captures(&lower) {
+        if let Some(imdb) = caps.name("imdb") {
+            entry.imdb_id = Some(imdb.as_str().to_string());
+        } else if let Some(id) = caps.name("tmdb").and_then(|m| m.as_str().parse().ok()) {
+            entry.tmdb_id = Some(id);
+            if let Some(kind) = caps.name("kind") {
+                entry.category = Some(kind.as_str().to_string());
+            }
+        }
+        return true;
+    }
+    if year.is_match(cell) {
+        entry.year = cell.parse().ok();
+        return true;
+    }
+    match lower.as_str() {
+        "movie" | "电影" => entry.category = Some("movie".to_string()),
+        "tv" | "剧集" | "电视剧" => entry.category = Some("tv".to_string()),
+        _ => return false,
+    }
+    true
+}
+
+/// Parse a planning list: one title per line, optionally followed by a year
+/// and a Douban, TMDB or IMDb ID, separated by commas or tabs ("Dune, 2021",
+/// "Dune (2021)", "tmdb:tv/1399", a douban.com URL). Blank lines, `#`
+/// comments and a header row are skipped. In plain lists a comma may also be
+/// part of the title ("Crouching Tiger, Hidden Dragon"); only CSV (a header
+/// row or quoted cells) and tab-separated lines take the first other cell as
+/// the title and ignore the rest.
+pub fn parse_title_list(text: &str) -> Vec<TitleListEntry> {
+    let Patterns { year, trailing_year, .. } = patterns();
+
+    let mut entries = Vec::new();
+    let mut has_header = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let cells = split_cells(line);
+        if entries.is_empty() && is_header(&cells) {
+            has_header = true;
+            continue;
+        }
+
+        let mut entry = TitleListEntry { line: index + 1, ..Default::default() };
+        let mut rest: Vec<String> = Vec::new();
+        for cell in cells.iter().filter(|c| !c.is_empty()) {
+            // Of two years the last is the year, the other a title ("1917, 2019")
+            let earlier_year = entry.year.filter(|_| year.is_match(cell));
+            if !read_cell(&mut entry, cell) {
+                rest.push(cell.clone());
+            } else if let Some(earlier) = earlier_year {
+                rest.push(earlier.to_string());
+            }
+        }
+        let structured = has_header || line.contains('\t') || line.contains('"');
+        entry.title = if structured {
+            rest.into_iter().next().unwrap_or_default()
+        } else {
+            rest.join(", ")
+        };
+        if entry.title.is_empty() && entry.tmdb_id.is_none() && entry.douban_id.is_none() && entry.imdb_id.is_none() {
+            // A title that looks like a year, "1917"
+            if let Some(year) = entry.year.take() {
+                entry.title = year.to_string();
+            }
+        }
+        if entry.year.is_none() {
+            if let Some(caps) = trailing_year.captures(&entry.title) {
+                entry.year = caps["year"].parse().ok();
+                entry.title = caps["title"].trim().to_string();
+            }
+        }
+        if !entry.title.is_empty() || entry.tmdb_id.is_some() || entry.douban_id.is_some() || entry.imdb_id.is_some() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// The library entry `movie` duplicates: same TMDB, Douban or IMDb ID, or
+/// the same title (or original title) and year.
+fn find_in_library<'a>(library: &'a [Movie], movie: &Movie) -> Option<&'a Movie> {
+    let titles = |m: &Movie| -> Vec<String> {
+        [Some(&m.title), m.original_title.as_ref()].into_iter().flatten()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect()
+    };
+    let year = |m: &Movie| m.release_date.as_deref().and_then(year_of);
+    let movie_titles = titles(movie);
+    library.iter().find(|m| {
+        (movie.tmdb_id.is_some() && m.tmdb_id == movie.tmdb_id)
+            || (movie.douban_id.is_some() && m.douban_id == movie.douban_id)
+            || (movie.imdb_id.is_some() && m.imdb_id == movie.imdb_id)
+            || (year(m) == year(movie) && titles(m).iter().any(|t| movie_titles.contains(t)))
+    })
+}
+
+fn candidate(movie: Movie, score: f64) -> TitleCandidate {
+    TitleCandidate { movie, confidence: to_percent(score) }
+}
+
+/// TMDB candidates for a Douban subject, searched by its title and year
+/// (the subject data carries no IMDb ID). The Douban ID is kept on each.
+async fn douban_candidates(tmdb: &TmdbClient, entry: &TitleListEntry, douban_id: &str) -> Result<Vec<TitleCandidate>, String> {
+    let meta = fetch_subject(douban_id, entry.category.as_deref() == Some("tv")).await?;
+    let search = TitleListEntry {
+        title: meta.title.clone().or_else(|| meta.original_title.clone()).unwrap_or_default(),
+        year: meta.release_date.as_deref().and_then(year_of).or(entry.year),
+        category: meta.category.clone().or_else(|| entry.category.clone()),
+        ..Default::default()
+    };
+    let mut found = search_candidates(tmdb, &search).await?;
+    for c in &mut found {
+        c.movie.douban_id = Some(douban_id.to_string());
+    }
+    Ok(found)
+}
+
+async fn imdb_candidates(tmdb: &TmdbClient, imdb_id: &str) -> Result<Vec<TitleCandidate>, String> {
+    let found = tmdb.find_by_imdb(imdb_id).await?;
+    let score = if found.len() == 1 { 1.0 } else { RESOLVE_THRESHOLD - AMBIGUITY_MARGIN };
+    Ok(found.iter().map(|r| candidate(r.to_movie(), score)).collect())
+}
+
+/// Candidates for a row, best first, scored like folder imports: title
+/// similarity with the year agreeing or not, and a nudge for the right type.
+async fn candidates(tmdb: &TmdbClient, entry: &TitleListEntry) -> Result<Vec<TitleCandidate>, String> {
+    if let Some(id) = &entry.douban_id {
+        return douban_candidates(tmdb, entry, id).await;
+    }
+    if let Some(id) = entry.tmdb_id {
+        let media_type = entry.category.as_deref().unwrap_or("movie");
+        let detail = tmdb.details(id, media_type).await?;
+        return Ok(vec![candidate(providers::to_movie(&tmdb_metadata(id, media_type, detail)), 1.0)]);
+    }
+    if let Some(id) = &entry.imdb_id {
+        return imdb_candidates(tmdb, id).await;
+    }
+    search_candidates(tmdb, entry).await
+}
+
+async fn search_candidates(tmdb: &TmdbClient, entry: &TitleListEntry) -> Result<Vec<TitleCandidate>, String> {
+    if entry.title.is_empty() {
+        return Ok(Vec::new());
+    }
+    let results = tmdb.search_multi(&entry.title, 1).await?;
+    let query = match entry.year {
+        Some(year) => format!("{} {}", entry.title, year),
+        None => entry.title.clone(),
+    };
+    let mut scored: Vec<(f64, &TmdbMovie)> = results.iter()
+        .map(|r| {
+            let titles: Vec<String> = [r.display_title(), r.display_original_title()]
+                .into_iter().flatten().map(|s| s.to_string()).collect();
+            let target = MatchTarget::new(&titles, r.display_date().and_then(year_of));
+            let mut score = score_name(&query, &target);
+            if entry.category.is_some() && r.media_type == entry.category {
+                score = (score + 0.02).min(1.0);
+            }
+            (score, r)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(scored.into_iter().take(MAX_CANDIDATES).map(|(score, r)| candidate(r.to_movie(), score)).collect())
+}
+
+async fn resolve(tmdb: &TmdbClient, library: &[Movie], entry: TitleListEntry) -> ResolvedTitle {
+    let mut row = ResolvedTitle {
+        entry,
+        status: TitleResolution::NotFound,
+        confidence: 0,
+        movie: None,
+        candidates: Vec::new(),
+        existing_movie_id: None,
+        error: None,
+    };
+    match candidates(tmdb, &row.entry).await {
+        Ok(found) => row.candidates = found,
+        Err(e) => {
+            row.status = TitleResolution::Failed;
+            row.error = Some(e);
+            return row;
+        }
+    }
+    let Some(best) = row.candidates.first() else {
+        return row;
+    };
+
+    row.confidence = best.confidence;
+    let threshold = to_percent(RESOLVE_THRESHOLD);
+    let close_runner_up = row.candidates.get(1)
+        .is_some_and(|c| best.confidence.saturating_sub(c.confidence) <= to_percent(AMBIGUITY_MARGIN));
+    if best.confidence < threshold || close_runner_up {
+        row.status = TitleResolution::Ambiguous;
+        return row;
+    }
+
+    let movie = best.movie.clone();
+    match find_in_library(library, &movie) {
+        Some(existing) => {
+            row.status = TitleResolution::InLibrary;
+            row.existing_movie_id = Some(existing.id);
+        }
+        None => row.status = TitleResolution::Resolved,
+    }
+    row.movie = Some(movie);
+    row
+}
+
+/// Resolve a planning list (plain text or CSV, see [`parse_title_list`]) to
+/// TMDB titles. Rows with one clear match are `resolved`; the others keep
+/// their candidates for a manual choice. Nothing is added yet, pass the
+/// picked movies to `add_movies`.
+#[tauri::command]
+pub async fn resolve_title_list(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, text: String) -> Result<Vec<ResolvedTitle>, String> {
+    let config = state.get_config();
+    if !config.tmdb_offline {
+        config.tmdb_api_key.as_ref().filter(|k| !k.trim().is_empty()).ok_or("TMDB API Key not set")?;
+    }
+    let library = state.get_movies();
+
+    // Sequential on purpose: the client's rate limiter paces the lookups anyway
+    let mut rows = Vec::new();
+    for entry in parse_title_list(&text) {
+        rows.push(resolve(&tmdb, &library, entry).await);
+    }
+    Ok(rows)
+}
+
+/// Add several movies at once, skipping those already in the library (or
+/// earlier in the batch). Images and details are fetched in the background
+/// as for `add_movie`.
+#[tauri::command]
+pub fn add_movies(state: State<'_, Database>, tmdb: State<'_, TmdbClient>, movies: Vec<Movie>) -> Result<BatchAddResult, String> {
+    let mut library = state.get_movies();
+    let mut result = BatchAddResult::default();
+    for movie in movies {
+        if let Some(existing) = find_in_library(&library, &movie) {
+            result.skipped.push(BatchAddSkip {
+                title: movie.title,
+                existing_movie_id: Some(existing.id),
+                reason: format!("已在库中: {}", existing.title),
+            });
+            continue;
+        }
+        let title = movie.title.clone();
+        match state.add_movie(movie) {
+            Ok(added) => {
+                spawn_post_add(state.inner().clone(), tmdb.inner().clone(), added.clone());
+                library.push(added.clone());
+                result.added.push(added);
+            }
+            Err(e) => result.skipped.push(BatchAddSkip { title, existing_movie_id: None, reason: e.to_string() }),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one(line: &str) -> TitleListEntry {
+        let mut entries = parse_title_list(line);
+        assert_eq!(entries.len(), 1, "{:?}", entries);
+        entries.remove(0)
+    }
+
+    #[test]
+    fn title_and_year() {
+        for line in ["Dune, 2021", "Dune\t2021", "Dune (2021)", "Dune（2021）", "Dune [2021]"] {
+            let entry = one(line);
+            assert_eq!((entry.title.as_str(), entry.year), ("Dune", Some(2021)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn years_inside_titles() {
+        let entry = one("Blade Runner 2049");
+        assert_eq!((entry.title.as_str(), entry.year), ("Blade Runner 2049", None));
+        let entry = one("1917");
+        assert_eq!((entry.title.as_str(), entry.year), ("1917", None));
+        let entry = one("1917, 2019");
+        assert_eq!((entry.title.as_str(), entry.year), ("1917", Some(2019)));
+        let entry = one("2001: A Space Odyssey (1968)");
+        assert_eq!((entry.title.as_str(), entry.year), ("2001: A Space Odyssey", Some(1968)));
+    }
+
+    #[test]
+    fn commas_in_plain_titles() {
+        let entry = one("Crouching Tiger, Hidden Dragon");
+        assert_eq!((entry.title.as_str(), entry.year), ("Crouching Tiger, Hidden Dragon", None));
+        let entry = one("Crouching Tiger, Hidden Dragon, 2000");
+        assert_eq!((entry.title.as_str(), entry.year), ("Crouching Tiger, Hidden Dragon", Some(2000)));
+        let entry = one("Crouching Tiger, Hidden Dragon (2000)");
+        assert_eq!((entry.title.as_str(), entry.year), ("Crouching Tiger, Hidden Dragon", Some(2000)));
+    }
+
+    #[test]
+    fn csv_with_header_and_quotes() {
+        let entries = parse_title_list("title,year,note\nHeat,1995,watch first\n\"Crouching Tiger, Hidden Dragon\",2000,\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].title.as_str(), entries[0].year, entries[0].line), ("Heat", Some(1995), 2));
+        assert_eq!((entries[1].title.as_str(), entries[1].year), ("Crouching Tiger, Hidden Dragon", Some(2000)));
+
+        let entry = one("\"Say \"\"Hello\"\"\",1999");
+        assert_eq!(entry.title, "Say \"Hello\"");
+    }
+
+    #[test]
+    fn ids_and_types() {
+        let entry = one("tt0111161");
+        assert_eq!((entry.imdb_id.as_deref(), entry.title.as_str()), (Some("tt0111161"), ""));
+        let entry = one("Game of Thrones, tmdb:tv/1399");
+        assert_eq!((entry.tmdb_id, entry.category.as_deref()), (Some(1399), Some("tv")));
+        assert_eq!(entry.title, "Game of Thrones");
+        let entry = one("https://www.themoviedb.org/movie/550-fight-club");
+        assert_eq!((entry.tmdb_id, entry.category.as_deref()), (Some(550), Some("movie")));
+        let entry = one("霸王别姬, https://movie.douban.com/subject/1291546/");
+        assert_eq!((entry.title.as_str(), entry.douban_id.as_deref()), ("霸王别姬", Some("1291546")));
+        let entry = one("请回答1988\t2015\t剧集");
+        assert_eq!((entry.title.as_str(), entry.year, entry.category.as_deref()), ("请回答1988", Some(2015), Some("tv")));
+    }
+
+    #[test]
+    fn skips_blank_lines_comments_and_headers() {
+        let entries = parse_title_list("片名\t年份\n\n# to watch\n卧虎藏龙（2000）\n  \n花样年华\n");
+        let found: Vec<(usize, &str, Option<u16>)> = entries.iter().map(|e| (e.line, e.title.as_str(), e.year)).collect();
+        assert_eq!(found, vec![(4, "卧虎藏龙", Some(2000)), (6, "花样年华", None)]);
+    }
+}
@@ -1,18 +1,64 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Mutex, Arc};
-use crate::models::{AppData, Movie, AppConfig, Material};
+use crate::models::{
+    AppData, Movie, AppConfig, CalendarEvent, CalendarEventKind, CreditRole, ImageKind, LibraryPerson, Material, MaterialMetadata, MovieImage, Person,
+    PersonCredit, StoredImage,
+};
+use crate::fingerprint::{merge_material, Fingerprint, MergeOutcome};
 use anyhow::Result;
 use dirs;
 use rusqlite::{Connection, params, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+/// Rows read per lock when searching the file indexes.
+const SEARCH_BATCH: usize = 1000;
+
+/// `%keyword%` for `LIKE ... ESCAPE '\'`, with the wildcards in the keyword escaped.
+fn like_pattern(keyword: &str) -> String {
+    let escaped = keyword.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
 #[derive(Serialize, Deserialize)]
 struct LauncherConfig {
     data_root: String,
 }
 
+/// Extracted text of a document material.
+#[derive(Debug, Clone)]
+pub struct ScriptRow {
+    pub movie_id: u64,
+    pub material_id: String,
+    pub path: String,
+    pub text: String,
+    pub char_count: u64,
+    pub extracted_at: String,
+}
+
+/// One file of the search catalog, see [`crate::search::CatalogBackend`].
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+/// One file or folder of the persisted NTFS index, see [`crate::search::ntfs`].
+pub struct UsnIndexEntry {
+    pub fid: u64,
+    pub parent_fid: u64,
+    pub name: String,
+    pub is_dir: bool,
+}
+
+pub enum UsnChange {
+    Upsert(UsnIndexEntry),
+    Delete(u64),
+}
+
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
@@ -83,7 +129,7 @@ impl Database {
                 genres TEXT,
                 actors TEXT,
                 directors TEXT,
-                materialsvalue TEXT
+                materials TEXT
             )",
             [],
         )?;
@@ -101,6 +147,26 @@ impl Database {
             [],
         )?;
 
+        // Columns added after the first release
+        Self::ensure_columns(&conn, "movies", &[
+            ("materials", "TEXT"),
+            ("douban_id", "TEXT"),
+            ("ratings", "TEXT"),
+            ("field_sources", "TEXT"),
+            ("user_edited_fields", "TEXT"),
+            ("runtime", "INTEGER"),
+            ("imdb_id", "TEXT"),
+            ("tvdb_id", "INTEGER"),
+            ("countries", "TEXT"),
+            ("original_language", "TEXT"),
+            ("certification", "TEXT"),
+            ("collection", "TEXT"),
+            ("backdrop_path", "TEXT"),
+            ("logo_path", "TEXT"),
+            ("posters", "TEXT"),
+            ("production_deadline", "TEXT"),
+        ])?;
+
         // Insert default preset
         conn.execute(
             "INSERT OR IGNORE INTO audio_presets (name, input_boost, max_amplitude, lookahead, release_time)
@@ -115,6 +181,167 @@ impl Database {
         conn.execute("CREATE INDEX IF NOT EXISTS idx_movies_release_date ON movies (release_date)", [])?;
         conn.execute("CREATE INDEX IF NOT EXISTS idx_movies_category ON movies (category)", [])?;
         
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS file_fingerprints (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified TEXT NOT NULL,
+                partial_hash TEXT NOT NULL,
+                full_hash TEXT
+            )",
+            [],
+        )?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_file_fingerprints_partial ON file_fingerprints (partial_hash)", [])?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS file_catalog (
+                root TEXT NOT NULL,
+                path TEXT NOT NULL,
+                name_lower TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                modified TEXT,
+                PRIMARY KEY (root, path)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS file_catalog_roots (
+                root TEXT PRIMARY KEY,
+                indexed_at TEXT NOT NULL,
+                file_count INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS usn_volumes (
+                volume TEXT PRIMARY KEY,
+                journal_id INTEGER NOT NULL,
+                next_usn INTEGER NOT NULL,
+                indexed_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS usn_entries (
+                volume TEXT NOT NULL,
+                fid INTEGER NOT NULL,
+                parent_fid INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                name_lower TEXT NOT NULL,
+                is_dir INTEGER NOT NULL,
+                PRIMARY KEY (volume, fid)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS script_texts (
+                movie_id INTEGER NOT NULL,
+                material_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                text TEXT NOT NULL,
+                char_count INTEGER NOT NULL,
+                extracted_at TEXT NOT NULL,
+                PRIMARY KEY (movie_id, material_id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS preview_cache (
+                key TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Downloaded artwork by content hash, and which movies use it
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS image_store (
+                hash TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                url TEXT,
+                width INTEGER,
+                height INTEGER,
+                size INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS image_refs (
+                movie_id INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                PRIMARY KEY (movie_id, hash, kind)
+            )",
+            [],
+        )?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_image_store_url ON image_store (url)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_image_refs_hash ON image_refs (hash)", [])?;
+
+        // Cast and crew of each movie, mirrored from its actors and directors
+        // (people with a TMDB ID only) so lookups by person need no scan
+        let has_credits: bool = conn.query_row(
+            "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'credits'",
+            [],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS people (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                original_name TEXT,
+                profile_path TEXT
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS credits (
+                movie_id INTEGER NOT NULL,
+                person_id INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                character TEXT,
+                job TEXT,
+                credit_order INTEGER NOT NULL,
+                PRIMARY KEY (movie_id, person_id, role)
+            )",
+            [],
+        )?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_credits_person ON credits (person_id)", [])?;
+        if !has_credits {
+            Self::rebuild_credits(&conn)?;
+        }
+
+        // Air and release dates fetched from TMDB for the calendar
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS calendar_events (
+                movie_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                date TEXT NOT NULL,
+                name TEXT,
+                season INTEGER,
+                episode INTEGER,
+                region TEXT,
+                release_type INTEGER
+            )",
+            [],
+        )?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_calendar_events_date ON calendar_events (date)", [])?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS http_cache (
+                key TEXT PRIMARY KEY,
+                body TEXT NOT NULL,
+                fetched_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
@@ -166,9 +393,13 @@ impl Database {
             "INSERT INTO movies (
                 id, tmdb_id, title, original_title, overview, poster_path, release_date, 
                 vote_average, local_video_path, aliases, add_time, remark, viewing_date, 
-                category, production_status, matched_folders, genres, actors, directors, materials
+                category, production_status, matched_folders, genres, actors, directors, materials,
+                douban_id, ratings, field_sources, user_edited_fields, runtime, imdb_id, tvdb_id,
+                countries, original_language, certification, collection, backdrop_path, logo_path, posters,
+                production_deadline
             ) VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+                ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35
             )",
             params![
                 movie.id as i64,
@@ -190,9 +421,25 @@ impl Database {
                 serde_json::to_string(&movie.genres)?,
                 serde_json::to_string(&movie.actors)?,
                 serde_json::to_string(&movie.directors)?,
-                serde_json::to_string(&movie.materials)?
+                serde_json::to_string(&movie.materials)?,
+                movie.douban_id,
+                serde_json::to_string(&movie.ratings)?,
+                serde_json::to_string(&movie.field_sources)?,
+                serde_json::to_string(&movie.user_edited_fields)?,
+                movie.runtime.map(|r| r as i64),
+                movie.imdb_id,
+                movie.tvdb_id.map(|id| id as i64),
+                serde_json::to_string(&movie.countries)?,
+                movie.original_language,
+                movie.certification,
+                serde_json::to_string(&movie.collection)?,
+                movie.backdrop_path,
+                movie.logo_path,
+                serde_json::to_string(&movie.posters)?,
+                movie.production_deadline
             ],
         )?;
+        Self::sync_credits(&conn, movie.id, &movie.actors, &movie.directors)?;
         Ok(())
     }
 
@@ -280,9 +527,13 @@ impl Database {
             "INSERT INTO movies (
                 tmdb_id, title, original_title, overview, poster_path, release_date, 
                 vote_average, local_video_path, aliases, add_time, remark, viewing_date, 
-                category, production_status, matched_folders, genres, actors, directors, materials
+                category, production_status, matched_folders, genres, actors, directors, materials,
+                douban_id, ratings, field_sources, user_edited_fields, runtime, imdb_id, tvdb_id,
+                countries, original_language, certification, collection, backdrop_path, logo_path, posters,
+                production_deadline
             ) VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19,
+                ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34
             )",
             params![
                 movie.tmdb_id.map(|id| id as i64),
@@ -303,16 +554,47 @@ impl Database {
                 serde_json::to_string(&movie.genres)?,
                 serde_json::to_string(&movie.actors)?,
                 serde_json::to_string(&movie.directors)?,
-                serde_json::to_string(&movie.materials)?
+                serde_json::to_string(&movie.materials)?,
+                movie.douban_id,
+                serde_json::to_string(&movie.ratings)?,
+                serde_json::to_string(&movie.field_sources)?,
+                serde_json::to_string(&movie.user_edited_fields)?,
+                movie.runtime.map(|r| r as i64),
+                movie.imdb_id,
+                movie.tvdb_id.map(|id| id as i64),
+                serde_json::to_string(&movie.countries)?,
+                movie.original_language,
+                movie.certification,
+                serde_json::to_string(&movie.collection)?,
+                movie.backdrop_path,
+                movie.logo_path,
+                serde_json::to_string(&movie.posters)?,
+                movie.production_deadline
             ],
         )?;
         
         let id = conn.last_insert_rowid() as u64;
         movie.id = id;
+        Self::sync_credits(&conn, id, &movie.actors, &movie.directors)?;
         
         Ok(movie)
     }
 
+    /// Add the columns `table` doesn't have yet.
+    fn ensure_columns(conn: &Connection, table: &str, columns: &[(&str, &str)]) -> Result<()> {
+        let existing: Vec<String> = {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for (name, kind) in columns {
+            if !existing.iter().any(|c| c == name) {
+                conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, name, kind), [])?;
+            }
+        }
+        Ok(())
+    }
+
     fn row_to_movie(row: &Row) -> Result<Movie, rusqlite::Error> {
         let aliases_str: Option<String> = row.get("aliases")?;
         let matched_folders_str: Option<String> = row.get("matched_folders")?;
@@ -320,6 +602,12 @@ impl Database {
         let actors_str: Option<String> = row.get("actors")?;
         let directors_str: Option<String> = row.get("directors")?;
         let materials_str: Option<String> = row.get("materials")?;
+        let ratings_str: Option<String> = row.get("ratings")?;
+        let field_sources_str: Option<String> = row.get("field_sources")?;
+        let user_edited_str: Option<String> = row.get("user_edited_fields")?;
+        let countries_str: Option<String> = row.get("countries")?;
+        let collection_str: Option<String> = row.get("collection")?;
+        let posters_str: Option<String> = row.get("posters")?;
 
         Ok(Movie {
             id: row.get::<_, i64>("id")? as u64,
@@ -342,6 +630,21 @@ impl Database {
             actors: actors_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
             directors: directors_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
             materials: materials_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            douban_id: row.get("douban_id")?,
+            ratings: ratings_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            field_sources: field_sources_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            user_edited_fields: user_edited_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            runtime: row.get::<_, Option<i64>>("runtime")?.map(|r| r as u64),
+            imdb_id: row.get("imdb_id")?,
+            tvdb_id: row.get::<_, Option<i64>>("tvdb_id")?.map(|id| id as u64),
+            countries: countries_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            original_language: row.get("original_language")?,
+            certification: row.get("certification")?,
+            collection: collection_str.and_then(|s| serde_json::from_str(&s).ok()).flatten(),
+            backdrop_path: row.get("backdrop_path")?,
+            logo_path: row.get("logo_path")?,
+            posters: posters_str.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default(),
+            production_deadline: row.get("production_deadline")?,
         })
     }
 
@@ -362,19 +665,36 @@ impl Database {
     pub fn delete_movie(&self, id: u64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM movies WHERE id = ?1", params![id as i64])?;
+        conn.execute("DELETE FROM script_texts WHERE movie_id = ?1", params![id as i64])?;
+        conn.execute("DELETE FROM image_refs WHERE movie_id = ?1", params![id as i64])?;
+        conn.execute("DELETE FROM credits WHERE movie_id = ?1", params![id as i64])?;
+        conn.execute("DELETE FROM people WHERE id NOT IN (SELECT person_id FROM credits)", [])?;
+        conn.execute("DELETE FROM calendar_events WHERE movie_id = ?1", params![id as i64])?;
         Ok(())
     }
 
     pub fn update_movie(&self, movie: Movie) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        // The stored dates belong to the old TMDB entry or type; the next
+        // calendar refresh fetches them again if the title still qualifies
+        conn.execute(
+            "DELETE FROM calendar_events WHERE movie_id = ?1 AND EXISTS (
+                SELECT 1 FROM movies WHERE id = ?1
+                AND NOT (tmdb_id IS ?2 AND category IS ?3 AND production_status IS ?4))",
+            params![movie.id as i64, movie.tmdb_id.map(|id| id as i64), movie.category, movie.production_status],
+        )?;
         conn.execute(
             "UPDATE movies SET 
                 tmdb_id = ?1, title = ?2, original_title = ?3, overview = ?4, poster_path = ?5, 
                 release_date = ?6, vote_average = ?7, local_video_path = ?8, aliases = ?9, 
                 add_time = ?10, remark = ?11, viewing_date = ?12, category = ?13, 
                 production_status = ?14, matched_folders = ?15, genres = ?16, actors = ?17, 
-                directors = ?18, materials = ?19
-             WHERE id = ?20",
+                directors = ?18, materials = ?19, douban_id = ?20, ratings = ?21,
+                field_sources = ?22, user_edited_fields = ?23, runtime = ?24, imdb_id = ?25,
+                tvdb_id = ?26, countries = ?27, original_language = ?28, certification = ?29,
+                collection = ?30, backdrop_path = ?31, logo_path = ?32, posters = ?33,
+                production_deadline = ?34
+             WHERE id = ?35",
             params![
                 movie.tmdb_id.map(|id| id as i64),
                 movie.title,
@@ -395,14 +715,35 @@ impl Database {
                 serde_json::to_string(&movie.actors)?,
                 serde_json::to_string(&movie.directors)?,
                 serde_json::to_string(&movie.materials)?,
+                movie.douban_id,
+                serde_json::to_string(&movie.ratings)?,
+                serde_json::to_string(&movie.field_sources)?,
+                serde_json::to_string(&movie.user_edited_fields)?,
+                movie.runtime.map(|r| r as i64),
+                movie.imdb_id,
+                movie.tvdb_id.map(|id| id as i64),
+                serde_json::to_string(&movie.countries)?,
+                movie.original_language,
+                movie.certification,
+                serde_json::to_string(&movie.collection)?,
+                movie.backdrop_path,
+                movie.logo_path,
+                serde_json::to_string(&movie.posters)?,
+                movie.production_deadline,
                 movie.id as i64
             ],
         )?;
+        Self::sync_credits(&conn, movie.id, &movie.actors, &movie.directors)?;
         Ok(())
     }
 
     pub fn update_movie_status(&self, id: u64, status: String) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM calendar_events WHERE movie_id = ?1 AND EXISTS (
+                SELECT 1 FROM movies WHERE id = ?1 AND production_status IS NOT ?2)",
+            params![id as i64, status],
+        )?;
         conn.execute(
             "UPDATE movies SET production_status = ?1 WHERE id = ?2",
             params![status, id as i64]
@@ -443,6 +784,20 @@ impl Database {
         Ok(())
     }
 
+    /// A value kept in the settings table next to the config.
+    pub fn get_setting(&self, key: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .unwrap_or(None)
+    }
+
+    pub fn save_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", params![key, value])?;
+        Ok(())
+    }
+
     // New methods to support commands/mod.rs
 
     pub fn update_movie_images(&self, id: u64, poster_path: Option<String>, actors_json: String, directors_json: String) -> Result<()> {
@@ -451,9 +806,91 @@ impl Database {
             "UPDATE movies SET poster_path = ?1, actors = ?2, directors = ?3 WHERE id = ?4",
             params![poster_path, actors_json, directors_json, id as i64],
         )?;
+        let actors: Vec<Person> = serde_json::from_str(&actors_json).unwrap_or_default();
+        let directors: Vec<Person> = serde_json::from_str(&directors_json).unwrap_or_default();
+        Self::sync_credits(&conn, id, &actors, &directors)?;
         Ok(())
     }
 
+    /// Replace the credits of a movie by its current actors and directors.
+    fn sync_credits(conn: &Connection, movie_id: u64, actors: &[Person], directors: &[Person]) -> Result<()> {
+        conn.execute("DELETE FROM credits WHERE movie_id = ?1", params![movie_id as i64])?;
+        for (role, people) in [(CreditRole::Cast, actors), (CreditRole::Crew, directors)] {
+            for (order, person) in people.iter().enumerate().filter(|(_, p)| p.id != 0) {
+                conn.execute(
+                    "INSERT INTO people (id, name, original_name, profile_path) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(id) DO UPDATE SET name = ?2,
+                        original_name = COALESCE(?3, original_name), profile_path = COALESCE(?4, profile_path)",
+                    params![person.id as i64, person.name, person.original_name, person.profile_path],
+                )?;
+                conn.execute(
+                    "INSERT OR IGNORE INTO credits (movie_id, person_id, role, character, job, credit_order)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![movie_id as i64, person.id as i64, role.as_str(), person.character, person.job, order as i64],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill the credits of every movie, for databases from before they existed.
+    fn rebuild_credits(conn: &Connection) -> Result<()> {
+        let rows: Vec<(i64, Option<String>, Option<String>)> = {
+            let mut stmt = conn.prepare("SELECT id, actors, directors FROM movies")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let parse = |json: Option<String>| -> Vec<Person> {
+            json.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+        };
+        for (id, actors, directors) in rows {
+            Self::sync_credits(conn, id as u64, &parse(actors), &parse(directors))?;
+        }
+        Ok(())
+    }
+
+    /// People credited on library titles, most titles first, optionally
+    /// only those whose name contains `query`.
+    pub fn get_people(&self, query: Option<&str>, limit: usize) -> Result<Vec<LibraryPerson>> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = query.map(str::trim).filter(|q| !q.is_empty()).map(like_pattern);
+        let mut stmt = conn.prepare(
+            "SELECT p.id, p.name, p.original_name, p.profile_path, COUNT(DISTINCT c.movie_id) AS movie_count
+             FROM people p JOIN credits c ON c.person_id = p.id
+             WHERE ?1 IS NULL OR p.name LIKE ?1 ESCAPE '\\' OR p.original_name LIKE ?1 ESCAPE '\\'
+             GROUP BY p.id ORDER BY movie_count DESC, p.name LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![pattern, limit as i64], |row| {
+            Ok(LibraryPerson {
+                id: row.get::<_, i64>("id")? as u64,
+                name: row.get("name")?,
+                original_name: row.get("original_name")?,
+                profile_path: row.get("profile_path")?,
+                movie_count: row.get::<_, i64>("movie_count")? as usize,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Library titles a person is credited on, newest first.
+    pub fn get_person_credits(&self, person_id: u64) -> Result<Vec<PersonCredit>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT m.*, c.role, c.character, c.job FROM credits c JOIN movies m ON m.id = c.movie_id
+             WHERE c.person_id = ?1 ORDER BY m.release_date DESC, c.role",
+        )?;
+        let rows = stmt.query_map(params![person_id as i64], |row| {
+            let role: String = row.get("role")?;
+            Ok(PersonCredit {
+                movie: Self::row_to_movie(row)?,
+                role: if role == "crew" { CreditRole::Crew } else { CreditRole::Cast },
+                character: row.get("character")?,
+                job: row.get("job")?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
     pub fn add_material(&self, movie_id: u64, material: Material) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         
@@ -465,8 +902,12 @@ impl Database {
         
         let mut materials: Vec<Material> = serde_json::from_str(&materials_json).unwrap_or_default();
         
-        if !materials.iter().any(|m| m.path == material.path) {
-            materials.push(material);
+        let path = material.path.clone();
+        let outcome = merge_material(&mut materials, material);
+        if let MergeOutcome::Moved(material_id) = &outcome {
+            Self::move_script_text(&conn, movie_id, material_id, &path)?;
+        }
+        if outcome != MergeOutcome::AlreadyLinked {
             let new_json = serde_json::to_string(&materials)?;
             conn.execute(
                 "UPDATE movies SET materials = ?1 WHERE id = ?2",
@@ -491,12 +932,20 @@ impl Database {
         let mut updated = false;
 
         for mat in new_materials {
-            if !materials.iter().any(|m| m.path == mat.path) {
-                println!("[DB] Adding new material: {}", mat.path);
-                materials.push(mat);
-                updated = true;
-            } else {
-                println!("[DB] Material already exists: {}", mat.path);
+            let path = mat.path.clone();
+            match merge_material(&mut materials, mat) {
+                MergeOutcome::Added => {
+                    println!("[DB] Adding new material: {}", path);
+                    updated = true;
+                }
+                MergeOutcome::Moved(material_id) => {
+                    println!("[DB] Relinked moved material: {}", path);
+                    Self::move_script_text(&conn, movie_id, &material_id, &path)?;
+                    updated = true;
+                }
+                MergeOutcome::AlreadyLinked => {
+                    println!("[DB] Material already exists: {}", path);
+                }
             }
         }
         
@@ -526,6 +975,10 @@ impl Database {
         let initial_len = materials.len();
         
         materials.retain(|m| m.id != material_id);
+        conn.execute(
+            "DELETE FROM script_texts WHERE movie_id = ?1 AND material_id = ?2",
+            params![movie_id as i64, material_id]
+        )?;
         
         if materials.len() != initial_len {
             let new_json = serde_json::to_string(&materials)?;
@@ -537,9 +990,535 @@ impl Database {
         Ok(())
     }
 
+    /// Store the probe result of one material; a no-op if it was unlinked meanwhile.
+    pub fn set_material_metadata(&self, movie_id: u64, material_id: &str, metadata: MaterialMetadata) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let materials_json: String = conn.query_row(
+            "SELECT materials FROM movies WHERE id = ?1",
+            params![movie_id as i64],
+            |row| row.get(0)
+        )?;
+
+        let mut materials: Vec<Material> = serde_json::from_str(&materials_json).unwrap_or_default();
+        if let Some(material) = materials.iter_mut().find(|m| m.id == material_id) {
+            material.metadata = Some(metadata);
+            conn.execute(
+                "UPDATE movies SET materials = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&materials)?, movie_id as i64]
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn save_script_text(&self, movie_id: u64, material_id: &str, path: &str, text: &str, char_count: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO script_texts (movie_id, material_id, path, text, char_count, extracted_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![movie_id as i64, material_id, path, text, char_count as i64, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_script_text(&self, movie_id: u64, material_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM script_texts WHERE movie_id = ?1 AND material_id = ?2", params![movie_id as i64, material_id])?;
+        Ok(())
+    }
+
+    /// Point a moved material's extracted text at its new path.
+    pub fn move_script_text_path(&self, movie_id: u64, material_id: &str, path: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        Self::move_script_text(&conn, movie_id, material_id, path)
+    }
+
+    fn move_script_text(conn: &Connection, movie_id: u64, material_id: &str, path: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE script_texts SET path = ?1 WHERE movie_id = ?2 AND material_id = ?3",
+            params![path, movie_id as i64, material_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn has_script_text(&self, movie_id: u64, material_id: &str) -> bool {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT 1 FROM script_texts WHERE movie_id = ?1 AND material_id = ?2",
+            params![movie_id as i64, material_id],
+            |_| Ok(()),
+        ).optional().unwrap_or(None).is_some()
+    }
+
+    fn script_from_row(row: &rusqlite::Row) -> rusqlite::Result<ScriptRow> {
+        Ok(ScriptRow {
+            movie_id: row.get::<_, i64>(0)? as u64,
+            material_id: row.get(1)?,
+            path: row.get(2)?,
+            text: row.get(3)?,
+            char_count: row.get::<_, i64>(4)? as u64,
+            extracted_at: row.get(5)?,
+        })
+    }
+
+    pub fn get_script_text(&self, movie_id: u64, material_id: &str) -> Option<ScriptRow> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT movie_id, material_id, path, text, char_count, extracted_at FROM script_texts WHERE movie_id = ?1 AND material_id = ?2",
+            params![movie_id as i64, material_id],
+            Self::script_from_row,
+        ).optional().unwrap_or(None)
+    }
+
+    /// Scripts whose text contains `query` (ASCII case-insensitive), or all of
+    /// them without a query; optionally only those of one movie.
+    pub fn search_scripts(&self, query: Option<&str>, movie_id: Option<u64>, limit: usize) -> Result<Vec<ScriptRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT movie_id, material_id, path, text, char_count, extracted_at FROM script_texts
+             WHERE (?1 IS NULL OR text LIKE ?1 ESCAPE '\\') AND (?2 IS NULL OR movie_id = ?2)
+             ORDER BY movie_id, path LIMIT ?3"
+        )?;
+        let rows = stmt.query_map(
+            params![query.map(like_pattern), movie_id.map(|id| id as i64), limit as i64],
+            Self::script_from_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Remember which file a preview key belongs to, for garbage collection.
+    pub fn save_preview_key(&self, key: &str, path: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO preview_cache (key, path, created_at) VALUES (?1, ?2, ?3)",
+            params![key, path, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_preview_keys(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, path FROM preview_cache")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn delete_preview_keys(&self, keys: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("DELETE FROM preview_cache WHERE key = ?1")?;
+            for key in keys {
+                stmt.execute(params![key])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn stored_image_from_row(row: &Row) -> rusqlite::Result<StoredImage> {
+        Ok(StoredImage {
+            hash: row.get("hash")?,
+            path: row.get("path")?,
+            url: row.get("url")?,
+            width: row.get("width")?,
+            height: row.get("height")?,
+            size: row.get::<_, i64>("size")? as u64,
+        })
+    }
+
+    pub fn save_stored_image(&self, image: &StoredImage) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO image_store (hash, path, url, width, height, size, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(hash) DO UPDATE SET path = ?2, url = COALESCE(?3, url)",
+            params![image.hash, image.path, image.url, image.width, image.height, image.size as i64, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn find_stored_image_by_url(&self, url: &str) -> Option<StoredImage> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT * FROM image_store WHERE url = ?1", params![url], Self::stored_image_from_row).ok()
+    }
+
+    pub fn add_image_ref(&self, movie_id: u64, hash: &str, kind: ImageKind) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO image_refs (movie_id, hash, kind) VALUES (?1, ?2, ?3)",
+            params![movie_id as i64, hash, kind.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Drop a movie's references to one image, or to all of its images.
+    pub fn remove_image_refs(&self, movie_id: u64, hash: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        match hash {
+            Some(hash) => conn.execute("DELETE FROM image_refs WHERE movie_id = ?1 AND hash = ?2", params![movie_id as i64, hash])?,
+            None => conn.execute("DELETE FROM image_refs WHERE movie_id = ?1", params![movie_id as i64])?,
+        };
+        Ok(())
+    }
+
+    pub fn get_movie_images(&self, movie_id: u64) -> Result<Vec<MovieImage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.kind, s.* FROM image_refs r JOIN image_store s ON s.hash = r.hash
+             WHERE r.movie_id = ?1 ORDER BY r.kind, s.created_at",
+        )?;
+        let rows = stmt.query_map(params![movie_id as i64], |row| {
+            let kind: String = row.get("kind")?;
+            Ok((kind, Self::stored_image_from_row(row)?))
+        })?;
+        let mut images = Vec::new();
+        for row in rows {
+            let (kind, image) = row?;
+            if let Some(kind) = ImageKind::parse(&kind) {
+                images.push(MovieImage { kind, image });
+            }
+        }
+        Ok(images)
+    }
+
+    /// Stored images no movie refers to any more.
+    pub fn get_unreferenced_images(&self) -> Result<Vec<StoredImage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM image_store WHERE hash NOT IN (SELECT hash FROM image_refs)")?;
+        let rows = stmt.query_map([], Self::stored_image_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn delete_stored_images(&self, hashes: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("DELETE FROM image_store WHERE hash = ?1")?;
+            for hash in hashes {
+                stmt.execute(params![hash])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replace the TMDB dates stored for a movie.
+    pub fn replace_calendar_events(&self, movie_id: u64, events: &[CalendarEvent]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM calendar_events WHERE movie_id = ?1", params![movie_id as i64])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO calendar_events (movie_id, kind, date, name, season, episode, region, release_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for event in events {
+                stmt.execute(params![
+                    movie_id as i64,
+                    event.kind.as_str(),
+                    event.date,
+                    event.name,
+                    event.season,
+                    event.episode,
+                    event.region,
+                    event.release_type
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stored TMDB dates between `start` and `end` (YYYY-MM-DD, both included).
+    /// Regional releases only count while the movie is still unmade.
+    pub fn get_calendar_events(&self, start: &str, end: &str) -> Result<Vec<CalendarEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT e.*, m.title FROM calendar_events e JOIN movies m ON m.id = e.movie_id
+             WHERE e.date >= ?1 AND e.date <= ?2
+                AND (e.kind <> 'regional_release' OR m.production_status = 'unmade')
+             ORDER BY e.date",
+        )?;
+        let rows = stmt.query_map(params![start, end], |row| {
+            let kind: String = row.get("kind")?;
+            let Some(kind) = CalendarEventKind::parse(&kind) else { return Ok(None) };
+            Ok(Some(CalendarEvent {
+                movie_id: row.get::<_, i64>("movie_id")? as u64,
+                title: row.get("title")?,
+                kind,
+                date: row.get("date")?,
+                name: row.get("name")?,
+                season: row.get("season")?,
+                episode: row.get("episode")?,
+                region: row.get("region")?,
+                release_type: row.get("release_type")?,
+            }))
+        })?;
+        let mut events = Vec::new();
+        for row in rows {
+            if let Some(event) = row? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// A cached API response body and when it was fetched (RFC 3339).
+    pub fn get_http_cache(&self, key: &str) -> Option<(String, String)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT body, fetched_at FROM http_cache WHERE key = ?1",
+            params![key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().unwrap_or(None)
+    }
+
+    pub fn save_http_cache(&self, key: &str, body: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO http_cache (key, body, fetched_at) VALUES (?1, ?2, ?3)",
+            params![key, body, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_cached_fingerprint(&self, path: &str, size: u64, modified: &str) -> Option<Fingerprint> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT partial_hash, full_hash FROM file_fingerprints WHERE path = ?1 AND size = ?2 AND modified = ?3",
+            params![path, size as i64, modified],
+            |row| Ok(Fingerprint { partial: row.get(0)?, full: row.get(1)? }),
+        ).optional().unwrap_or(None)
+    }
+
+    pub fn save_fingerprint(&self, path: &str, size: u64, modified: &str, fingerprint: &Fingerprint) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO file_fingerprints (path, size, modified, partial_hash, full_hash) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![path, size as i64, modified, fingerprint.partial, fingerprint.full],
+        )?;
+        Ok(())
+    }
+
+    /// Replace everything indexed under `root` (a normalized path) in one transaction.
+    pub fn replace_catalog(&self, root: &str, entries: &[CatalogEntry]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM file_catalog WHERE root = ?1", params![root])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO file_catalog (root, path, name_lower, size, modified) VALUES (?1, ?2, ?3, ?4, ?5)"
+            )?;
+            for e in entries {
+                stmt.execute(params![root, e.path, e.name.to_lowercase(), e.size as i64, e.modified])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO file_catalog_roots (root, indexed_at, file_count) VALUES (?1, ?2, ?3)",
+            params![root, chrono::Utc::now().to_rfc3339(), entries.len() as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_catalog_indexed_at(&self, root: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT indexed_at FROM file_catalog_roots WHERE root = ?1",
+            params![root],
+            |row| row.get(0),
+        ).optional().unwrap_or(None)
+    }
+
+    /// Files indexed under `root`, narrowed to names containing `like_lower`
+    /// when given. `visit` sees each one until it returns false.
+    pub fn search_catalog(
+        &self,
+        root: &str,
+        like_lower: Option<&str>,
+        visit: &mut dyn FnMut(CatalogEntry) -> bool,
+    ) -> Result<()> {
+        let mut after = 0i64;
+        loop {
+            // Visited without holding the lock, callers stat every hit
+            let batch: Vec<(i64, CatalogEntry)> = {
+                let conn = self.conn.lock().unwrap();
+                let mut stmt = conn.prepare(
+                    "SELECT rowid, path, size, modified FROM file_catalog
+                     WHERE root = ?1 AND (?2 IS NULL OR name_lower LIKE ?2 ESCAPE '\\') AND rowid > ?3
+                     ORDER BY rowid LIMIT ?4"
+                )?;
+                let rows = stmt.query_map(params![root, like_lower.map(like_pattern), after, SEARCH_BATCH as i64], |row| {
+                    let path: String = row.get(1)?;
+                    let name = std::path::Path::new(&path).file_name().unwrap_or_default().to_string_lossy().to_string();
+                    Ok((row.get(0)?, CatalogEntry {
+                        path,
+                        name,
+                        size: row.get::<_, i64>(2)? as u64,
+                        modified: row.get(3)?,
+                    }))
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            let Some((last, _)) = batch.last() else { return Ok(()) };
+            after = *last;
+            let complete = batch.len() < SEARCH_BATCH;
+            for (_, entry) in batch {
+                if !visit(entry) {
+                    return Ok(());
+                }
+            }
+            if complete {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Journal id and the USN the index of `volume` is up to date with.
+    pub fn get_usn_cursor(&self, volume: &str) -> Option<(u64, i64)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT journal_id, next_usn FROM usn_volumes WHERE volume = ?1",
+            params![volume],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)),
+        ).optional().unwrap_or(None)
+    }
+
+    /// Replace the whole index of a volume, e.g. after the journal was reset.
+    pub fn replace_usn_index(&self, volume: &str, journal_id: u64, next_usn: i64, entries: &[UsnIndexEntry]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM usn_entries WHERE volume = ?1", params![volume])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO usn_entries (volume, fid, parent_fid, name, name_lower, is_dir) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+            )?;
+            for e in entries {
+                stmt.execute(params![volume, e.fid as i64, e.parent_fid as i64, e.name, e.name.to_lowercase(), e.is_dir])?;
+            }
+        }
+        Self::save_usn_cursor(&tx, volume, journal_id, next_usn)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Apply journal records in order and move the cursor, all or nothing.
+    pub fn apply_usn_changes(&self, volume: &str, journal_id: u64, next_usn: i64, changes: &[UsnChange]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(
+                "INSERT OR REPLACE INTO usn_entries (volume, fid, parent_fid, name, name_lower, is_dir) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+            )?;
+            let mut delete = tx.prepare("DELETE FROM usn_entries WHERE volume = ?1 AND fid = ?2")?;
+            for change in changes {
+                match change {
+                    UsnChange::Upsert(e) => {
+                        upsert.execute(params![volume, e.fid as i64, e.parent_fid as i64, e.name, e.name.to_lowercase(), e.is_dir])?;
+                    }
+                    UsnChange::Delete(fid) => {
+                        delete.execute(params![volume, *fid as i64])?;
+                    }
+                }
+            }
+        }
+        Self::save_usn_cursor(&tx, volume, journal_id, next_usn)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_usn_cursor(conn: &Connection, volume: &str, journal_id: u64, next_usn: i64) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO usn_volumes (volume, journal_id, next_usn, indexed_at) VALUES (?1, ?2, ?3, ?4)",
+            params![volume, journal_id as i64, next_usn, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Entries on `volume` that `accept_name` lets through (given the name
+    /// and whether it is a folder), narrowed in SQL to names containing
+    /// `like_lower` when given. Each is handed to `visit` as a full path,
+    /// built by following parent ids up to the volume root, until it returns false.
+    pub fn search_usn_index(
+        &self,
+        volume: &str,
+        volume_root: &str,
+        like_lower: Option<&str>,
+        include_dirs: bool,
+        accept_name: &dyn Fn(&str, bool) -> bool,
+        visit: &mut dyn FnMut(String, bool) -> bool,
+    ) -> Result<()> {
+        let mut dirs: std::collections::HashMap<i64, Option<(i64, String)>> = std::collections::HashMap::new();
+        let mut after = 0i64;
+        loop {
+            // Paths are built under the lock, visited without it: callers stat every hit
+            let (found, last, complete) = {
+                let conn = self.conn.lock().unwrap();
+                let mut stmt = conn.prepare(
+                    "SELECT rowid, fid, parent_fid, name, is_dir FROM usn_entries
+                     WHERE volume = ?1 AND (?2 OR is_dir = 0) AND (?3 IS NULL OR name_lower LIKE ?3 ESCAPE '\\') AND rowid > ?4
+                     ORDER BY rowid LIMIT ?5"
+                )?;
+                let mut parent_stmt = conn.prepare("SELECT parent_fid, name FROM usn_entries WHERE volume = ?1 AND fid = ?2")?;
+
+                let hits = stmt.query_map(params![volume, include_dirs, like_lower.map(like_pattern), after, SEARCH_BATCH as i64], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, String>(3)?, row.get::<_, bool>(4)?))
+                })?.collect::<rusqlite::Result<Vec<_>>>()?;
+                let last = hits.last().map(|h| h.0);
+                let complete = hits.len() < SEARCH_BATCH;
+
+                let mut found = Vec::new();
+                for (_, fid, parent, name, is_dir) in hits {
+                    if !accept_name(&name, is_dir) {
+                        continue;
+                    }
+                    let mut parts = vec![name];
+                    let mut current = parent;
+                    while current != fid && parts.len() < 256 {
+                        let entry = match dirs.get(&current) {
+                            Some(e) => e.clone(),
+                            None => {
+                                let e = parent_stmt.query_row(params![volume, current], |row| Ok((row.get(0)?, row.get(1)?))).optional()?;
+                                dirs.insert(current, e.clone());
+                                e
+                            }
+                        };
+                        // The root folder is its own parent (or not indexed at all)
+                        let Some((grandparent, dir_name)) = entry else { break };
+                        if grandparent == current {
+                            break;
+                        }
+                        parts.push(dir_name);
+                        current = grandparent;
+                    }
+                    parts.reverse();
+                    found.push((format!("{}{}", volume_root, parts.join("\\")), is_dir));
+                }
+                (found, last, complete)
+            };
+
+            let Some(last) = last else { return Ok(()) };
+            for (path, is_dir) in found {
+                if !visit(path, is_dir) {
+                    return Ok(());
+                }
+            }
+            if complete {
+                return Ok(());
+            }
+            after = last;
+        }
+    }
+
     pub fn clear_all_data(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM movies", [])?;
+        // Movie ids are reused, nothing may point at the removed ones
+        conn.execute("DELETE FROM image_refs", [])?;
+        conn.execute("DELETE FROM script_texts", [])?;
+        conn.execute("DELETE FROM credits", [])?;
+        conn.execute("DELETE FROM people", [])?;
+        conn.execute("DELETE FROM calendar_events", [])?;
         // Optional: Reset settings? Keeping settings is usually better.
         // conn.execute("DELETE FROM settings", [])?;
         Ok(())
@@ -551,6 +1530,44 @@ impl Database {
         Ok(())
     }
 
+    /// Columns of `table` in both the main and the attached `restore_db`
+    /// database, quoted for SQL.
+    fn shared_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+        let columns = |schema: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        };
+        let backup = columns("restore_db")?;
+        Ok(columns("main")?.into_iter()
+            .filter(|c| backup.contains(c))
+            .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
+            .collect())
+    }
+
+    /// Count the stored images the movies' artwork fields point at.
+    fn rebuild_image_refs(conn: &Connection) -> Result<()> {
+        type Artwork = (i64, Option<String>, Option<String>, Option<String>, Option<String>);
+        let rows: Vec<Artwork> = {
+            let mut stmt = conn.prepare("SELECT id, poster_path, backdrop_path, logo_path, posters FROM movies")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let mut stmt = conn.prepare(
+            "INSERT OR IGNORE INTO image_refs (movie_id, hash, kind) SELECT ?1, hash, ?2 FROM image_store WHERE path = ?3",
+        )?;
+        for (id, poster, backdrop, logo, posters) in rows {
+            let posters: Vec<String> = posters.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default();
+            let images = poster.into_iter().chain(posters).map(|p| (ImageKind::Poster, p))
+                .chain(backdrop.map(|p| (ImageKind::Backdrop, p)))
+                .chain(logo.map(|p| (ImageKind::Logo, p)));
+            for (kind, path) in images {
+                stmt.execute(params![id, kind.as_str(), path])?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn restore(&self, path: &str) -> Result<()> {
         let mut conn = self.conn.lock().unwrap();
         
@@ -583,9 +1600,30 @@ impl Database {
             let tx = conn.transaction()?;
             tx.execute("DELETE FROM movies", [])?;
             tx.execute("DELETE FROM settings", [])?;
+            // The backup's movies reuse the ids, nothing may point at the current ones
+            tx.execute("DELETE FROM image_refs", [])?;
+            tx.execute("DELETE FROM script_texts", [])?;
+            tx.execute("DELETE FROM calendar_events", [])?;
             
-            tx.execute("INSERT INTO main.movies SELECT * FROM restore_db.movies", [])?;
+            // By name: backups from before a column was added have fewer,
+            // and databases upgraded in place have them in another order
+            let columns = Self::shared_columns(&tx, "movies")?.join(", ");
+            tx.execute(&format!("INSERT INTO main.movies ({0}) SELECT {0} FROM restore_db.movies", columns), [])?;
             tx.execute("INSERT INTO main.settings SELECT * FROM restore_db.settings", [])?;
+            // The backup's artwork bookkeeping, when it is new enough to have it
+            let columns = Self::shared_columns(&tx, "image_store")?.join(", ");
+            if !columns.is_empty() {
+                tx.execute(&format!("INSERT OR IGNORE INTO main.image_store ({0}) SELECT {0} FROM restore_db.image_store", columns), [])?;
+            }
+            if !Self::shared_columns(&tx, "image_refs")?.is_empty() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO main.image_refs (movie_id, hash, kind)
+                     SELECT movie_id, hash, kind FROM restore_db.image_refs WHERE movie_id IN (SELECT id FROM main.movies)",
+                    [],
+                )?;
+            }
+            // Older backups have no refs; without them GC would delete the restored artwork
+            Self::rebuild_image_refs(&tx)?;
             
             tx.commit()?;
             Ok(())
@@ -593,8 +1631,11 @@ impl Database {
         
         // Always detach
         conn.execute("DETACH DATABASE restore_db", [])?;
-        
-        result
+        result?;
+
+        conn.execute("DELETE FROM credits", [])?;
+        conn.execute("DELETE FROM people", [])?;
+        Self::rebuild_credits(&conn)
     }
 
     pub fn clear_cache(&self) -> Result<()> {
@@ -607,6 +1648,16 @@ impl Database {
             fs::remove_dir_all(&images_dir)?;
             fs::create_dir_all(&images_dir)?;
         }
+        // Material previews are regenerated on demand
+        let previews_dir = self.root_dir.join("previews");
+        if previews_dir.exists() {
+            fs::remove_dir_all(&previews_dir)?;
+        }
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM preview_cache", [])?;
+        conn.execute("DELETE FROM http_cache", [])?;
+        conn.execute("DELETE FROM image_store", [])?;
+        conn.execute("DELETE FROM image_refs", [])?;
         Ok(())
     }
 }
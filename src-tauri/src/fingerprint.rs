@@ -0,0 +1,149 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
+use crate::db::Database;
+use crate::paths;
+use crate::models::Material;
+
+/// Bytes hashed from the head, middle and tail of a file for the quick fingerprint.
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    /// `<size>:<xxh3-128 of head/middle/tail>`, cheap enough for every scan
+    pub partial: String,
+    /// `<size>:<xxh3-128 of the whole file>`, only computed on request
+    pub full: Option<String>,
+}
+
+fn format_hash(size: u64, hash: u128) -> String {
+    format!("{}:{:032x}", size, hash)
+}
+
+/// Hash the size plus three blocks of the file. Small files are hashed whole,
+/// so for them the partial and full fingerprints are the same.
+pub fn partial_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    if size <= BLOCK_SIZE * 3 {
+        let mut buf = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    } else {
+        let mut buf = vec![0u8; BLOCK_SIZE as usize];
+        for offset in [0, size / 2 - BLOCK_SIZE / 2, size - BLOCK_SIZE] {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut buf)?;
+            hasher.update(&buf);
+        }
+    }
+
+    Ok(format_hash(size, hasher.digest128()))
+}
+
+pub fn full_hash(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format_hash(size, hasher.digest128()))
+}
+
+/// Fingerprint a file, reusing the cached hashes as long as size and mtime are unchanged.
+pub fn fingerprint_cached(db: &Database, path: &Path, with_full: bool) -> io::Result<Fingerprint> {
+    let meta = fs::metadata(path)?;
+    let size = meta.len();
+    let modified = meta
+        .modified()
+        .ok()
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+        .unwrap_or_default();
+    let path_str = path.to_string_lossy().to_string();
+
+    let cached = db.get_cached_fingerprint(&path_str, size, &modified);
+    let (partial, full) = match cached {
+        Some(fp) if !with_full || fp.full.is_some() => return Ok(fp),
+        Some(fp) => (fp.partial, None),
+        None => (partial_hash(path)?, None),
+    };
+    let full = match full {
+        Some(f) => Some(f),
+        None if with_full => Some(full_hash(path)?),
+        None => None,
+    };
+
+    let fp = Fingerprint { partial, full };
+    if let Err(e) = db.save_fingerprint(&path_str, size, &modified, &fp) {
+        eprintln!("Failed to cache fingerprint for {}: {}", path_str, e);
+    }
+    Ok(fp)
+}
+
+/// Fill in the fingerprint of a material that doesn't have one yet.
+pub fn ensure_material_fingerprint(db: &Database, material: &mut Material) {
+    if material.fingerprint.is_some() {
+        return;
+    }
+    match fingerprint_cached(db, Path::new(&material.path), false) {
+        Ok(fp) => material.fingerprint = Some(fp.partial),
+        Err(e) => eprintln!("Failed to fingerprint {}: {}", material.path, e),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MergeOutcome {
+    Added,
+    /// An existing material (this id) whose file disappeared was found again
+    /// at a new path
+    Moved(String),
+    AlreadyLinked,
+}
+
+/// Add `material` to `materials`, unless it is already there. A file whose
+/// fingerprint matches a material that no longer exists on disk is treated as
+/// that material having been moved or renamed: it keeps its id, category and
+/// add time, only its location changes. An added material whose id (its
+/// path when it was found) is taken, by a material that moved away from
+/// that path, gets a suffixed one.
+pub fn merge_material(materials: &mut Vec<Material>, mut material: Material) -> MergeOutcome {
+    if materials.iter().any(|m| paths::same_path(&m.path, &material.path)) {
+        return MergeOutcome::AlreadyLinked;
+    }
+
+    if let Some(fp) = &material.fingerprint {
+        let moved = materials.iter_mut().find(|m| {
+            m.fingerprint.as_ref() == Some(fp) && !Path::new(&m.path).exists()
+        });
+        if let Some(existing) = moved {
+            println!("[MATERIAL] Detected move: {} -> {}", existing.path, material.path);
+            existing.path = material.path;
+            existing.name = material.name;
+            existing.size = material.size;
+            existing.modified_time = material.modified_time;
+            return MergeOutcome::Moved(existing.id.clone());
+        }
+    }
+
+    let base = material.id.clone();
+    let mut n = 1;
+    while materials.iter().any(|m| m.id == material.id) {
+        n += 1;
+        material.id = format!("{}#{}", base, n);
+    }
+    materials.push(material);
+    MergeOutcome::Added
+}
@@ -0,0 +1,61 @@
+use regex::{Regex, RegexBuilder};
+use crate::paths::PathStyle;
+
+/// Translate a shell/gitignore style glob into a regex body (no anchors).
+///
+/// `*` and `?` stop at `/`, `**` crosses directories, `**/` also matches
+/// zero directories and `[...]` / `[!...]` are character classes. Paths are
+/// expected to use `/` as separator.
+pub fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut re = String::with_capacity(glob.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    re.push_str("(?:.*/)?");
+                    i += 3;
+                } else {
+                    re.push_str(".*");
+                    i += 2;
+                }
+                continue;
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                if let Some(len) = chars[i + 1..].iter().position(|c| *c == ']') {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let class = match class.strip_prefix('!') {
+                        Some(rest) => format!("^{}", rest),
+                        None => class,
+                    };
+                    re.push('[');
+                    re.push_str(&class.replace('\\', "\\\\"));
+                    re.push(']');
+                    i += len + 2;
+                    continue;
+                }
+                re.push_str("\\[");
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    re
+}
+
+/// Compile a glob that must match the whole input.
+pub fn compile(glob: &str, case_insensitive: bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&format!("^{}$", glob_to_regex(glob)))
+        .case_insensitive(case_insensitive)
+        .build()
+}
+
+/// Whether globs on this platform should ignore case (matching the file system).
+pub fn default_case_insensitive() -> bool {
+    PathStyle::current().case_insensitive()
+}
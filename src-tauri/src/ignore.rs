@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use regex::Regex;
+use walkdir::{DirEntry, WalkDir};
+use crate::glob;
+use crate::models::AppConfig;
+
+/// Name of the per-folder ignore file, gitignore syntax.
+pub const IGNORE_FILE_NAME: &str = ".zqignore";
+
+/// Folders that are never worth walking, whatever the config says.
+const BUILTIN_SKIPPED_DIRS: &[&str] = &["System Volume Information", "$RECYCLE.BIN", "node_modules"];
+
+/// One line of a `.zqignore` file or one global exclude pattern.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns containing a `/` match the path relative to the ignore file,
+    /// the others match the entry name at any depth.
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let pattern = pattern.replace('\\', "/");
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let regex = glob::compile(pattern, glob::default_case_insensitive()).ok()?;
+        Some(Self { regex, negated, dir_only, anchored })
+    }
+
+    /// `rel` is relative to the folder holding the rule, `/` separated.
+    fn matches(&self, rel: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.regex.is_match(rel)
+        } else {
+            let name = rel.rsplit('/').next().unwrap_or(rel);
+            self.regex.is_match(name)
+        }
+    }
+}
+
+fn parse_rules<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<IgnoreRule> {
+    lines.filter_map(IgnoreRule::parse).collect()
+}
+
+fn relative_slash_path(path: &Path, base: &Path) -> Option<String> {
+    let rel = path.strip_prefix(base).ok()?;
+    let parts: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    Some(parts.join("/"))
+}
+
+/// Decides which entries a scan of the monitored folders looks at: built-in
+/// system folders, `.zqignore` files found while walking, the global exclude
+/// globs and the size/age limits from [`AppConfig`].
+#[derive(Debug, Clone, Default)]
+pub struct ScanFilter {
+    excludes: Vec<IgnoreRule>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+}
+
+impl ScanFilter {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            excludes: parse_rules(config.exclude_patterns.iter().map(|s| s.as_str())),
+            min_size: config.min_file_size,
+            max_size: config.max_file_size,
+            max_age: config.max_file_age_days.map(|d| Duration::from_secs(d.saturating_mul(86_400))),
+        }
+    }
+
+    /// Walk `root`, skipping ignored folders entirely and yielding only the
+    /// files and folders that pass every rule.
+    pub fn walk<'a>(&'a self, root: &'a Path) -> impl Iterator<Item = DirEntry> + 'a {
+        let mut ignore_files = IgnoreFiles::default();
+        WalkDir::new(root)
+            .into_iter()
+            .filter_entry(move |e| e.depth() == 0 || !self.is_ignored(root, e, &mut ignore_files))
+            .filter_map(|e| e.ok())
+            .filter(move |e| !e.file_type().is_file() || self.accepts_metadata(e.metadata().ok().as_ref()))
+    }
+
+    fn is_ignored(&self, root: &Path, entry: &DirEntry, ignore_files: &mut IgnoreFiles) -> bool {
+        let name = entry.file_name().to_string_lossy();
+        let is_dir = entry.file_type().is_dir();
+        if name.starts_with('.') || (is_dir && BUILTIN_SKIPPED_DIRS.contains(&name.as_ref())) {
+            return true;
+        }
+
+        let path = entry.path();
+        let mut ignored = false;
+
+        if let Some(rel) = relative_slash_path(path, root) {
+            for rule in &self.excludes {
+                if rule.matches(&rel, is_dir) {
+                    ignored = !rule.negated;
+                }
+            }
+        }
+
+        // Deeper ignore files win over shallower ones, later lines over earlier ones
+        let mut dirs: Vec<&Path> = path.ancestors().skip(1).take_while(|d| d.starts_with(root)).collect();
+        dirs.reverse();
+        for dir in dirs {
+            let rules = ignore_files.rules_for(dir);
+            if rules.is_empty() {
+                continue;
+            }
+            if let Some(rel) = relative_slash_path(path, dir) {
+                for rule in rules {
+                    if rule.matches(&rel, is_dir) {
+                        ignored = !rule.negated;
+                    }
+                }
+            }
+        }
+
+        ignored
+    }
+
+    /// Size and age checks for a file; files whose metadata can't be read pass.
+    pub fn accepts_metadata(&self, metadata: Option<&fs::Metadata>) -> bool {
+        let Some(meta) = metadata else {
+            return true;
+        };
+        let size = meta.len();
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+        if let (Some(max_age), Ok(modified)) = (self.max_age, meta.modified()) {
+            if let Ok(age) = SystemTime::now().duration_since(modified) {
+                if age > max_age {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Lazily loaded `.zqignore` rules, one entry per folder visited.
+#[derive(Default)]
+struct IgnoreFiles {
+    by_dir: HashMap<PathBuf, Vec<IgnoreRule>>,
+}
+
+impl IgnoreFiles {
+    fn rules_for(&mut self, dir: &Path) -> &[IgnoreRule] {
+        self.by_dir.entry(dir.to_path_buf()).or_insert_with(|| {
+            fs::read_to_string(dir.join(IGNORE_FILE_NAME))
+                .map(|content| parse_rules(content.lines()))
+                .unwrap_or_default()
+        })
+    }
+}
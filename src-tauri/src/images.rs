@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use imagesize::ImageType;
+use xxhash_rust::xxh3::xxh3_128;
+use crate::db::Database;
+use crate::models::{AppConfig, ImageKind, StoredImage};
+
+const DOWNLOAD_TIMEOUT_SECS: u64 = 30;
+const KNOWN_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+
+/// Downloaded artwork is stored once per content, as
+/// `<image folder>/store/<first two hash digits>/<hash>.<ext>`, so two
+/// sources that both call their file `cover.jpg` never collide. Which movies
+/// use an image is kept in `image_refs`; images no movie refers to any more
+/// are removed by [`collect_garbage`].
+pub fn store_dir(db: &Database, config: &AppConfig) -> PathBuf {
+    let root = config.image_save_path.as_ref()
+        .filter(|p| !p.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| db.get_root_dir().join("images"));
+    root.join("store")
+}
+
+/// The file extension for the image data, from its header, else from the URL.
+fn extension(bytes: &[u8], url: &str) -> &'static str {
+    match imagesize::image_type(bytes) {
+        Ok(ImageType::Jpeg) => "jpg",
+        Ok(ImageType::Png) => "png",
+        Ok(ImageType::Webp) => "webp",
+        Ok(ImageType::Gif) => "gif",
+        Ok(ImageType::Bmp) => "bmp",
+        _ => {
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            KNOWN_EXTENSIONS.iter().find(|k| **k == ext).copied().unwrap_or("jpg")
+        }
+    }
+}
+
+fn client(config: &AppConfig) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(DOWNLOAD_TIMEOUT_SECS));
+    if let Some(proxy_url) = config.proxy.as_ref().filter(|p| !p.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| format!("Proxy config error: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Put image data into the store, or find the copy already there.
+pub fn store_bytes(db: &Database, bytes: &[u8], url: Option<&str>) -> Result<StoredImage, String> {
+    let config = db.get_config();
+    let hash = format!("{:032x}", xxh3_128(bytes));
+    let dir = store_dir(db, &config).join(&hash[..2]);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let path = dir.join(format!("{}.{}", hash, extension(bytes, url.unwrap_or(""))));
+    if !path.is_file() {
+        // Move into place once complete, an interrupted write never leaves half an image
+        let partial = path.with_extension("part");
+        fs::write(&partial, bytes).map_err(|e| e.to_string())?;
+        fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+    }
+
+    let dimensions = imagesize::blob_size(bytes).ok();
+    let image = StoredImage {
+        hash,
+        path: path.to_string_lossy().to_string(),
+        url: url.map(str::to_string),
+        width: dimensions.map(|d| d.width as u32),
+        height: dimensions.map(|d| d.height as u32),
+        size: bytes.len() as u64,
+    };
+    db.save_stored_image(&image).map_err(|e| e.to_string())?;
+    Ok(image)
+}
+
+/// Download `url` into the store, unless it was downloaded before.
+pub async fn fetch(db: &Database, url: &str) -> Result<StoredImage, String> {
+    if let Some(image) = db.find_stored_image_by_url(url).filter(|i| Path::new(&i.path).is_file()) {
+        return Ok(image);
+    }
+    let response = client(&db.get_config())?.get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Image download returned status: {}", response.status()));
+    }
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    store_bytes(db, &bytes, Some(url))
+}
+
+/// A local copy of a remote image, counted as one of the movie's images.
+/// None when images aren't saved locally, `url` is local already or the
+/// download failed.
+pub async fn save_for_movie(db: &Database, movie_id: u64, url: &str, kind: ImageKind) -> Option<String> {
+    if !db.get_config().save_images_locally || !url.starts_with("http") {
+        return None;
+    }
+    match fetch(db, url).await {
+        Ok(image) => {
+            if let Err(e) = db.add_image_ref(movie_id, &image.hash, kind) {
+                eprintln!("[Images] Failed to count {} for movie {}: {}", image.hash, movie_id, e);
+            }
+            Some(image.path)
+        }
+        Err(e) => {
+            eprintln!("[Images] Failed to download {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Delete the stored images no movie refers to any more. Returns how many
+/// files were removed.
+pub fn collect_garbage(db: &Database) -> Result<usize, String> {
+    let unused = db.get_unreferenced_images().map_err(|e| e.to_string())?;
+    if unused.is_empty() {
+        return Ok(0);
+    }
+    let removed = unused.iter().filter(|i| fs::remove_file(&i.path).is_ok()).count();
+    let hashes: Vec<String> = unused.into_iter().map(|i| i.hash).collect();
+    db.delete_stored_images(&hashes).map_err(|e| e.to_string())?;
+    Ok(removed)
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Checked by long-running jobs between units of work.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Running background jobs, so the UI can cancel them by id. Managed as
+/// Tauri state next to the [`Database`](crate::db::Database).
+#[derive(Debug, Clone, Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<HashMap<String, CancelToken>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl JobRegistry {
+    /// Register a job. The caller may pick the id (so it can cancel before
+    /// the command returns), otherwise one is generated.
+    pub fn start(&self, id: Option<String>) -> (String, CancelToken) {
+        let id = id.unwrap_or_else(|| {
+            format!("job-{}-{}", chrono::Utc::now().timestamp_millis(), self.next_id.fetch_add(1, Ordering::Relaxed))
+        });
+        let token = CancelToken::default();
+        self.jobs.lock().unwrap().insert(id.clone(), token.clone());
+        (id, token)
+    }
+
+    /// Returns false when no job with that id is running.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(id) {
+            Some(token) => {
+                token.0.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, id: &str) {
+        self.jobs.lock().unwrap().remove(id);
+    }
+}
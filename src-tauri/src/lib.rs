@@ -2,8 +2,24 @@ mod db;
 mod models;
 mod commands;
 mod drag;
+mod fingerprint;
+mod category;
+mod glob;
+mod ignore;
+mod jobs;
+mod matching;
+mod paths;
+mod search;
+mod metadata;
+mod preview;
+mod tmdb;
+mod providers;
+mod library;
+mod images;
+mod calendar;
 
 use db::Database;
+use jobs::JobRegistry;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -14,6 +30,7 @@ fn greet(name: &str) -> String {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let db = Database::new().expect("Failed to initialize database");
+    let tmdb_client = tmdb::TmdbClient::new(db.clone());
 
     tauri::Builder::default()
         .register_asynchronous_uri_scheme_protocol("asset", |_ctx, request, responder| {
@@ -57,29 +74,67 @@ pub fn run() {
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .manage(db)
+        .manage(tmdb_client.clone())
+        .manage(db.clone())
+        .manage(JobRegistry::default())
+        .setup(move |app| {
+            calendar::spawn_daily_refresh(app.handle().clone(), db, tmdb_client);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::get_movies,
             commands::read_image,
             commands::get_app_info,
             commands::add_movie,
+            commands::resolve_title_list,
+            commands::add_movies,
             commands::auto_match_movie,
+            commands::auto_match_all,
             commands::delete_movie,
             commands::update_movie,
             commands::update_movie_status,
             commands::get_movie_details,
+            commands::download_movie_images,
+            commands::save_movie_image,
+            commands::get_movie_images,
+            commands::set_primary_image,
+            commands::remove_movie_image,
+            commands::get_people,
+            commands::get_person_movies,
+            commands::get_person_filmography,
+            commands::get_calendar_events,
+            commands::refresh_calendar,
             commands::get_tmdb_details,
+            commands::find_tmdb_by_imdb,
             commands::get_config,
             commands::save_config,
+            commands::test_category_rules,
+            commands::cancel_job,
+            commands::search_tmdb,
             commands::search_tmdb_movies,
             commands::test_tmdb_connection,
             commands::scan_directories,
+            commands::find_duplicate_files,
+            commands::find_orphan_files,
             commands::refresh_movie_materials,
             commands::add_material_to_movie,
+            commands::assign_materials,
             commands::remove_material_from_movie,
+            commands::refresh_material_metadata,
+            commands::filter_materials,
+            commands::filter_movies,
+            commands::export_movies,
+            commands::search_scripts,
+            commands::get_script,
             commands::open_file_with_player,
             commands::fetch_douban_subject,
+            commands::refresh_movie_metadata,
+            commands::search_metadata,
+            commands::get_metadata_images,
+            commands::refresh_library_metadata,
+            commands::apply_metadata_changes,
+            commands::unlock_movie_fields,
             commands::scan_for_movies,
             commands::clear_data,
             commands::backup_database,
@@ -92,6 +147,8 @@ pub fn run() {
             commands::rename_file_direct,
             commands::check_ffmpeg,
             commands::get_media_info,
+            commands::get_material_preview,
+            commands::clean_preview_cache,
             commands::convert_video,
             commands::extract_audio,
             commands::extract_subtitles,
@@ -106,6 +163,8 @@ pub fn run() {
             commands::install_whisper,
             commands::run_whisper,
             commands::search_usn_journal,
+            commands::search_files,
+            commands::rebuild_search_catalog,
             commands::open_directory,
             commands::save_audio_preset,
             commands::get_audio_presets,
@@ -0,0 +1,89 @@
+use crate::models::{ExportFormat, Movie, MovieFilter};
+
+fn same(value: Option<&str>, wanted: &Option<String>) -> bool {
+    wanted.as_ref().is_none_or(|w| value.is_some_and(|v| v.eq_ignore_ascii_case(w)))
+}
+
+fn matches(movie: &Movie, filter: &MovieFilter) -> bool {
+    let year = movie.release_date.as_deref()
+        .and_then(|d| d.get(..4))
+        .and_then(|y| y.parse::<u32>().ok());
+    let in_range = |value: Option<u64>, min: Option<u64>, max: Option<u64>| {
+        (min.is_none() && max.is_none())
+            || value.is_some_and(|v| min.is_none_or(|m| v >= m) && max.is_none_or(|m| v <= m))
+    };
+
+    same(movie.category.as_deref(), &filter.category)
+        && same(movie.production_status.as_deref(), &filter.production_status)
+        && same(movie.original_language.as_deref(), &filter.original_language)
+        && same(movie.certification.as_deref(), &filter.certification)
+        && filter.genre.as_ref().is_none_or(|g| movie.genres.iter().any(|x| x.eq_ignore_ascii_case(g)))
+        && filter.country.as_ref().is_none_or(|c| movie.countries.iter().any(|x| x.eq_ignore_ascii_case(c)))
+        && filter.collection_id.is_none_or(|id| movie.collection.as_ref().is_some_and(|c| c.id == id))
+        && in_range(movie.runtime, filter.min_runtime, filter.max_runtime)
+        && in_range(year.map(u64::from), filter.year_from.map(u64::from), filter.year_to.map(u64::from))
+        && filter.has_imdb_id.is_none_or(|has| movie.imdb_id.is_some() == has)
+}
+
+pub fn filter_movies(movies: Vec<Movie>, filter: &MovieFilter) -> Vec<Movie> {
+    movies.into_iter().filter(|m| matches(m, filter)).collect()
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "id", "title", "original_title", "category", "release_date", "runtime", "genres", "countries",
+    "original_language", "certification", "collection", "directors", "vote_average",
+    "production_status", "imdb_id", "tvdb_id", "tmdb_id", "douban_id", "add_time",
+];
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(movie: &Movie) -> String {
+    let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+    let num = |v: Option<u64>| v.map(|n| n.to_string()).unwrap_or_default();
+    let values = [
+        movie.id.to_string(),
+        movie.title.clone(),
+        opt(&movie.original_title),
+        opt(&movie.category),
+        opt(&movie.release_date),
+        num(movie.runtime),
+        movie.genres.join(" / "),
+        movie.countries.join(" / "),
+        opt(&movie.original_language),
+        opt(&movie.certification),
+        movie.collection.as_ref().map(|c| c.name.clone()).unwrap_or_default(),
+        movie.directors.iter().map(|d| d.name.as_str()).collect::<Vec<_>>().join(" / "),
+        movie.vote_average.map(|v| v.to_string()).unwrap_or_default(),
+        opt(&movie.production_status),
+        opt(&movie.imdb_id),
+        num(movie.tvdb_id),
+        num(movie.tmdb_id),
+        opt(&movie.douban_id),
+        movie.add_time.clone(),
+    ];
+    values.iter().map(|v| csv_field(v)).collect::<Vec<_>>().join(",")
+}
+
+/// The movies as CSV (with a BOM, so Excel reads the UTF-8 titles) or as
+/// the same JSON `get_movies` returns.
+pub fn export(movies: &[Movie], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(movies).map_err(|e| e.to_string()),
+        ExportFormat::Csv => {
+            let mut out = String::from("\u{feff}");
+            out.push_str(&CSV_COLUMNS.join(","));
+            out.push_str("\r\n");
+            for movie in movies {
+                out.push_str(&csv_row(movie));
+                out.push_str("\r\n");
+            }
+            Ok(out)
+        }
+    }
+}
@@ -0,0 +1,320 @@
+pub mod release;
+
+use crate::models::{ComponentScore, MatchComponent, MatchDetail, MatchMethod};
+use release::ParsedName;
+
+/// Score added when the year in a name agrees with the movie's release year.
+const YEAR_BONUS: f64 = 0.05;
+/// Score removed when the year in a name clearly disagrees (more than one year off).
+const YEAR_PENALTY: f64 = 0.15;
+
+/// The titles (title, original title, aliases) and year a file is scored against.
+#[derive(Debug, Clone)]
+pub struct MatchTarget {
+    /// (title as entered, lowercased title)
+    titles: Vec<(String, String)>,
+    year: Option<u16>,
+}
+
+impl MatchTarget {
+    pub fn new(titles: &[String], year: Option<u16>) -> Self {
+        let titles = titles
+            .iter()
+            .map(|t| (t.trim().to_string(), t.trim().to_lowercase()))
+            .filter(|(_, lower)| !lower.is_empty())
+            .collect();
+        Self { titles, year }
+    }
+}
+
+fn similarity_with_method(name_lower: &str, title_lower: &str) -> (f64, MatchMethod) {
+    if name_lower.is_empty() || title_lower.is_empty() {
+        return (0.0, MatchMethod::JaroWinkler);
+    }
+    if name_lower == title_lower {
+        (1.0, MatchMethod::Exact)
+    } else if name_lower.contains(title_lower) || title_lower.contains(name_lower) {
+        (0.95, MatchMethod::Contains)
+    } else {
+        (strsim::jaro_winkler(name_lower, title_lower), MatchMethod::JaroWinkler)
+    }
+}
+
+/// A name with its lowercase form and parsed release components, so it can
+/// be scored against many targets without re-parsing.
+#[derive(Debug, Clone)]
+struct PreparedName {
+    value: String,
+    raw: String,
+    parsed: ParsedName,
+    parsed_title: String,
+}
+
+impl PreparedName {
+    fn new(name: &str) -> Self {
+        let parsed = release::parse(name);
+        Self {
+            value: name.to_string(),
+            raw: name.trim().to_lowercase(),
+            parsed_title: parsed.title.to_lowercase(),
+            parsed,
+        }
+    }
+}
+
+/// A file stem and the folders between the scan root and the file, parsed
+/// once for scoring against every movie of the library.
+#[derive(Debug, Clone)]
+pub struct PreparedPath {
+    stem: PreparedName,
+    dirs: Vec<PreparedName>,
+}
+
+impl PreparedPath {
+    pub fn new(stem: &str, dirs: &[String]) -> Self {
+        Self {
+            stem: PreparedName::new(stem),
+            dirs: dirs.iter().map(|d| PreparedName::new(d)).collect(),
+        }
+    }
+}
+
+/// How a name compares to its closest title, before the year is looked at.
+struct TitleMatch {
+    similarity: f64,
+    title: usize, // Index into the target's titles
+    parsed: bool, // Won by the title parsed out of the name
+    method: MatchMethod,
+}
+
+/// The target's title closest to `name`, the first of equally close ones.
+///
+/// Both the raw name and the title parsed out of it are compared, so release
+/// noise like `2160p.WEB-DL.H265-XXX` no longer drags the score down.
+fn best_title(name: &PreparedName, target: &MatchTarget) -> Option<TitleMatch> {
+    let mut best: Option<TitleMatch> = None;
+    for (i, (_, title_lower)) in target.titles.iter().enumerate() {
+        let (raw_sim, raw_method) = similarity_with_method(&name.raw, title_lower);
+        let (parsed_sim, parsed_method) = similarity_with_method(&name.parsed_title, title_lower);
+        let candidate = if parsed_sim > raw_sim {
+            TitleMatch { similarity: parsed_sim, title: i, parsed: true, method: parsed_method }
+        } else {
+            TitleMatch { similarity: raw_sim, title: i, parsed: false, method: raw_method }
+        };
+        if best.as_ref().is_none_or(|b| candidate.similarity > b.similarity) {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+/// Score one name (a file stem or a folder name) against every title of the
+/// target; the parsed year then nudges it up or down against the target's.
+fn score_prepared_name(name: &PreparedName, target: &MatchTarget) -> f64 {
+    let similarity = best_title(name, target).map_or(0.0, |m| m.similarity);
+    adjust_for_year(similarity, &name.parsed, target.year).0
+}
+
+/// [`score_prepared_name`] with the detail of how the score came about.
+fn score_component(name: &PreparedName, component: MatchComponent, target: &MatchTarget) -> (f64, ComponentScore) {
+    let found = best_title(name, target);
+    let (score, year_method) = adjust_for_year(found.as_ref().map_or(0.0, |m| m.similarity), &name.parsed, target.year);
+
+    let mut methods = Vec::new();
+    if let Some(m) = &found {
+        if m.parsed {
+            methods.push(MatchMethod::ParsedTitle);
+        }
+        methods.push(m.method);
+    }
+    methods.extend(year_method);
+
+    let detail = ComponentScore {
+        component,
+        value: name.value.clone(),
+        matched_title: found.map(|m| target.titles[m.title].0.clone()).unwrap_or_default(),
+        methods,
+        score: to_percent(score),
+    };
+    (score, detail)
+}
+
+/// Score a single name (file stem, folder name or search query) against the target.
+pub fn score_name(name: &str, target: &MatchTarget) -> f64 {
+    score_prepared_name(&PreparedName::new(name), target)
+}
+
+/// The score [`explain_prepared`] gives, without building its detail. For
+/// scoring every file against every movie; explain only the pairs kept.
+pub fn score_prepared(path: &PreparedPath, target: &MatchTarget) -> f64 {
+    let file = score_prepared_name(&path.stem, target);
+    if file >= 1.0 {
+        return file;
+    }
+    path.dirs.iter().map(|d| score_prepared_name(d, target)).fold(file, f64::max)
+}
+
+/// Best score of a file against the target, looking at the file stem first
+/// and then at each folder between the scan root and the file. The detail
+/// records which component and title produced the score and how every
+/// component fared.
+pub fn explain_path(stem: &str, dirs: &[String], target: &MatchTarget) -> (f64, MatchDetail) {
+    explain_prepared(&PreparedPath::new(stem, dirs), target)
+}
+
+/// [`explain_path`] for a path that was already prepared.
+pub fn explain_prepared(path: &PreparedPath, target: &MatchTarget) -> (f64, MatchDetail) {
+    let (mut best, file_score) = score_component(&path.stem, MatchComponent::File, target);
+    let mut components = vec![file_score];
+    let mut best_idx = 0;
+
+    if best < 1.0 {
+        for (depth, dir) in path.dirs.iter().enumerate() {
+            let (dir_sim, dir_score) = score_component(dir, MatchComponent::Directory { depth }, target);
+            components.push(dir_score);
+            if dir_sim > best {
+                best = dir_sim;
+                best_idx = components.len() - 1;
+            }
+        }
+    }
+
+    let winner = &components[best_idx];
+    let detail = MatchDetail {
+        matched_title: winner.matched_title.clone(),
+        component: winner.component.clone(),
+        component_value: winner.value.clone(),
+        methods: winner.methods.clone(),
+        components,
+    };
+    (best, detail)
+}
+
+fn adjust_for_year(score: f64, parsed: &ParsedName, target_year: Option<u16>) -> (f64, Option<MatchMethod>) {
+    let (Some(found), Some(expected)) = (parsed.year, target_year) else {
+        return (score, None);
+    };
+    if score <= 0.0 {
+        return (score, None);
+    }
+
+    match found.abs_diff(expected) {
+        // A matching year raises confidence but never turns a fuzzy match into an exact one
+        0 if score < 1.0 => ((score + YEAR_BONUS).min(0.99), Some(MatchMethod::YearBonus)),
+        0 | 1 => (score, None),
+        _ => ((score - YEAR_PENALTY).max(0.0), Some(MatchMethod::YearPenalty)),
+    }
+}
+
+pub fn to_percent(score: f64) -> u8 {
+    (score.clamp(0.0, 1.0) * 100.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(titles: &[&str], year: Option<u16>) -> MatchTarget {
+        MatchTarget::new(&titles.iter().map(|t| t.to_string()).collect::<Vec<_>>(), year)
+    }
+
+    #[test]
+    fn reports_the_method() {
+        use MatchMethod::*;
+        // (file stem, title, score, methods)
+        let cases: &[(&str, &str, f64, &[MatchMethod])] = &[
+            ("Heat", "Heat", 1.0, &[Exact]),
+            ("heat behind the scenes", "Heat", 0.95, &[Contains]),
+            ("The.Wandering.Earth.2019.2160p.WEB-DL.H265-XXX", "The Wandering Earth", 1.0, &[ParsedTitle, Exact]),
+            ("The.Wandering.Earth.Making.Of.1080p", "The Wandering Earth", 0.95, &[ParsedTitle, Contains]),
+            ("卧虎藏龙.2000.1080p.国语中字", "卧虎藏龙", 1.0, &[ParsedTitle, Exact]),
+        ];
+        for (stem, title, score, methods) in cases {
+            let (found, detail) = explain_path(stem, &[], &target(&[title], None));
+            assert!((found - score).abs() < 1e-9, "{}: {}", stem, found);
+            assert_eq!(detail.methods, *methods, "{}", stem);
+        }
+
+        let (score, detail) = explain_path("Heet", &[], &target(&["Heat"], None));
+        assert!(score > 0.8 && score < 0.95, "{}", score);
+        assert_eq!(detail.methods, [JaroWinkler]);
+    }
+
+    #[test]
+    fn year_bonus_and_penalty() {
+        use MatchMethod::*;
+        let wandering = |stem: &str, year: u16| explain_path(stem, &[], &target(&["The Wandering Earth"], Some(year)));
+
+        // An exact title stays exact, a fuzzy one is raised but capped below it
+        let (score, detail) = wandering("The.Wandering.Earth.2019.2160p", 2019);
+        assert_eq!((score, detail.methods), (1.0, vec![ParsedTitle, Exact]));
+        let (score, detail) = wandering("The.Wandring.Earth.2019.2160p", 2019);
+        assert_eq!((score, detail.methods), (0.99, vec![ParsedTitle, JaroWinkler, YearBonus]));
+        let (fuzzy, _) = wandering("The.Wandring.Earth.2160p", 2019);
+        assert!(fuzzy < 0.99);
+
+        // One year off is tolerated (festival vs. release year), more is not
+        let (score, detail) = wandering("The.Wandering.Earth.2018.2160p", 2019);
+        assert_eq!((score, detail.methods), (1.0, vec![ParsedTitle, Exact]));
+        let (score, detail) = wandering("The.Wandering.Earth.2015.2160p", 2019);
+        assert!((score - (1.0 - YEAR_PENALTY)).abs() < 1e-9, "{}", score);
+        assert_eq!(detail.methods, [ParsedTitle, Exact, YearPenalty]);
+
+        // Without a year on either side nothing changes
+        let (score, detail) = explain_path("The.Wandering.Earth.2015", &[], &target(&["The Wandering Earth"], None));
+        assert_eq!((score, detail.methods), (1.0, vec![ParsedTitle, Exact]));
+    }
+
+    #[test]
+    fn picks_the_winning_component() {
+        let heat = target(&["Heat"], Some(1995));
+        let dirs = ["Movies".to_string(), "Heat (1995)".to_string()];
+        let (score, detail) = explain_path("video_001", &dirs, &heat);
+        assert_eq!(score, 1.0);
+        assert_eq!(detail.component, MatchComponent::Directory { depth: 1 });
+        assert_eq!((detail.component_value.as_str(), detail.matched_title.as_str()), ("Heat (1995)", "Heat"));
+        let scores: Vec<u8> = detail.components.iter().map(|c| c.score).collect();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores[2], 100);
+        assert!(scores[0] < 100 && scores[1] < 100);
+
+        // An exact file name needs no folders
+        let (_, detail) = explain_path("Heat.1995.1080p", &dirs, &heat);
+        assert_eq!(detail.component, MatchComponent::File);
+        assert_eq!(detail.components.len(), 1);
+
+        // Of equal scores the file wins over its folders
+        let (_, detail) = explain_path("Heet", &["Heet".to_string()], &heat);
+        assert_eq!(detail.component, MatchComponent::File);
+    }
+
+    #[test]
+    fn matches_aliases() {
+        let tiger = target(&["Crouching Tiger, Hidden Dragon", "臥虎藏龍", "卧虎藏龙"], Some(2000));
+        let (score, detail) = explain_path("卧虎藏龙.2000.1080p", &[], &tiger);
+        assert_eq!((score, detail.matched_title.as_str()), (1.0, "卧虎藏龙"));
+        assert_eq!(score_name("卧虎藏龙 2000", &tiger), 1.0);
+        assert_eq!(score_name("", &tiger), 0.0);
+    }
+
+    #[test]
+    fn score_agrees_with_explain() {
+        let heat = target(&["Heat", "盗火线"], Some(1995));
+        let cases: &[(&str, &[&str])] = &[
+            ("Heat.1995.1080p", &["Movies"]),
+            ("video_001", &["Movies", "Heat (1995)"]),
+            ("盗火线.2001", &[]),
+            ("Heet", &["Heet", "Crime"]),
+            ("Something Else", &[]),
+        ];
+        for (stem, dirs) in cases {
+            let prepared = PreparedPath::new(stem, &dirs.iter().map(|d| d.to_string()).collect::<Vec<_>>());
+            assert_eq!(score_prepared(&prepared, &heat), explain_prepared(&prepared, &heat).0, "{}", stem);
+        }
+    }
+
+    #[test]
+    fn percent_is_clamped() {
+        assert_eq!((to_percent(0.994), to_percent(1.5), to_percent(-0.2)), (99, 100, 0));
+    }
+}
@@ -0,0 +1,429 @@
+use chrono::Datelike;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Structured view of a scene/release style file name, e.g.
+/// `The.Wandering.Earth.2019.2160p.WEB-DL.H265-XXX` or `繁花.第12集.4K`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ParsedName {
+    /// Title with release noise stripped and separators turned into spaces
+    pub title: String,
+    pub year: Option<u16>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// Normalized vertical resolution, e.g. "2160p"
+    pub resolution: Option<String>,
+    pub source: Option<String>,
+    pub codec: Option<String>,
+    pub group: Option<String>,
+    /// Everything else we recognized: HDR, 10bit, audio formats, 中字 ...
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+struct Patterns {
+    split: Regex,
+    dotted_codec: Regex,
+    trailing_group: Regex,
+    leading_group: Regex,
+    season_episode: Regex,
+    cross_episode: Regex,
+    season_only: Regex,
+    episode_only: Regex,
+    cn_episode: Regex,
+    cn_season: Regex,
+    year: Regex,
+    resolution: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        split: Regex::new(r"[\s._\[\]()【】（）「」{}+,，]+").unwrap(),
+        dotted_codec: Regex::new(r"(?i)\b([hx])\.(26[45])\b").unwrap(),
+        trailing_group: Regex::new(r"-([A-Za-z0-9@]+)$").unwrap(),
+        leading_group: Regex::new(r"^\s*[\[【]([^\]】]+)[\]】]\s*").unwrap(),
+        season_episode: Regex::new(r"(?i)^s(\d{1,2})[\s.]?e(\d{1,4})(?:-?e\d{1,4})?$").unwrap(),
+        cross_episode: Regex::new(r"(?i)^(\d{1,2})x(\d{1,3})$").unwrap(),
+        season_only: Regex::new(r"(?i)^s(\d{1,2})$").unwrap(),
+        episode_only: Regex::new(r"(?i)^(?:ep?)(\d{1,4})$").unwrap(),
+        cn_episode: Regex::new(r"第\s*([0-9零〇一二两三四五六七八九十百]+)\s*[集话話期]").unwrap(),
+        cn_season: Regex::new(r"第\s*([0-9零〇一二两三四五六七八九十]+)\s*[季部]").unwrap(),
+        year: Regex::new(r"^(19\d{2}|20\d{2})$").unwrap(),
+        resolution: Regex::new(r"(?i)^(\d{3,4})[pi]$").unwrap(),
+    })
+}
+
+const SOURCES: &[(&str, &str)] = &[
+    ("web-dl", "WEB-DL"),
+    ("webdl", "WEB-DL"),
+    ("webrip", "WEBRip"),
+    ("web", "WEB"),
+    ("bluray", "BluRay"),
+    ("blu-ray", "BluRay"),
+    ("bdrip", "BDRip"),
+    ("brrip", "BDRip"),
+    ("remux", "Remux"),
+    ("hdtv", "HDTV"),
+    ("hdrip", "HDRip"),
+    ("dvdrip", "DVDRip"),
+    ("dvd", "DVD"),
+    ("uhd", "UHD"),
+];
+
+const CODECS: &[(&str, &str)] = &[
+    ("h265", "H265"),
+    ("x265", "H265"),
+    ("hevc", "H265"),
+    ("h264", "H264"),
+    ("x264", "H264"),
+    ("avc", "H264"),
+    ("av1", "AV1"),
+    ("vp9", "VP9"),
+    ("xvid", "XviD"),
+];
+
+const TAGS: &[&str] = &[
+    "hdr", "hdr10", "hdr10+", "dv", "dovi", "sdr", "10bit", "8bit", "60fps", "120fps", "imax",
+    "aac", "ac3", "eac3", "ddp", "dd", "dts", "dts-hd", "truehd", "atmos", "flac", "opus", "mp3",
+    "proper", "repack", "extended", "uncut", "complete", "internal", "limited",
+    "国语", "粤语", "国粤", "中字", "中英", "双语", "双字", "简繁", "简体", "繁体", "国配", "内封", "内嵌", "未删减", "完整版", "导演剪辑版",
+];
+
+enum Token {
+    Year(u16),
+    Release,
+}
+
+/// Parse a file stem (or folder name) into its release components.
+///
+/// Anything after the first recognized marker (year, episode, resolution,
+/// source, codec ...) is considered release noise; what comes before it
+/// is the title.
+pub fn parse(name: &str) -> ParsedName {
+    let p = patterns();
+    let mut parsed = ParsedName::default();
+
+    let mut rest = p.dotted_codec.replace_all(name.trim(), "${1}${2}").to_string();
+
+    if let Some(caps) = p.leading_group.captures(&rest) {
+        let group = caps[1].trim().to_string();
+        // `[2019]` or `[1080p]` up front is not a group
+        if !p.year.is_match(&group) && classify(&group, &mut ParsedName::default()).is_none() {
+            parsed.group = Some(group);
+            rest = rest[caps.get(0).unwrap().end()..].to_string();
+        }
+    }
+
+    if let Some(caps) = p.trailing_group.captures(&rest) {
+        let prefix = &rest[..caps.get(0).unwrap().start()];
+        let words: Vec<&str> = p.split.split(prefix).filter(|s| !s.is_empty()).collect();
+        let last_word = words.last().copied().unwrap_or("");
+        // `DDP5.1-XXX` splits into `DDP5` and `1`
+        let tech_word = if last_word.chars().all(|c| c.is_ascii_digit()) && words.len() > 1 {
+            words[words.len() - 2]
+        } else {
+            last_word
+        };
+        let whole = format!("{}{}", last_word, caps.get(0).unwrap().as_str());
+        // Only a group when it hangs off a technical token: `H265-XXX`, not `Spider-Man` or `WEB-DL`
+        if classify(tech_word, &mut ParsedName::default()).is_some()
+            && classify(&whole, &mut ParsedName::default()).is_none()
+        {
+            parsed.group = Some(caps[1].to_string());
+            rest = prefix.to_string();
+        }
+    }
+
+    let tokens: Vec<&str> = p.split.split(&rest).filter(|t| !t.is_empty()).collect();
+
+    let mut title_end = tokens.len();
+    let mut title_prefix: Option<(usize, String)> = None;
+    let mut year_candidate: Option<(usize, u16)> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        // Fansub style "Title - 01": a bare number right after a dash is the episode
+        if i > 0 && tokens[i - 1] == "-" && token.len() <= 4 && token.chars().all(|c| c.is_ascii_digit()) {
+            parsed.episode = token.parse().ok();
+            if title_end == tokens.len() {
+                title_end = i;
+            }
+            continue;
+        }
+
+        // Chinese markers can be glued to the title: `繁花第12集`
+        if let Some(m) = p.cn_episode.find(token).or_else(|| p.cn_season.find(token)) {
+            if m.start() > 0 && title_end == tokens.len() {
+                title_end = i;
+                title_prefix = Some((i, token[..m.start()].to_string()));
+            }
+        }
+
+        match classify(token, &mut parsed) {
+            Some(Token::Year(y)) => {
+                // A leading year is part of the title ("2012", "1917")
+                if i > 0 && title_end == tokens.len() {
+                    year_candidate = Some((i, y));
+                } else if i > 0 && parsed.year.is_none() && year_candidate.is_none() {
+                    parsed.year = Some(y);
+                }
+            }
+            Some(Token::Release) if title_end == tokens.len() => title_end = i,
+            _ => {}
+        }
+    }
+
+    if let Some((i, y)) = year_candidate {
+        parsed.year = Some(y);
+        title_end = title_end.min(i);
+    }
+
+    let mut words: Vec<String> = tokens[..title_end]
+        .iter()
+        .filter(|t| **t != "-")
+        .map(|s| s.to_string())
+        .collect();
+    if let Some((i, prefix)) = title_prefix {
+        if i == title_end {
+            words.push(prefix);
+        }
+    }
+    let title = words.join(" ").trim_matches(|c: char| c == '-' || c.is_whitespace()).to_string();
+
+    parsed.title = if title.is_empty() {
+        tokens
+            .iter()
+            .find(|t| **t != "-")
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| name.trim().to_string())
+    } else {
+        title
+    };
+
+    parsed
+}
+
+/// The latest year a release can carry: announced titles go up to next year.
+fn latest_year() -> u16 {
+    static LATEST: OnceLock<u16> = OnceLock::new();
+    *LATEST.get_or_init(|| chrono::Utc::now().year() as u16 + 1)
+}
+
+/// Recognize a single token, recording what it means into `parsed`.
+/// Returns `None` for plain title words.
+fn classify(token: &str, parsed: &mut ParsedName) -> Option<Token> {
+    let p = patterns();
+    let lower = token.to_lowercase();
+
+    // Not yet released next year, "2049" is a title word
+    if let Some(y) = p.year.captures(token).and_then(|caps| caps[1].parse().ok()).filter(|y| *y <= latest_year()) {
+        return Some(Token::Year(y));
+    }
+    if let Some(caps) = p.season_episode.captures(token) {
+        parsed.season = caps[1].parse().ok();
+        parsed.episode = caps[2].parse().ok();
+        return Some(Token::Release);
+    }
+    if let Some(caps) = p.cross_episode.captures(token) {
+        parsed.season = caps[1].parse().ok();
+        parsed.episode = caps[2].parse().ok();
+        return Some(Token::Release);
+    }
+    if let Some(caps) = p.season_only.captures(token) {
+        parsed.season = caps[1].parse().ok();
+        return Some(Token::Release);
+    }
+    if let Some(caps) = p.episode_only.captures(token) {
+        parsed.episode = caps[1].parse().ok();
+        return Some(Token::Release);
+    }
+
+    let mut cn_marker = false;
+    if let Some(caps) = p.cn_episode.captures(token) {
+        parsed.episode = parse_number(&caps[1]);
+        cn_marker = true;
+    }
+    if let Some(caps) = p.cn_season.captures(token) {
+        parsed.season = parse_number(&caps[1]);
+        cn_marker = true;
+    }
+    if cn_marker {
+        return Some(Token::Release);
+    }
+
+    if let Some(caps) = p.resolution.captures(token) {
+        parsed.resolution = Some(format!("{}p", &caps[1]));
+        return Some(Token::Release);
+    }
+    match lower.as_str() {
+        "4k" | "uhd4k" => {
+            parsed.resolution = Some("2160p".to_string());
+            return Some(Token::Release);
+        }
+        "8k" => {
+            parsed.resolution = Some("4320p".to_string());
+            return Some(Token::Release);
+        }
+        "2k" => {
+            parsed.resolution = Some("1440p".to_string());
+            return Some(Token::Release);
+        }
+        _ => {}
+    }
+
+    if let Some((_, source)) = SOURCES.iter().find(|(k, _)| *k == lower) {
+        parsed.source = Some(source.to_string());
+        return Some(Token::Release);
+    }
+    if let Some((_, codec)) = CODECS.iter().find(|(k, _)| *k == lower) {
+        parsed.codec = Some(codec.to_string());
+        return Some(Token::Release);
+    }
+
+    // Audio tokens like DDP5 / AAC2 lose their ".1" / ".0" to the splitter
+    let base = lower.trim_end_matches(|c: char| c.is_ascii_digit());
+    if TAGS.contains(&lower.as_str()) || (base.len() >= 2 && base != lower && TAGS.contains(&base)) {
+        if !parsed.tags.iter().any(|t| t.eq_ignore_ascii_case(token)) {
+            parsed.tags.push(token.to_string());
+        }
+        return Some(Token::Release);
+    }
+
+    None
+}
+
+/// Arabic or simple Chinese numerals (up to 999): "12", "十二", "二十三".
+fn parse_number(s: &str) -> Option<u32> {
+    if let Ok(n) = s.parse::<u32>() {
+        return Some(n);
+    }
+
+    let digit = |c: char| -> Option<u32> {
+        Some(match c {
+            '零' | '〇' => 0,
+            '一' => 1,
+            '二' | '两' => 2,
+            '三' => 3,
+            '四' => 4,
+            '五' => 5,
+            '六' => 6,
+            '七' => 7,
+            '八' => 8,
+            '九' => 9,
+            _ => return None,
+        })
+    };
+
+    let mut total = 0;
+    let mut current = 0;
+    for c in s.chars() {
+        match c {
+            '百' => {
+                total += current.max(1) * 100;
+                current = 0;
+            }
+            '十' => {
+                total += current.max(1) * 10;
+                current = 0;
+            }
+            _ => current = digit(c)?,
+        }
+    }
+    Some(total + current)
+}
+
+/// Year part of a `release_date` like "2019-02-05" or "2019".
+pub fn year_of(date: &str) -> Option<u16> {
+    date.get(..4).and_then(|y| y.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (name, resolution, source, codec, group)
+    type ReleaseCase = (&'static str, Option<&'static str>, Option<&'static str>, Option<&'static str>, Option<&'static str>);
+
+    #[test]
+    fn title_and_year() {
+        let cases: &[(&str, &str, Option<u16>)] = &[
+            ("The.Wandering.Earth.2019.2160p.WEB-DL.H265-XXX", "The Wandering Earth", Some(2019)),
+            ("Blade.Runner.2049.2017.1080p.BluRay.x264", "Blade Runner 2049", Some(2017)),
+            ("Blade Runner 2049", "Blade Runner 2049", None),
+            ("Blade Runner 2049 (2017)", "Blade Runner 2049", Some(2017)),
+            ("1917.2019.1080p.WEB-DL", "1917", Some(2019)),
+            ("1917", "1917", None),
+            ("2012 (2009)", "2012", Some(2009)),
+            ("Spider-Man.No.Way.Home.2021.2160p", "Spider-Man No Way Home", Some(2021)),
+            ("流浪地球2 (2023) 4K", "流浪地球2", Some(2023)),
+            ("霸王别姬.1993.BluRay.国语中字", "霸王别姬", Some(1993)),
+            ("【蓝光】卧虎藏龙 2000 1080p", "卧虎藏龙", Some(2000)),
+            ("Heat", "Heat", None),
+        ];
+        for (name, title, year) in cases {
+            let parsed = parse(name);
+            assert_eq!((parsed.title.as_str(), parsed.year), (*title, *year), "{}", name);
+        }
+    }
+
+    #[test]
+    fn release_tags() {
+        let cases: &[ReleaseCase] = &[
+            ("The.Wandering.Earth.2019.2160p.WEB-DL.H265-XXX", Some("2160p"), Some("WEB-DL"), Some("H265"), Some("XXX")),
+            ("Movie.2020.1080i.HDTV.h.264-GRP", Some("1080p"), Some("HDTV"), Some("H264"), Some("GRP")),
+            ("Movie 2020 4K HEVC", Some("2160p"), None, Some("H265"), None),
+            ("Movie.2020.720p.BluRay.DDP5.1.x264-FGT", Some("720p"), Some("BluRay"), Some("H264"), Some("FGT")),
+            ("[Nekomoe] Frieren - 05 [1080p]", Some("1080p"), None, None, Some("Nekomoe")),
+            ("[2019] Movie", None, None, None, None),
+            ("Spider-Man", None, None, None, None),
+        ];
+        for (name, resolution, source, codec, group) in cases {
+            let parsed = parse(name);
+            assert_eq!(
+                (parsed.resolution.as_deref(), parsed.source.as_deref(), parsed.codec.as_deref(), parsed.group.as_deref()),
+                (*resolution, *source, *codec, *group),
+                "{}",
+                name
+            );
+        }
+
+        let parsed = parse("Movie.2021.2160p.UHD.BluRay.10bit.HDR.DDP5.1.Atmos.国语.中字-GRP");
+        assert_eq!(parsed.tags, ["10bit", "HDR", "DDP5", "Atmos", "国语", "中字"]);
+    }
+
+    #[test]
+    fn seasons_and_episodes() {
+        // (name, title, season, episode)
+        let cases: &[(&str, &str, Option<u32>, Option<u32>)] = &[
+            ("Show.Name.S02E10.720p.HDTV.x264-GRP", "Show Name", Some(2), Some(10)),
+            ("Show Name 3x07", "Show Name", Some(3), Some(7)),
+            ("Show.Name.S01", "Show Name", Some(1), None),
+            ("Show Name EP12", "Show Name", None, Some(12)),
+            ("[Nekomoe] Frieren - 05 [1080p]", "Frieren", None, Some(5)),
+            ("繁花.第12集.4K", "繁花", None, Some(12)),
+            ("繁花第十二集", "繁花", None, Some(12)),
+            ("三体 第一季 第二十三集", "三体", Some(1), Some(23)),
+            ("三体.S01E03.2023.2160p", "三体", Some(1), Some(3)),
+        ];
+        for (name, title, season, episode) in cases {
+            let parsed = parse(name);
+            assert_eq!((parsed.title.as_str(), parsed.season, parsed.episode), (*title, *season, *episode), "{}", name);
+        }
+    }
+
+    #[test]
+    fn chinese_numbers() {
+        for (text, n) in [("12", 12), ("十", 10), ("十二", 12), ("二十三", 23), ("两百零五", 205), ("一百", 100)] {
+            assert_eq!(parse_number(text), Some(n), "{}", text);
+        }
+        assert_eq!(parse_number("第"), None);
+    }
+
+    #[test]
+    fn year_of_dates() {
+        assert_eq!(year_of("2019-02-05"), Some(2019));
+        assert_eq!(year_of("2019"), Some(2019));
+        assert_eq!(year_of("19"), None);
+    }
+}
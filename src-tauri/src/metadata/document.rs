@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use encoding_rs::{Encoding, GB18030, UTF_8};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Plain text of a document, and its page count when the format has pages.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentText {
+    pub text: String,
+    pub pages: Option<u32>,
+}
+
+/// Plain text formats, decoded as they are.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "nfo", "srt"];
+
+/// Whether [`extract_text`] can read the file. Ebooks (epub, mobi, azw3)
+/// and legacy .doc files are linked as documents but can't be.
+pub fn is_supported(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    ext == "docx" || ext == "pdf" || TEXT_EXTENSIONS.contains(&ext.as_str())
+}
+
+/// Extract the text of a docx, pdf or plain text file (txt, md, nfo, srt).
+/// Anything else is an error rather than its bytes read as text.
+pub fn extract_text(path: &Path) -> Result<DocumentText, String> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "docx" => extract_docx(path),
+        "pdf" => extract_pdf(path),
+        "doc" => Err("legacy .doc files are not supported, save as .docx".to_string()),
+        ext if TEXT_EXTENSIONS.contains(&ext) => {
+            let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+            Ok(DocumentText { text: decode_text(&bytes), pages: None })
+        }
+        ext => Err(format!("unsupported document format: .{}", ext)),
+    }
+}
+
+/// Decode text that is UTF-8 (with or without BOM), UTF-16 with BOM, or
+/// else the GBK family most Chinese Windows tools save in.
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return encoding.decode_without_bom_handling(&bytes[bom_len..]).0.into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let (text, _, had_errors) = GB18030.decode(bytes);
+    if had_errors {
+        // Not GBK either, keep what UTF-8 can make of it
+        return UTF_8.decode(bytes).0.into_owned();
+    }
+    text.into_owned()
+}
+
+fn read_zip_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// Text runs of `word/document.xml`, one line per paragraph. The page count
+/// is what Word saved in `docProps/app.xml`, if anything.
+fn extract_docx(path: &Path) -> Result<DocumentText, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let xml = read_zip_entry(&mut archive, "word/document.xml").ok_or("word/document.xml missing")?;
+
+    let mut reader = Reader::from_str(&xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(t) if in_text => text.push_str(&t.unescape().map_err(|e| e.to_string())?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let pages = read_zip_entry(&mut archive, "docProps/app.xml").and_then(|app| {
+        let start = app.find("<Pages>")? + "<Pages>".len();
+        let end = start + app[start..].find("</Pages>")?;
+        app[start..end].trim().parse().ok()
+    });
+    Ok(DocumentText { text, pages })
+}
+
+/// The text layer of every page; scanned PDFs without one come back empty.
+fn extract_pdf(path: &Path) -> Result<DocumentText, String> {
+    let doc = lopdf::Document::load(path).map_err(|e| e.to_string())?;
+    let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+    let text = pages.iter()
+        .filter_map(|p| doc.extract_text(&[*p]).ok())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(DocumentText { text, pages: Some(pages.len() as u32) })
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // Extensions B and later
+    )
+}
+
+/// Words the way editors count them for mixed text: every CJK character is
+/// one word, other words are runs of letters and digits.
+pub fn word_count(text: &str) -> u64 {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if !(in_word && (c == '\'' || c == '-')) {
+            // Apostrophes and hyphens inside a word don't split it
+            in_word = false;
+        }
+    }
+    count
+}
@@ -0,0 +1,209 @@
+pub mod document;
+mod probe;
+pub mod script;
+
+use std::cmp::Ordering;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+use crate::commands::media::run_media_tool;
+use crate::db::Database;
+use crate::models::{Material, MaterialFilter, MaterialListItem, MaterialMetadata, MaterialSort, Movie};
+
+/// Size and modification time of a file, the way `MaterialMetadata` records them.
+fn file_stamp(path: &Path) -> Option<(u64, Option<String>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok().map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+    Some((metadata.len(), modified))
+}
+
+/// Whether the material was probed since the file last changed. Files that
+/// are gone keep what we have.
+pub fn is_current(material: &Material) -> bool {
+    let Some(meta) = &material.metadata else {
+        return false;
+    };
+    file_stamp(Path::new(&material.path))
+        .is_none_or(|(size, modified)| meta.file_size == size && meta.file_modified == modified)
+}
+
+async fn probe_media(app: &AppHandle, db: &Database, path: &str, meta: &mut MaterialMetadata) -> Result<(), String> {
+    let (success, stdout, stderr) = run_media_tool(
+        app, db, "ffprobe",
+        &["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams", path],
+    ).await?;
+    if !success {
+        let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+        return Err(if stderr.is_empty() { "ffprobe failed".to_string() } else { stderr });
+    }
+    let json: serde_json::Value = serde_json::from_slice(&stdout).map_err(|e| e.to_string())?;
+    probe::apply_ffprobe(&json, meta);
+    Ok(())
+}
+
+fn probe_image(path: &str, meta: &mut MaterialMetadata) -> Result<(), String> {
+    let size = imagesize::size(path).map_err(|e| e.to_string())?;
+    meta.width = Some(size.width as u32);
+    meta.height = Some(size.height as u32);
+    Ok(())
+}
+
+/// Page, word and character counts; returns the text for the script index.
+/// Parsing large PDFs takes a while, so off the async runtime.
+async fn probe_document(path: &str, meta: &mut MaterialMetadata) -> Result<String, String> {
+    let path = path.to_string();
+    let doc = tauri::async_runtime::spawn_blocking(move || document::extract_text(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())??;
+    meta.page_count = doc.pages;
+    meta.word_count = Some(document::word_count(&doc.text));
+    meta.char_count = Some(script::char_count(&doc.text));
+    Ok(doc.text)
+}
+
+/// Everything we can find out about one file, plus the text of documents.
+/// Failures are kept in `error` so the file isn't probed again until it changes.
+pub async fn probe(app: &AppHandle, db: &Database, path: &str, file_type: &str) -> (MaterialMetadata, Option<String>) {
+    let (file_size, file_modified) = file_stamp(Path::new(path)).unwrap_or_default();
+    let mut meta = MaterialMetadata {
+        file_size,
+        file_modified,
+        probed_at: chrono::Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    let mut text = None;
+    let result = match file_type {
+        "video" | "audio" => probe_media(app, db, path, &mut meta).await,
+        "image" => probe_image(path, &mut meta),
+        "doc" => probe_document(path, &mut meta).await.map(|t| text = Some(t)),
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("[METADATA] {}: {}", path, e);
+        meta.error = Some(e);
+    }
+    (meta, text)
+}
+
+/// Probe the materials of a movie whose metadata is missing or outdated,
+/// or all of them with `force`, and index the text of its documents.
+/// Returns how many were probed.
+pub async fn enrich_movie(app: &AppHandle, db: &Database, movie_id: u64, force: bool) -> Result<usize, String> {
+    let movie = db.get_movie(movie_id).ok_or("Movie not found")?;
+    let mut probed = 0;
+    for material in movie.materials {
+        // Documents that failed to extract are done too, until they change.
+        // Text indexed from formats we no longer read is probed again, and dropped
+        let failed = material.metadata.as_ref().is_some_and(|m| m.error.is_some());
+        let indexed = material.file_type != "doc"
+            || failed
+            || (db.has_script_text(movie_id, &material.id) && document::is_supported(Path::new(&material.path)));
+        if !Path::new(&material.path).exists() || (!force && indexed && is_current(&material)) {
+            continue;
+        }
+        let (meta, text) = probe(app, db, &material.path, &material.file_type).await;
+        match text {
+            Some(text) => {
+                let chars = meta.char_count.unwrap_or(0);
+                db.save_script_text(movie_id, &material.id, &material.path, &text, chars).map_err(|e| e.to_string())?;
+            }
+            None if material.file_type == "doc" => {
+                db.delete_script_text(movie_id, &material.id).map_err(|e| e.to_string())?;
+            }
+            None => {}
+        }
+        db.set_material_metadata(movie_id, &material.id, meta).map_err(|e| e.to_string())?;
+        probed += 1;
+    }
+    Ok(probed)
+}
+
+/// Enrich newly linked materials in the background. Emits
+/// `material-metadata-updated` with the movie id for each movie that changed.
+pub fn spawn_enrich(app: AppHandle, db: Database, movie_ids: Vec<u64>) {
+    tauri::async_runtime::spawn(async move {
+        for movie_id in movie_ids {
+            match enrich_movie(&app, &db, movie_id, false).await {
+                Ok(0) => {}
+                Ok(probed) => {
+                    println!("[METADATA] Probed {} materials of movie {}", probed, movie_id);
+                    app.emit("material-metadata-updated", movie_id).unwrap_or(());
+                }
+                Err(e) => eprintln!("[METADATA] Movie {}: {}", movie_id, e),
+            }
+        }
+    });
+}
+
+/// Size in bytes, from the last probe or else the size recorded at linking.
+fn material_size(material: &Material) -> Option<u64> {
+    material.metadata.as_ref().map(|m| m.file_size).or_else(|| material.size.parse().ok())
+}
+
+fn in_range<T: PartialOrd + Copy>(value: Option<T>, min: Option<T>, max: Option<T>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    value.is_some_and(|v| min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max))
+}
+
+fn matches(material: &Material, filter: &MaterialFilter) -> bool {
+    let meta = material.metadata.as_ref();
+    let text_eq = |want: &Option<String>, have: Option<&str>| {
+        want.as_ref().is_none_or(|w| have.is_some_and(|h| h.eq_ignore_ascii_case(w)))
+    };
+
+    text_eq(&filter.file_type, Some(&material.file_type))
+        && text_eq(&filter.category, material.category.as_deref())
+        && filter.tag.as_ref().is_none_or(|t| material.tags.iter().any(|m| m.eq_ignore_ascii_case(t)))
+        && (text_eq(&filter.codec, meta.and_then(|m| m.video_codec.as_deref()))
+            || text_eq(&filter.codec, meta.and_then(|m| m.audio_codec.as_deref())))
+        && in_range(material_size(material), filter.min_size, filter.max_size)
+        && in_range(meta.and_then(|m| m.duration), filter.min_duration, filter.max_duration)
+        && in_range(meta.and_then(|m| m.width), filter.min_width, filter.max_width)
+        && in_range(meta.and_then(|m| m.height), filter.min_height, filter.max_height)
+}
+
+/// Materials of all movies that pass `filter`, sorted as it asks. Materials
+/// missing the sort key go last either way.
+pub fn filter_materials(movies: Vec<Movie>, filter: &MaterialFilter) -> Vec<MaterialListItem> {
+    let mut items: Vec<MaterialListItem> = movies.into_iter()
+        .flat_map(|movie| {
+            let (movie_id, movie_title) = (movie.id, movie.title);
+            movie.materials.into_iter()
+                .filter(|m| matches(m, filter))
+                .map(move |material| MaterialListItem { movie_id, movie_title: movie_title.clone(), material })
+        })
+        .collect();
+
+    let key = |item: &MaterialListItem| -> Option<f64> {
+        let m = &item.material;
+        let meta = m.metadata.as_ref();
+        match filter.sort_by {
+            MaterialSort::Name => None,
+            MaterialSort::Size => material_size(m).map(|s| s as f64),
+            MaterialSort::Duration => meta.and_then(|m| m.duration),
+            MaterialSort::Resolution => meta.and_then(|m| Some(m.width? as f64 * m.height? as f64)),
+            MaterialSort::Modified => m.modified_time.as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.timestamp() as f64),
+        }
+    };
+    let direction = |order: Ordering| if filter.descending { order.reverse() } else { order };
+    items.sort_by(|a, b| {
+        if filter.sort_by == MaterialSort::Name {
+            return direction(a.material.name.to_lowercase().cmp(&b.material.name.to_lowercase()));
+        }
+        match (key(a), key(b)) {
+            (Some(x), Some(y)) => direction(x.partial_cmp(&y).unwrap_or(Ordering::Equal)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    });
+
+    if let Some(limit) = filter.limit {
+        items.truncate(limit);
+    }
+    items
+}
@@ -0,0 +1,48 @@
+use serde_json::Value;
+use crate::models::MaterialMetadata;
+
+/// ffprobe prints most numbers as strings ("48000", "5.312000").
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str()?.parse().ok())
+}
+
+/// "24000/1001" → 23.976
+fn frame_rate(value: &Value) -> Option<f64> {
+    let (num, den) = value.as_str()?.split_once('/')?;
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+    (den > 0.0 && num > 0.0).then(|| num / den)
+}
+
+/// Fill `meta` from `ffprobe -show_format -show_streams` JSON, using the
+/// first video and the first audio stream. Embedded cover art is a video
+/// stream too and is skipped.
+pub fn apply_ffprobe(json: &Value, meta: &mut MaterialMetadata) {
+    let format = &json["format"];
+    meta.duration = number(&format["duration"]);
+    meta.bit_rate = number(&format["bit_rate"]).map(|b| b as u64);
+
+    let empty = Vec::new();
+    let streams = json["streams"].as_array().unwrap_or(&empty);
+
+    let video = streams.iter().find(|s| {
+        s["codec_type"] == "video" && s["disposition"]["attached_pic"].as_u64() != Some(1)
+    });
+    if let Some(v) = video {
+        meta.video_codec = v["codec_name"].as_str().map(str::to_string);
+        meta.width = v["width"].as_u64().map(|w| w as u32);
+        meta.height = v["height"].as_u64().map(|h| h as u32);
+        meta.frame_rate = frame_rate(&v["avg_frame_rate"]).or_else(|| frame_rate(&v["r_frame_rate"]));
+        if meta.duration.is_none() {
+            meta.duration = number(&v["duration"]);
+        }
+    }
+
+    if let Some(a) = streams.iter().find(|s| s["codec_type"] == "audio") {
+        meta.audio_codec = a["codec_name"].as_str().map(str::to_string);
+        meta.sample_rate = number(&a["sample_rate"]).map(|r| r as u32);
+        meta.channels = a["channels"].as_u64().map(|c| c as u32);
+        if meta.duration.is_none() {
+            meta.duration = number(&a["duration"]);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// Characters a narrator reads, i.e. everything but whitespace.
+pub fn char_count(text: &str) -> u64 {
+    text.chars().filter(|c| !c.is_whitespace()).count() as u64
+}
+
+/// Seconds it takes to read `chars` characters at `chars_per_minute`.
+pub fn narration_seconds(chars: u64, chars_per_minute: u32) -> f64 {
+    if chars_per_minute == 0 {
+        return 0.0;
+    }
+    chars as f64 * 60.0 / chars_per_minute as f64
+}
+
+/// Characters of context kept on each side of a match.
+const SNIPPET_CONTEXT: usize = 30;
+
+/// The first match of `query` (case-insensitive) with some text around it,
+/// on one line.
+pub fn snippet(text: &str, query: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let needle: Vec<char> = query.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    let start = lower.windows(needle.len()).position(|w| w == needle.as_slice())?;
+
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (start + needle.len() + SNIPPET_CONTEXT).min(chars.len());
+    let body: String = chars[from..to].iter().map(|c| if c.is_whitespace() { ' ' } else { *c }).collect();
+    Some(format!(
+        "{}{}{}",
+        if from > 0 { "…" } else { "" },
+        body.trim(),
+        if to < chars.len() { "…" } else { "" },
+    ))
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -27,14 +28,54 @@ pub struct Movie {
     pub directors: Vec<Person>,
     #[serde(default)]
     pub materials: Vec<Material>,
+    #[serde(default)]
+    pub douban_id: Option<String>,
+    #[serde(default)]
+    pub ratings: BTreeMap<ProviderId, f64>, // Each provider's rating, kept apart
+    #[serde(default)]
+    pub field_sources: BTreeMap<MetadataField, ProviderId>, // Which provider filled a field on the last refresh
+    #[serde(default)]
+    pub user_edited_fields: BTreeSet<MetadataField>, // Edited by hand; refreshes leave them alone
+    #[serde(default)]
+    pub runtime: Option<u64>, // Minutes; episode length for TV shows
+    #[serde(default)]
+    pub imdb_id: Option<String>,
+    #[serde(default)]
+    pub tvdb_id: Option<u64>,
+    #[serde(default)]
+    pub countries: Vec<String>, // ISO 3166-1 codes of the production countries
+    #[serde(default)]
+    pub original_language: Option<String>, // ISO 639-1
+    #[serde(default)]
+    pub certification: Option<String>, // "PG-13", "TV-MA"…, for the first configured region that has one
+    #[serde(default)]
+    pub collection: Option<MovieCollection>,
+    #[serde(default)]
+    pub backdrop_path: Option<String>, // The primary backdrop
+    #[serde(default)]
+    pub logo_path: Option<String>,
+    #[serde(default)]
+    pub posters: Vec<String>, // Every poster kept, `poster_path` is the one shown
+    #[serde(default)]
+    pub production_deadline: Option<String>, // YYYY-MM-DD, shown on the calendar
+}
+
+/// The TMDB collection (franchise) a movie belongs to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MovieCollection {
+    pub id: u64,
+    pub name: String,
+    pub poster_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Person {
-    pub id: u64,
+    pub id: u64, // TMDB person ID, 0 when the source has none
     pub name: String,
     pub original_name: Option<String>,
     pub profile_path: Option<String>,
+    pub character: Option<String>, // Actors only
+    pub job: Option<String>, // Crew only, e.g. "Director"
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -50,12 +91,104 @@ pub struct Material {
     pub add_time: String,
     #[serde(default)]
     pub modified_time: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>, // Size + partial content hash, survives moves/renames
+    #[serde(default)]
+    pub tags: Vec<String>, // From the category rules
+    #[serde(default)]
+    pub metadata: Option<MaterialMetadata>, // Filled in the background after linking
+}
+
+/// What ffprobe, the image header or the document itself says about a
+/// material. Only the fields that apply to the file type are set.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MaterialMetadata {
+    pub duration: Option<f64>, // Seconds
+    pub bit_rate: Option<u64>,
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub audio_codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub page_count: Option<u32>,
+    pub word_count: Option<u64>,
+    pub char_count: Option<u64>, // Without whitespace, for narration estimates
+    pub file_size: u64,
+    pub file_modified: Option<String>, // The file as probed; when it changes we probe again
+    pub probed_at: String,
+    pub error: Option<String>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_narration_cpm() -> u32 {
+    250
+}
+
+fn default_tmdb_language() -> String {
+    "zh-CN".to_string()
+}
+
+fn default_tmdb_fallback_languages() -> Vec<String> {
+    vec!["zh-TW".to_string(), "en-US".to_string()]
+}
+
+fn default_metadata_providers() -> Vec<ProviderId> {
+    vec![ProviderId::Nfo, ProviderId::Tmdb, ProviderId::Douban]
+}
+
+/// Hand-written NFO files win where they have something; Douban has the
+/// titles and synopses Chinese audiences know, TMDB the better cast data.
+fn default_merge_policy() -> BTreeMap<MetadataField, Vec<ProviderId>> {
+    use MetadataField::*;
+    use ProviderId::*;
+    BTreeMap::from([
+        (Title, vec![Nfo, Douban, Tmdb]),
+        (OriginalTitle, vec![Nfo, Tmdb, Douban]),
+        (Overview, vec![Nfo, Douban, Tmdb]),
+        (Poster, vec![Nfo, Tmdb, Douban]),
+        (ReleaseDate, vec![Nfo, Tmdb, Douban]),
+        (Rating, vec![Douban, Tmdb, Nfo]),
+        (Genres, vec![Nfo, Douban, Tmdb]),
+        (Cast, vec![Nfo, Tmdb, Douban]),
+        (Runtime, vec![Nfo, Tmdb, Douban]),
+        (Countries, vec![Tmdb, Nfo, Douban]),
+        (OriginalLanguage, vec![Tmdb, Nfo, Douban]),
+        (Certification, vec![Nfo, Tmdb, Douban]),
+        (Collection, vec![Tmdb, Nfo, Douban]),
+    ])
+}
+
+fn default_category_rules() -> Vec<CategoryRule> {
+    ["**/成片/**", "**/finished/**"]
+        .into_iter()
+        .map(|path| CategoryRule {
+            path: Some(path.to_string()),
+            category: Some("finished".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn default_exclude_patterns() -> Vec<String> {
+    [
+        "Adobe Premiere Pro Auto-Save/",
+        "Adobe Premiere Pro Video Previews/",
+        "Adobe Premiere Pro Audio Previews/",
+        "CacheClip/",
+        "*.pek",
+        "*.cfa",
+        "*.tmp",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub tmdb_api_key: Option<String>,
@@ -77,6 +210,29 @@ pub struct AppConfig {
     pub local_player_path: Option<String>,
     pub ffmpeg_path: Option<String>,
     pub ai_model_path: Option<String>,
+    // Scan filters, applied on top of the .zqignore files in monitored folders
+    #[serde(default = "default_exclude_patterns")]
+    pub exclude_patterns: Vec<String>, // gitignore-style globs, relative to each monitored folder
+    #[serde(default)]
+    pub min_file_size: Option<u64>, // bytes
+    #[serde(default)]
+    pub max_file_size: Option<u64>, // bytes
+    #[serde(default)]
+    pub max_file_age_days: Option<u64>, // skip files not modified within this many days
+    #[serde(default = "default_category_rules")]
+    pub category_rules: Vec<CategoryRule>, // Evaluated in order; source/finished folders keep their own category
+    #[serde(default = "default_narration_cpm")]
+    pub narration_chars_per_minute: u32, // Voiceover speed for script duration estimates
+    #[serde(default)]
+    pub tmdb_offline: bool, // Answer TMDB lookups from the response cache only
+    #[serde(default = "default_tmdb_language")]
+    pub tmdb_language: String, // e.g. "zh-CN"
+    #[serde(default = "default_tmdb_fallback_languages")]
+    pub tmdb_fallback_languages: Vec<String>, // Tried in order for fields the primary language lacks
+    #[serde(default = "default_metadata_providers")]
+    pub metadata_providers: Vec<ProviderId>, // Enabled providers
+    #[serde(default = "default_merge_policy")]
+    pub metadata_merge_policy: BTreeMap<MetadataField, Vec<ProviderId>>, // Providers to take each field from, best first
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -92,6 +248,123 @@ pub struct MatchedFile {
     pub category: Option<String>,
     #[serde(default)]
     pub modified_time: Option<String>,
+    #[serde(default)]
+    pub match_detail: Option<MatchDetail>,
+}
+
+/// Why a file matched: the winning title/alias and component, plus the score
+/// of every component that was looked at.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchDetail {
+    pub matched_title: String,
+    pub component: MatchComponent,
+    pub component_value: String,
+    pub methods: Vec<MatchMethod>,
+    pub components: Vec<ComponentScore>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ComponentScore {
+    pub component: MatchComponent,
+    pub value: String, // The file stem or folder name as found on disk
+    pub matched_title: String,
+    pub methods: Vec<MatchMethod>,
+    pub score: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MatchComponent {
+    File,
+    Directory { depth: usize }, // 0 = first folder below the scanned root
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    Exact,
+    Contains,
+    JaroWinkler,
+    ParsedTitle,
+    YearBonus,
+    YearPenalty,
+}
+
+/// Assigns a category and/or tags to materials. Every condition that is set
+/// must match; globs and regexes see the full path with `/` separators.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryRule {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>, // Glob, e.g. "**/配音/**"
+    #[serde(default)]
+    pub path_regex: Option<String>,
+    #[serde(default)]
+    pub file_type: Option<String>, // video, audio, image, doc
+    #[serde(default)]
+    pub file_name: Option<String>, // Glob on the file name, e.g. "*_v2.*"
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for CategoryRule {
+    fn default() -> Self {
+        Self {
+            name: None,
+            path: None,
+            path_regex: None,
+            file_type: None,
+            file_name: None,
+            category: None,
+            tags: Vec::new(),
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryRuleTest {
+    pub file_type: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub matched_rules: Vec<usize>, // Indexes into the tested rule list
+    pub errors: Vec<String>,
+}
+
+impl AppConfig {
+    /// Every configured monitor folder (default, source and finished), without duplicates.
+    pub fn monitored_folders(&self) -> Vec<String> {
+        let mut folders: Vec<String> = Vec::new();
+        for p in self.default_monitor_folders.iter()
+            .chain(&self.monitor_folders_source)
+            .chain(&self.monitor_folders_finished)
+        {
+            if !folders.contains(p) {
+                folders.push(p.clone());
+            }
+        }
+        folders
+    }
+
+    /// The primary metadata language followed by its fallbacks, without blanks or duplicates.
+    pub fn tmdb_languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = Vec::new();
+        for lang in std::iter::once(&self.tmdb_language).chain(&self.tmdb_fallback_languages) {
+            let lang = lang.trim();
+            if !lang.is_empty() && !languages.iter().any(|l| l.eq_ignore_ascii_case(lang)) {
+                languages.push(lang.to_string());
+            }
+        }
+        if languages.is_empty() {
+            languages.push(default_tmdb_language());
+        }
+        languages
+    }
 }
 
 impl Default for AppConfig {
@@ -110,6 +383,17 @@ impl Default for AppConfig {
             local_player_path: None,
             ffmpeg_path: None,
             ai_model_path: None,
+            exclude_patterns: default_exclude_patterns(),
+            min_file_size: None,
+            max_file_size: None,
+            max_file_age_days: None,
+            category_rules: default_category_rules(),
+            narration_chars_per_minute: default_narration_cpm(),
+            tmdb_offline: false,
+            tmdb_language: default_tmdb_language(),
+            tmdb_fallback_languages: default_tmdb_fallback_languages(),
+            metadata_providers: default_metadata_providers(),
+            metadata_merge_policy: default_merge_policy(),
         }
     }
 }
@@ -128,6 +412,79 @@ pub struct AppInfo {
     pub default_image_path: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateGroup {
+    pub fingerprint: String,
+    pub size: u64,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateFile {
+    pub path: String,
+    pub modified_time: Option<String>,
+    pub linked_movie_ids: Vec<u64>, // Movies that have this exact path as a material
+}
+
+/// Unlinked files of one type in one folder.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanGroup {
+    pub folder: String,
+    pub file_type: String,
+    pub files: Vec<OrphanFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified_time: Option<String>,
+    pub suggestion: Option<OrphanSuggestion>, // Best-scoring movie, if any scored reasonably
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanSuggestion {
+    pub movie_id: u64,
+    pub title: String,
+    pub similarity: u8,
+    pub match_detail: MatchDetail,
+}
+
+/// Files to link to one movie in a bulk assignment.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaterialAssignment {
+    pub movie_id: u64,
+    pub paths: Vec<String>,
+}
+
+/// Payload of the `auto-match-progress` event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutoMatchProgress {
+    pub job_id: String,
+    pub phase: String, // scanning, matching, linking
+    pub processed: usize,
+    pub total: usize,
+    pub current: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutoMatchReport {
+    pub job_id: String,
+    pub dry_run: bool,
+    pub cancelled: bool,
+    pub files_scanned: usize,
+    pub movies: Vec<AutoMatchMovieResult>, // Only movies with new matches
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutoMatchMovieResult {
+    pub movie_id: u64,
+    pub title: String,
+    pub linked: usize, // 0 on a dry run
+    pub files: Vec<MatchedFile>, // Files not yet linked to this movie
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScannedFile {
     pub path: String,
@@ -136,6 +493,658 @@ pub struct ScannedFile {
     pub search_query: String,
 }
 
+/// A title found by `scan_for_movies`, with the TMDB result we think it is.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovieImportProposal {
+    pub file: ScannedFile,
+    pub candidate: Option<tmdb::TmdbMovie>,
+    pub movie: Option<Movie>, // Ready to pass to add_movie, local_video_path set
+    pub confidence: u8,
+    #[serde(default)]
+    pub alternatives: Vec<tmdb::TmdbMovie>,
+    #[serde(default)]
+    pub already_in_library: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// One line of a planning list given to `resolve_title_list`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TitleListEntry {
+    pub line: usize, // 1-based
+    pub title: String,
+    pub year: Option<u16>,
+    pub category: Option<String>, // "movie" or "tv", when the line says so
+    pub tmdb_id: Option<u64>,
+    pub douban_id: Option<String>,
+    pub imdb_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleResolution {
+    Resolved,
+    Ambiguous, // Several close candidates or only weak ones; pick by hand
+    NotFound,
+    InLibrary,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TitleCandidate {
+    pub movie: Movie, // Ready to pass to add_movies
+    pub confidence: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResolvedTitle {
+    pub entry: TitleListEntry,
+    pub status: TitleResolution,
+    pub confidence: u8,
+    pub movie: Option<Movie>, // The pick for resolved rows
+    #[serde(default)]
+    pub candidates: Vec<TitleCandidate>, // Best first
+    #[serde(default)]
+    pub existing_movie_id: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchAddSkip {
+    pub title: String,
+    pub existing_movie_id: Option<u64>, // None when adding failed
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BatchAddResult {
+    pub added: Vec<Movie>,
+    pub skipped: Vec<BatchAddSkip>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+/// A file search below `scope`. Every filter that is set must match;
+/// `pattern` is checked against the file name only.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FileQuery {
+    pub scope: String, // Folder, or a drive like "C:" for the whole volume
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub pattern_kind: PatternKind,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub extensions: Vec<String>, // Without the dot; case-insensitive
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub modified_after: Option<String>, // YYYY-MM-DD or RFC 3339, inclusive
+    #[serde(default)]
+    pub modified_before: Option<String>, // Exclusive
+    #[serde(default)]
+    pub include_dirs: bool,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileHit {
+    pub path: String,
+    pub size: Option<u64>, // None for folders and files we couldn't stat
+    pub modified_time: Option<String>,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileSearchResult {
+    pub hits: Vec<FileHit>,
+    pub backend: String,
+    pub has_more: bool, // More hits past offset + limit
+    pub errors: Vec<String>, // Entries that couldn't be read, backend fallbacks
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialSort {
+    #[default]
+    Name,
+    Size,
+    Duration,
+    Resolution, // Pixel count
+    Modified,
+}
+
+/// Filters over the materials of every movie. Every condition that is set
+/// must match; materials without metadata fail metadata conditions.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MaterialFilter {
+    #[serde(default)]
+    pub file_type: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub codec: Option<String>, // Video or audio codec
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub min_duration: Option<f64>,
+    #[serde(default)]
+    pub max_duration: Option<f64>,
+    #[serde(default)]
+    pub min_width: Option<u32>,
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    #[serde(default)]
+    pub min_height: Option<u32>,
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    #[serde(default)]
+    pub sort_by: MaterialSort,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaterialListItem {
+    pub movie_id: u64,
+    pub movie_title: String,
+    pub material: Material,
+}
+
+/// A linked script found by `search_scripts`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptHit {
+    pub movie_id: u64,
+    pub material_id: String,
+    pub path: String,
+    pub char_count: u64,
+    pub estimated_seconds: f64, // At the configured narration speed
+    pub snippet: Option<String>, // Text around the first match
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptDetail {
+    pub movie_id: u64,
+    pub material_id: String,
+    pub path: String,
+    pub text: String,
+    pub char_count: u64,
+    pub estimated_seconds: f64,
+    pub extracted_at: String,
+}
+
+/// Cached previews of one file, as paths for the `asset` protocol. Only
+/// the ones that apply to the file type are set.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MaterialPreview {
+    pub key: String,
+    pub file_type: String,
+    pub thumbnail: Option<String>, // Video
+    pub sprite: Option<String>, // Video, frames side by side
+    pub sprite_frames: Option<u32>,
+    pub waveform: Option<String>, // Audio, JSON peak data
+    pub waveform_image: Option<String>, // Audio
+    pub image: Option<String>, // Images, downscaled
+    pub text: Option<String>, // Docs, the beginning of the text
+    pub errors: Vec<String>, // Previews that couldn't be generated
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderId {
+    Tmdb,
+    Douban,
+    Nfo, // Kodi-style .nfo files next to the videos
+}
+
+/// Movie fields a provider can fill, see [`AppConfig::metadata_merge_policy`].
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataField {
+    Title,
+    OriginalTitle,
+    Overview,
+    Poster,
+    ReleaseDate,
+    Rating, // `vote_average`; every provider's rating is kept in `ratings` as well
+    Genres,
+    Cast, // Actors and directors
+    Runtime,
+    Countries,
+    OriginalLanguage,
+    Certification,
+    Collection,
+}
+
+impl MetadataField {
+    pub const ALL: [MetadataField; 13] = [
+        MetadataField::Title,
+        MetadataField::OriginalTitle,
+        MetadataField::Overview,
+        MetadataField::Poster,
+        MetadataField::ReleaseDate,
+        MetadataField::Rating,
+        MetadataField::Genres,
+        MetadataField::Cast,
+        MetadataField::Runtime,
+        MetadataField::Countries,
+        MetadataField::OriginalLanguage,
+        MetadataField::Certification,
+        MetadataField::Collection,
+    ];
+}
+
+/// What one provider knows about a title. Empty fields are left to the others.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderMetadata {
+    pub provider: ProviderId,
+    pub provider_item_id: Option<String>, // The title's ID with this provider
+    pub tmdb_id: Option<u64>, // Cross references, NFO files often carry them
+    pub douban_id: Option<String>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u64>,
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub overview: Option<String>,
+    pub poster: Option<String>, // URL or local path
+    pub release_date: Option<String>,
+    pub rating: Option<f64>, // 0-10
+    pub category: Option<String>, // "movie" or "tv"
+    pub genres: Vec<String>,
+    pub actors: Vec<Person>,
+    pub directors: Vec<Person>,
+    pub aliases: Vec<String>,
+    pub runtime: Option<u64>, // Minutes
+    pub countries: Vec<String>, // ISO 3166-1 codes
+    pub original_language: Option<String>,
+    pub certification: Option<String>,
+    pub collection: Option<MovieCollection>,
+}
+
+impl ProviderMetadata {
+    pub fn new(provider: ProviderId) -> Self {
+        Self {
+            provider,
+            provider_item_id: None,
+            tmdb_id: None,
+            douban_id: None,
+            imdb_id: None,
+            tvdb_id: None,
+            title: None,
+            original_title: None,
+            overview: None,
+            poster: None,
+            release_date: None,
+            rating: None,
+            category: None,
+            genres: Vec::new(),
+            actors: Vec::new(),
+            directors: Vec::new(),
+            aliases: Vec::new(),
+            runtime: None,
+            countries: Vec::new(),
+            original_language: None,
+            certification: None,
+            collection: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderHit {
+    pub provider: ProviderId,
+    pub id: String, // TMDB/Douban ID, or the path of the NFO file
+    pub title: String,
+    pub original_title: Option<String>,
+    pub year: Option<String>,
+    pub poster: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderImage {
+    pub provider: ProviderId,
+    pub kind: String, // poster, backdrop, logo
+    pub url: String, // URL or local path
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataRefreshResult {
+    pub movie: Movie,
+    pub changed_fields: Vec<MetadataField>,
+    pub providers: Vec<ProviderId>, // Those that knew the title
+    pub errors: Vec<String>,
+}
+
+/// Which movies a library-wide metadata refresh looks at. Only movies with
+/// a TMDB ID are refreshed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MetadataRefreshFilter {
+    #[serde(default)]
+    pub category: Option<String>, // "movie" or "tv"
+    #[serde(default)]
+    pub added_after: Option<String>, // YYYY-MM-DD, inclusive
+    #[serde(default)]
+    pub added_before: Option<String>, // YYYY-MM-DD, exclusive
+    #[serde(default)]
+    pub providers: Option<Vec<ProviderId>>, // Default: every enabled provider
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataFieldChange {
+    pub field: MetadataField,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+/// What a refresh changes, or would change, on one movie.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovieMetadataDiff {
+    pub movie_id: u64,
+    pub title: String,
+    pub changes: Vec<MetadataFieldChange>,
+    #[serde(default)]
+    pub ratings: BTreeMap<ProviderId, f64>, // Every provider's rating as fetched
+    #[serde(default)]
+    pub new_aliases: Vec<String>,
+    #[serde(default)]
+    pub skipped_fields: Vec<MetadataField>, // Would have changed, but were edited by hand
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub applied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataRefreshProgress {
+    pub job_id: String,
+    pub processed: usize,
+    pub total: usize,
+    pub movie_id: u64,
+    pub title: String,
+    pub changed_fields: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataRefreshReport {
+    pub job_id: String,
+    pub dry_run: bool,
+    pub cancelled: bool,
+    pub checked: usize,
+    pub movies: Vec<MovieMetadataDiff>, // Only movies with changes, skipped fields or errors
+}
+
+/// Filters over the library. Every condition that is set must match; text
+/// conditions ignore case.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MovieFilter {
+    #[serde(default)]
+    pub category: Option<String>, // "movie" or "tv"
+    #[serde(default)]
+    pub production_status: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>, // ISO 3166-1 code
+    #[serde(default)]
+    pub original_language: Option<String>, // ISO 639-1
+    #[serde(default)]
+    pub certification: Option<String>,
+    #[serde(default)]
+    pub collection_id: Option<u64>,
+    #[serde(default)]
+    pub min_runtime: Option<u64>, // Minutes
+    #[serde(default)]
+    pub max_runtime: Option<u64>,
+    #[serde(default)]
+    pub year_from: Option<u32>, // Release year, inclusive
+    #[serde(default)]
+    pub year_to: Option<u32>,
+    #[serde(default)]
+    pub has_imdb_id: Option<bool>,
+}
+
+/// What a stored image shows.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageKind {
+    Poster,
+    Backdrop,
+    Logo,
+    Still, // Episode stills of TV shows
+    Profile, // Cast and crew photos
+}
+
+impl ImageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageKind::Poster => "poster",
+            ImageKind::Backdrop => "backdrop",
+            ImageKind::Logo => "logo",
+            ImageKind::Still => "still",
+            ImageKind::Profile => "profile",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [ImageKind::Poster, ImageKind::Backdrop, ImageKind::Logo, ImageKind::Still, ImageKind::Profile]
+            .into_iter()
+            .find(|k| k.as_str() == value)
+    }
+}
+
+/// TMDB image sizes offered for downloads.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSize {
+    #[default]
+    W500,
+    Original,
+}
+
+impl ImageSize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageSize::W500 => "w500",
+            ImageSize::Original => "original",
+        }
+    }
+}
+
+fn default_image_kinds() -> Vec<ImageKind> {
+    vec![ImageKind::Poster, ImageKind::Backdrop, ImageKind::Logo]
+}
+
+fn default_images_per_kind() -> usize {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageDownloadOptions {
+    #[serde(default = "default_image_kinds")]
+    pub kinds: Vec<ImageKind>,
+    #[serde(default)]
+    pub size: ImageSize,
+    #[serde(default = "default_images_per_kind")]
+    pub limit: usize, // Per kind, best voted first
+    #[serde(default)]
+    pub season: Option<u32>, // Stills come from this season's episodes; TV shows only
+}
+
+impl Default for ImageDownloadOptions {
+    fn default() -> Self {
+        Self {
+            kinds: default_image_kinds(),
+            size: ImageSize::default(),
+            limit: default_images_per_kind(),
+            season: None,
+        }
+    }
+}
+
+/// A file in the image store.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredImage {
+    pub hash: String, // xxh3-128 of the content, also the file name
+    pub path: String,
+    pub url: Option<String>, // Where it was last downloaded from
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: u64,
+}
+
+/// A stored image as one movie uses it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovieImage {
+    pub kind: ImageKind,
+    #[serde(flatten)]
+    pub image: StoredImage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CreditRole {
+    Cast,
+    Crew,
+}
+
+impl CreditRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CreditRole::Cast => "cast",
+            CreditRole::Crew => "crew",
+        }
+    }
+}
+
+/// Someone credited on at least one library title.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LibraryPerson {
+    pub id: u64,
+    pub name: String,
+    pub original_name: Option<String>,
+    pub profile_path: Option<String>,
+    pub movie_count: usize,
+}
+
+/// A library title someone worked on, and as what.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PersonCredit {
+    pub movie: Movie,
+    pub role: CreditRole,
+    pub character: Option<String>,
+    pub job: Option<String>,
+}
+
+/// One title of a TMDB filmography; cast and crew credits on the same
+/// title are merged.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FilmographyEntry {
+    pub movie: tmdb::TmdbMovie,
+    pub characters: Vec<String>,
+    pub jobs: Vec<String>,
+    pub library_movie_id: Option<u64>, // None for titles not in the library yet
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PersonFilmography {
+    pub person: tmdb::TmdbPerson,
+    pub biography: Option<String>,
+    pub birthday: Option<String>,
+    pub deathday: Option<String>,
+    pub entries: Vec<FilmographyEntry>, // Newest first
+    pub in_library: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarEventKind {
+    Release, // The movie's own release date
+    Viewing,
+    Deadline, // Production deadline
+    Episode, // A TV episode airs
+    SeasonPremiere,
+    RegionalRelease, // Release in one region, for movies not made yet
+}
+
+impl CalendarEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalendarEventKind::Release => "release",
+            CalendarEventKind::Viewing => "viewing",
+            CalendarEventKind::Deadline => "deadline",
+            CalendarEventKind::Episode => "episode",
+            CalendarEventKind::SeasonPremiere => "season_premiere",
+            CalendarEventKind::RegionalRelease => "regional_release",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            CalendarEventKind::Release,
+            CalendarEventKind::Viewing,
+            CalendarEventKind::Deadline,
+            CalendarEventKind::Episode,
+            CalendarEventKind::SeasonPremiere,
+            CalendarEventKind::RegionalRelease,
+        ]
+        .into_iter()
+        .find(|k| k.as_str() == value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarEvent {
+    pub movie_id: u64,
+    pub title: String, // The movie's
+    pub kind: CalendarEventKind,
+    pub date: String, // YYYY-MM-DD
+    pub name: Option<String>, // Episode or season name
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    pub region: Option<String>, // ISO 3166-1, regional releases only
+    pub release_type: Option<u8>, // As TMDB: 1 premiere, 2 limited, 3 theatrical, 4 digital, 5 physical, 6 TV
+}
+
+/// Dates as YYYY-MM-DD, both included.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarRange {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
 pub mod tmdb;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1,11 +1,94 @@
 use serde::{Deserialize, Serialize};
-use reqwest::blocking::Client;
-
-const TMDB_BASE_URL: &str = "https://api.themoviedb.org/3";
+use crate::models::Movie;
 
+/// A raw search response; results are told apart by `media_type`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TmdbSearchResult {
+    #[serde(default)]
+    pub page: u64,
+    pub results: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub total_results: u64,
+    #[serde(default)]
+    pub total_pages: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TmdbSearchKind {
+    #[default]
+    Multi,
+    Movie,
+    Tv,
+}
+
+/// What to search for. `year` is sent as `year` for movies and as
+/// `first_air_date_year` for TV shows; multi-search has no year parameter,
+/// so other years are dropped from the page instead. `region` only applies
+/// to movie searches.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TmdbSearchOptions {
+    #[serde(default)]
+    pub kind: TmdbSearchKind,
+    #[serde(default)]
+    pub page: Option<u64>,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub region: Option<String>, // ISO 3166-1
+    #[serde(default)]
+    pub include_adult: bool,
+}
+
+/// One page of search results. People found by a multi-search come apart
+/// from the titles; the totals are TMDB's, for the whole search, and left
+/// out when a multi-search is filtered by year here.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TmdbSearchPage {
+    pub page: u64,
+    pub total_results: Option<u64>,
+    pub total_pages: Option<u64>,
     pub results: Vec<TmdbMovie>,
+    pub people: Vec<TmdbPerson>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TmdbPerson {
+    pub id: u64,
+    pub name: String,
+    pub original_name: Option<String>,
+    pub profile_path: Option<String>,
+    pub known_for_department: Option<String>,
+}
+
+/// A person with the titles they worked on (`/person/{id}` with
+/// `combined_credits` appended).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbPersonDetail {
+    pub id: u64,
+    pub name: String,
+    pub profile_path: Option<String>,
+    pub known_for_department: Option<String>,
+    pub biography: Option<String>,
+    pub birthday: Option<String>,
+    pub deathday: Option<String>,
+    pub combined_credits: Option<TmdbPersonCredits>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbPersonCredits {
+    #[serde(default)]
+    pub cast: Vec<TmdbPersonCredit>,
+    #[serde(default)]
+    pub crew: Vec<TmdbPersonCredit>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbPersonCredit {
+    #[serde(flatten)]
+    pub movie: TmdbMovie,
+    pub character: Option<String>, // Cast credits
+    pub job: Option<String>, // Crew credits
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,11 +122,241 @@ pub struct TmdbGenre {
     pub name: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbAlternativeTitles {
+    #[serde(default, alias = "results")] // Movies call the list `titles`, TV shows `results`
+    pub titles: Vec<TmdbAlternativeTitle>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbAlternativeTitle {
+    pub iso_3166_1: Option<String>,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbTranslations {
+    #[serde(default)]
+    pub translations: Vec<TmdbTranslation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbTranslation {
+    pub iso_639_1: String,
+    pub iso_3166_1: String,
+    pub data: TmdbTranslationData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbTranslationData {
+    pub title: Option<String>,
+    pub name: Option<String>, // For TV shows
+    pub overview: Option<String>,
+}
+
+impl TmdbTranslationData {
+    pub fn display_title(&self) -> Option<&str> {
+        self.title.as_deref().or(self.name.as_deref()).filter(|t| !t.trim().is_empty())
+    }
+
+    pub fn display_overview(&self) -> Option<&str> {
+        self.overview.as_deref().filter(|o| !o.trim().is_empty())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbExternalIds {
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbCountry {
+    pub iso_3166_1: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbCollection {
+    pub id: u64,
+    pub name: String,
+    pub poster_path: Option<String>,
+}
+
+/// A movie's release dates per country (`release_dates` appended to details).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbReleaseDates {
+    #[serde(default)]
+    pub results: Vec<TmdbCountryReleases>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbCountryReleases {
+    pub iso_3166_1: String,
+    #[serde(default)]
+    pub release_dates: Vec<TmdbReleaseDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbReleaseDate {
+    #[serde(default)]
+    pub certification: String,
+    pub release_date: Option<String>,
+    #[serde(rename = "type")]
+    pub release_type: Option<u8>, // 1 premiere, 2 limited, 3 theatrical, 4 digital, 5 physical, 6 TV
+}
+
+/// A TV show's content ratings per country (`content_ratings` appended to details).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbContentRatings {
+    #[serde(default)]
+    pub results: Vec<TmdbContentRating>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbContentRating {
+    pub iso_3166_1: String,
+    #[serde(default)]
+    pub rating: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TmdbDetailResponse {
     pub credits: Option<TmdbCredits>,
     pub genres: Option<Vec<TmdbGenre>>,
     pub runtime: Option<u64>,
+    #[serde(default)]
+    pub episode_run_time: Vec<u64>, // For TV shows
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>, // For TV shows
+    #[serde(default)]
+    pub original_title: Option<String>,
+    #[serde(default)]
+    pub original_name: Option<String>,
+    #[serde(default)]
+    pub original_language: Option<String>,
+    #[serde(default)]
+    pub overview: Option<String>,
+    #[serde(default)]
+    pub poster_path: Option<String>,
+    #[serde(default)]
+    pub release_date: Option<String>,
+    #[serde(default)]
+    pub first_air_date: Option<String>,
+    #[serde(default)]
+    pub vote_average: Option<f64>,
+    #[serde(default)]
+    pub alternative_titles: Option<TmdbAlternativeTitles>,
+    #[serde(default)]
+    pub translations: Option<TmdbTranslations>,
+    #[serde(default)]
+    pub external_ids: Option<TmdbExternalIds>,
+    #[serde(default)]
+    pub production_countries: Vec<TmdbCountry>,
+    #[serde(default)]
+    pub origin_country: Vec<String>, // For TV shows
+    #[serde(default)]
+    pub belongs_to_collection: Option<TmdbCollection>,
+    #[serde(default)]
+    pub release_dates: Option<TmdbReleaseDates>,
+    #[serde(default)]
+    pub content_ratings: Option<TmdbContentRatings>,
+    /// Alternative and translated titles, filled in by the client.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// The certification for the first configured region that has one,
+    /// filled in by the client.
+    #[serde(default)]
+    pub certification: Option<String>,
+}
+
+impl TmdbDetailResponse {
+    /// Minutes; TV shows give the usual episode length.
+    pub fn display_runtime(&self) -> Option<u64> {
+        self.runtime.or(self.episode_run_time.first().copied()).filter(|r| *r > 0)
+    }
+
+    /// ISO 3166-1 codes of the production countries.
+    pub fn country_codes(&self) -> Vec<String> {
+        let mut codes: Vec<String> = self.production_countries.iter().map(|c| c.iso_3166_1.clone()).collect();
+        for code in &self.origin_country {
+            if !codes.contains(code) {
+                codes.push(code.clone());
+            }
+        }
+        codes
+    }
+}
+
+/// Results of `/find/{external_id}`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbFindResponse {
+    #[serde(default)]
+    pub movie_results: Vec<TmdbMovie>,
+    #[serde(default)]
+    pub tv_results: Vec<TmdbMovie>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbImages {
+    #[serde(default)]
+    pub posters: Vec<TmdbImage>,
+    #[serde(default)]
+    pub backdrops: Vec<TmdbImage>,
+    #[serde(default)]
+    pub logos: Vec<TmdbImage>,
+    #[serde(default)]
+    pub stills: Vec<TmdbImage>, // Episode images only
+}
+
+/// A TV season with its episodes (`/tv/{id}/season/{n}`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbSeason {
+    pub season_number: u32,
+    pub name: Option<String>,
+    pub air_date: Option<String>,
+    #[serde(default)]
+    pub episodes: Vec<TmdbEpisode>,
+}
+
+/// What the calendar needs of a TV show (`/tv/{id}`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbTvSchedule {
+    pub id: u64,
+    pub status: Option<String>, // "Returning Series", "Ended"…
+    pub next_episode_to_air: Option<TmdbEpisode>,
+    #[serde(default)]
+    pub seasons: Vec<TmdbSeasonSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbSeasonSummary {
+    pub season_number: u32,
+    pub name: Option<String>,
+    pub air_date: Option<String>,
+    pub episode_count: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TmdbEpisode {
+    pub episode_number: u32,
+    #[serde(default)]
+    pub season_number: u32,
+    pub name: Option<String>,
+    pub air_date: Option<String>,
+    pub still_path: Option<String>,
+    pub runtime: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmdbImage {
+    pub file_path: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub iso_639_1: Option<String>,
+    pub vote_average: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -62,95 +375,36 @@ pub struct TmdbMovie {
 }
 
 
+const TMDB_IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p/w500";
 
-use reqwest::header;
-
-pub fn create_client(proxy: Option<String>) -> Result<Client, String> {
-    let mut headers = header::HeaderMap::new();
-    headers.insert("accept", header::HeaderValue::from_static("application/json"));
-
-    let mut client_builder = Client::builder()
-        .default_headers(headers)
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36");
-
-    if let Some(proxy_url) = proxy {
-        if !proxy_url.trim().is_empty() {
-             // println!("Using Proxy: {}", proxy_url);
-             let proxy = reqwest::Proxy::all(&proxy_url).map_err(|e| format!("Proxy config error: {}", e))?;
-             client_builder = client_builder.proxy(proxy);
-        }
+impl TmdbMovie {
+    pub fn display_title(&self) -> Option<&str> {
+        self.title.as_deref().or(self.name.as_deref())
     }
-    
-    client_builder.build().map_err(|e| e.to_string())
-}
-
-pub fn search_tmdb(api_key: &str, query: &str, page: u64, proxy: Option<String>) -> Result<Vec<TmdbMovie>, String> {
-    let client = create_client(proxy)?;
-    let url = format!("{}/search/multi", TMDB_BASE_URL);
-    
-    let params = [
-        ("api_key", api_key),
-        ("query", query),
-        ("language", "zh-CN"),
-        ("page", &page.to_string()),
-    ];
-
-    let response = client.get(&url)
-        .query(&params)
-        .send()
-        .map_err(|e| e.to_string())?;
-    
-    if response.status().is_success() {
-        let result: TmdbSearchResult = response.json().map_err(|e| e.to_string())?;
-        Ok(result.results)
-    } else {
-        Err(format!("TMDB API Error: {}", response.status()))
-    }
-}
 
-pub fn test_connection(api_key: &str, proxy: Option<String>) -> Result<String, String> {
-    let client = create_client(proxy)?;
-    let url = format!("{}/configuration", TMDB_BASE_URL);
-    
-    let response = client.get(&url)
-        .query(&[("api_key", api_key)])
-        .send()
-        .map_err(|e| {
-            if e.is_timeout() {
-                "Connection Timed Out. Please check your network or proxy settings.".to_string()
-            } else if e.is_connect() {
-                format!("Connection Failed: Could not connect to TMDB. Check proxy settings. Error: {}", e)
-            } else {
-                format!("Connection Failed: {}", e)
-            }
-        })?;
+    pub fn display_original_title(&self) -> Option<&str> {
+        self.original_title.as_deref().or(self.original_name.as_deref())
+    }
 
-    if response.status().is_success() {
-        Ok("Connection Successful!".to_string())
-    } else {
-        Err(format!("TMDB API Error: {} - {}", response.status(), response.text().unwrap_or_default()))
+    pub fn display_date(&self) -> Option<&str> {
+        self.release_date.as_deref().or(self.first_air_date.as_deref()).filter(|d| !d.is_empty())
     }
-}
 
-pub fn get_movie_details(api_key: &str, id: u64, media_type: &str, proxy: Option<String>) -> Result<TmdbDetailResponse, String> {
-    let client = create_client(proxy)?;
-    let url = format!("{}/{}/{}", TMDB_BASE_URL, media_type, id); // media_type should be "movie" or "tv"
-    
-    let params = [
-        ("api_key", api_key),
-        ("language", "zh-CN"),
-        ("append_to_response", "credits"),
-    ];
-
-    let response = client.get(&url)
-        .query(&params)
-        .send()
-        .map_err(|e| e.to_string())?;
-    
-    if response.status().is_success() {
-        let result: TmdbDetailResponse = response.json().map_err(|e| e.to_string())?;
-        Ok(result)
-    } else {
-        Err(format!("TMDB API Error: {}", response.status()))
+    /// Build a library entry the same way the add dialog does.
+    pub fn to_movie(&self) -> Movie {
+        Movie {
+            tmdb_id: Some(self.id),
+            title: self.display_title().unwrap_or("未知标题").to_string(),
+            original_title: self.display_original_title().map(|s| s.to_string()),
+            overview: self.overview.clone(),
+            poster_path: self.poster_path.as_ref().map(|p| {
+                if p.starts_with("http") { p.clone() } else { format!("{}{}", TMDB_IMAGE_BASE_URL, p) }
+            }),
+            release_date: self.display_date().map(|s| s.to_string()),
+            vote_average: self.vote_average,
+            category: Some(self.media_type.clone().unwrap_or_else(|| "movie".to_string())),
+            add_time: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            ..Default::default()
+        }
     }
 }
@@ -0,0 +1,176 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// How paths compare on a platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// `\` and `/` separators, drive letters, UNC and `\\?\` prefixes, case-insensitive
+    Windows,
+    /// `/` separators, case-insensitive (the APFS/HFS+ default)
+    MacOs,
+    /// `/` separators, case-sensitive; `\` is an ordinary file name character
+    Posix,
+}
+
+impl PathStyle {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            PathStyle::Windows
+        } else if cfg!(target_os = "macos") {
+            PathStyle::MacOs
+        } else {
+            PathStyle::Posix
+        }
+    }
+
+    pub fn case_insensitive(self) -> bool {
+        self != PathStyle::Posix
+    }
+
+    /// Comparison key for a path: `/` separators, no verbatim prefix, no
+    /// repeated or trailing separators, Unicode NFC (macOS hands out NFD
+    /// names) and lowercase where the file system ignores case.
+    ///
+    /// The key is for comparing only, never for opening files.
+    pub fn normalize(self, path: &str) -> String {
+        let path: String = path.trim().nfc().collect();
+        let path = match self {
+            PathStyle::Windows => strip_verbatim(&path).replace('\\', "/"),
+            _ => path,
+        };
+
+        // A leading `//` is a UNC share on Windows and must survive the collapsing below
+        let unc = self == PathStyle::Windows && path.starts_with("//");
+        let mut out = String::with_capacity(path.len());
+        if unc {
+            out.push('/');
+        }
+        let mut last_was_sep = false;
+        for c in path.chars() {
+            if c == '/' {
+                if !last_was_sep {
+                    out.push(c);
+                }
+                last_was_sep = true;
+            } else {
+                out.push(c);
+                last_was_sep = false;
+            }
+        }
+
+        // Keep the root itself: "/" or "c:/"
+        while out.ends_with('/') && out.len() > 1 && !(self == PathStyle::Windows && is_drive_root(&out)) {
+            out.pop();
+        }
+
+        if self.case_insensitive() {
+            out.to_lowercase()
+        } else {
+            out
+        }
+    }
+
+    pub fn same_path(self, a: &str, b: &str) -> bool {
+        self.normalize(a) == self.normalize(b)
+    }
+
+    /// Whether `path` is `folder` or lies somewhere below it. Compares whole
+    /// components, so `D:/Movies2` is not inside `D:/Movies`.
+    pub fn is_within(self, path: &str, folder: &str) -> bool {
+        let path = self.normalize(path);
+        let folder = self.normalize(folder);
+        if path == folder {
+            return true;
+        }
+        let prefix = if folder.ends_with('/') { folder } else { folder + "/" };
+        path.starts_with(&prefix)
+    }
+}
+
+fn is_drive_root(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() == 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'/'
+}
+
+/// Turn a Win32 verbatim path back into its usual form:
+/// `\\?\C:\x` becomes `C:\x` and `\\?\UNC\server\share` becomes `\\server\share`.
+pub fn strip_verbatim(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\").or_else(|| path.strip_prefix("//?/UNC/")) {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\").or_else(|| path.strip_prefix("//?/")) {
+        rest.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// [`PathStyle::normalize`] with the current platform's rules.
+pub fn normalize(path: &str) -> String {
+    PathStyle::current().normalize(path)
+}
+
+/// [`PathStyle::same_path`] with the current platform's rules.
+pub fn same_path(a: &str, b: &str) -> bool {
+    PathStyle::current().same_path(a, b)
+}
+
+/// [`PathStyle::is_within`] with the current platform's rules.
+pub fn is_within(path: &str, folder: &str) -> bool {
+    PathStyle::current().is_within(path, folder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NFD_E_ACUTE: &str = "Ame\u{301}lie";
+    const NFC_E_ACUTE: &str = "Am\u{e9}lie";
+
+    #[test]
+    fn windows_separators_case_and_trailing_slashes() {
+        let w = PathStyle::Windows;
+        assert_eq!(w.normalize(r"D:\Movies\Iron Man\"), "d:/movies/iron man");
+        assert_eq!(w.normalize("D:/Movies//Iron Man/"), "d:/movies/iron man");
+        assert_eq!(w.normalize(r"D:\"), "d:/");
+        assert!(w.same_path(r"D:\MOVIES\a.mp4", "d:/movies/A.mp4"));
+    }
+
+    #[test]
+    fn windows_verbatim_and_unc_prefixes() {
+        let w = PathStyle::Windows;
+        assert_eq!(w.normalize(r"\\?\D:\Movies\a.mp4"), "d:/movies/a.mp4");
+        assert_eq!(w.normalize(r"\\?\UNC\nas\share\a.mp4"), "//nas/share/a.mp4");
+        assert_eq!(w.normalize(r"\\NAS\Share\\a.mp4"), "//nas/share/a.mp4");
+        assert!(w.is_within(r"\\?\UNC\nas\share\films\a.mp4", r"\\nas\share"));
+        assert_eq!(strip_verbatim(r"\\?\UNC\nas\share"), r"\\nas\share");
+        assert_eq!(strip_verbatim(r"\\?\C:\x"), r"C:\x");
+    }
+
+    #[test]
+    fn windows_scope_checks_respect_components() {
+        let w = PathStyle::Windows;
+        assert!(w.is_within(r"D:\Movies\a.mp4", r"d:\movies\"));
+        assert!(w.is_within(r"D:\Movies", "D:/Movies/"));
+        assert!(!w.is_within(r"D:\Movies2\a.mp4", r"D:\Movies"));
+        assert!(w.is_within(r"D:\a.mp4", r"D:\"));
+    }
+
+    #[test]
+    fn posix_is_case_sensitive_and_keeps_backslashes() {
+        let p = PathStyle::Posix;
+        assert_eq!(p.normalize("/srv/Movies//Iron Man/"), "/srv/Movies/Iron Man");
+        assert_eq!(p.normalize("/"), "/");
+        assert!(!p.same_path("/srv/Movies/a.mp4", "/srv/movies/a.mp4"));
+        assert!(!p.is_within("/srv/movies/a.mp4", "/srv/Movies"));
+        assert!(p.is_within("/srv/Movies/sub/a.mp4", "/srv/Movies/"));
+        assert!(!p.is_within("/srv/Movies2/a.mp4", "/srv/Movies"));
+        assert_eq!(p.normalize(r"/srv/a\b.mp4"), r"/srv/a\b.mp4");
+    }
+
+    #[test]
+    fn unicode_forms_compare_equal() {
+        for style in [PathStyle::Windows, PathStyle::MacOs, PathStyle::Posix] {
+            assert!(style.same_path(&format!("/films/{}", NFD_E_ACUTE), &format!("/films/{}", NFC_E_ACUTE)));
+        }
+        assert!(PathStyle::MacOs.same_path("/Films/A.mp4", "/films/a.mp4"));
+    }
+}
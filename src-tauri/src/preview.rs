@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use xxhash_rust::xxh3::xxh3_64;
+use crate::commands::files::file_type_of;
+use crate::commands::media::run_media_tool;
+use crate::db::Database;
+use crate::metadata::document;
+use crate::models::MaterialPreview;
+use crate::paths;
+
+/// Peaks per waveform; enough for a full-width track in the detail view.
+const WAVEFORM_BUCKETS: usize = 1000;
+/// Sample rate the audio is decoded at for peaks; plenty for a picture.
+const WAVEFORM_SAMPLE_RATE: u32 = 4000;
+const THUMBNAIL_WIDTH: u32 = 480;
+const SPRITE_FRAMES: u32 = 10;
+const SPRITE_FRAME_WIDTH: u32 = 160;
+const IMAGE_MAX_WIDTH: u32 = 800;
+const SNIPPET_CHARS: usize = 500;
+
+/// Written as `<key>.waveform.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaveformData {
+    pub sample_rate: u32,
+    pub duration: f64,
+    pub peaks: Vec<f32>, // 0..1, the loudest sample of each bucket
+}
+
+pub fn previews_dir(db: &Database) -> PathBuf {
+    db.get_root_dir().join("previews")
+}
+
+/// Previews are keyed by path, size and modification time, so editing or
+/// replacing a file gets it new previews. None if the file can't be read.
+pub fn preview_key(path: &str) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let source = format!("{}|{}|{}", paths::normalize(path), metadata.len(), modified);
+    Some(format!("{:016x}", xxh3_64(source.as_bytes())))
+}
+
+/// Run ffmpeg into a temporary file next to `target` and move it into place
+/// once complete, so an interrupted run never leaves a half-written preview.
+async fn ffmpeg_to(app: &AppHandle, db: &Database, target: &Path, before_input: &[&str], input: &str, args: &[&str]) -> Result<(), String> {
+    let ext = target.extension().and_then(|e| e.to_str()).unwrap_or("tmp");
+    let tmp = target.with_extension(format!("part.{}", ext));
+    let tmp_str = tmp.to_string_lossy().to_string();
+
+    let mut all: Vec<&str> = vec!["-v", "error"];
+    all.extend_from_slice(before_input);
+    all.extend_from_slice(&["-i", input]);
+    all.extend_from_slice(args);
+    all.extend_from_slice(&["-y", &tmp_str]);
+
+    let (success, _, stderr) = run_media_tool(app, db, "ffmpeg", &all).await?;
+    if !success || !tmp.exists() {
+        let _ = fs::remove_file(&tmp);
+        return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&stderr).trim()));
+    }
+    fs::rename(&tmp, target).map_err(|e| e.to_string())
+}
+
+async fn duration_of(app: &AppHandle, db: &Database, path: &str) -> Option<f64> {
+    let (success, stdout, _) = run_media_tool(
+        app, db, "ffprobe",
+        &["-v", "quiet", "-show_entries", "format=duration", "-of", "csv=p=0", path],
+    ).await.ok()?;
+    if !success {
+        return None;
+    }
+    String::from_utf8_lossy(&stdout).trim().parse().ok()
+}
+
+/// Loudest sample per bucket from signed 16-bit little-endian mono PCM.
+fn peaks(pcm: &[u8], buckets: usize) -> Vec<f32> {
+    let samples: Vec<i16> = pcm.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let per_bucket = samples.len().div_ceil(buckets);
+    samples.chunks(per_bucket)
+        .map(|chunk| chunk.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0) as f32 / 32768.0)
+        .collect()
+}
+
+async fn audio_previews(app: &AppHandle, db: &Database, path: &str, base: &Path, preview: &mut MaterialPreview) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let json_path = base.with_extension("waveform.json");
+    if !json_path.exists() {
+        let rate = WAVEFORM_SAMPLE_RATE.to_string();
+        let result = run_media_tool(
+            app, db, "ffmpeg",
+            &["-v", "error", "-i", path, "-ac", "1", "-ar", &rate, "-f", "s16le", "-"],
+        ).await.and_then(|(success, pcm, stderr)| {
+            if !success {
+                return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&stderr).trim()));
+            }
+            let data = WaveformData {
+                sample_rate: WAVEFORM_SAMPLE_RATE,
+                duration: pcm.len() as f64 / 2.0 / WAVEFORM_SAMPLE_RATE as f64,
+                peaks: peaks(&pcm, WAVEFORM_BUCKETS),
+            };
+            let json = serde_json::to_string(&data).map_err(|e| e.to_string())?;
+            fs::write(&json_path, json).map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            errors.push(format!("waveform data: {}", e));
+        }
+    }
+    if json_path.exists() {
+        preview.waveform = Some(json_path.to_string_lossy().to_string());
+    }
+
+    let png_path = base.with_extension("waveform.png");
+    if !png_path.exists() {
+        let filter = "showwavespic=s=1200x200:colors=#1677ff";
+        if let Err(e) = ffmpeg_to(app, db, &png_path, &[], path, &["-filter_complex", filter, "-frames:v", "1"]).await {
+            errors.push(format!("waveform image: {}", e));
+        }
+    }
+    if png_path.exists() {
+        preview.waveform_image = Some(png_path.to_string_lossy().to_string());
+    }
+    errors
+}
+
+async fn video_previews(app: &AppHandle, db: &Database, path: &str, base: &Path, preview: &mut MaterialPreview) -> Vec<String> {
+    let mut errors = Vec::new();
+    let thumb_path = base.with_extension("thumb.jpg");
+    let sprite_path = base.with_extension("sprite.jpg");
+    let duration = if thumb_path.exists() && sprite_path.exists() {
+        None
+    } else {
+        duration_of(app, db, path).await
+    };
+
+    if !thumb_path.exists() {
+        // A tenth in usually skips black frames and title cards; seeking lands on a keyframe
+        let at = format!("{:.2}", duration.map(|d| d * 0.1).unwrap_or(0.0));
+        let scale = format!("scale={}:-2", THUMBNAIL_WIDTH);
+        if let Err(e) = ffmpeg_to(app, db, &thumb_path, &["-ss", &at], path, &["-frames:v", "1", "-vf", &scale]).await {
+            errors.push(format!("thumbnail: {}", e));
+        }
+    }
+    if thumb_path.exists() {
+        preview.thumbnail = Some(thumb_path.to_string_lossy().to_string());
+    }
+
+    if !sprite_path.exists() {
+        match duration.filter(|d| *d > 0.0) {
+            Some(duration) => {
+                // Only keyframes are decoded, spread evenly over the whole video
+                let filter = format!(
+                    "fps={}/{:.3},scale={}:-2,tile={}x1",
+                    SPRITE_FRAMES, duration, SPRITE_FRAME_WIDTH, SPRITE_FRAMES
+                );
+                let result = ffmpeg_to(
+                    app, db, &sprite_path, &["-skip_frame", "nokey"], path,
+                    &["-an", "-vf", &filter, "-frames:v", "1"],
+                ).await;
+                if let Err(e) = result {
+                    errors.push(format!("sprite: {}", e));
+                }
+            }
+            None => errors.push("sprite: unknown duration".to_string()),
+        }
+    }
+    if sprite_path.exists() {
+        preview.sprite = Some(sprite_path.to_string_lossy().to_string());
+        preview.sprite_frames = Some(SPRITE_FRAMES);
+    }
+    errors
+}
+
+async fn image_preview(app: &AppHandle, db: &Database, path: &str, base: &Path, preview: &mut MaterialPreview) -> Vec<String> {
+    let mut errors = Vec::new();
+    let image_path = base.with_extension("image.jpg");
+    if !image_path.exists() {
+        let scale = format!("scale='min({},iw)':-2", IMAGE_MAX_WIDTH);
+        if let Err(e) = ffmpeg_to(app, db, &image_path, &[], path, &["-vf", &scale, "-frames:v", "1"]).await {
+            errors.push(format!("image: {}", e));
+        }
+    }
+    if image_path.exists() {
+        preview.image = Some(image_path.to_string_lossy().to_string());
+    }
+    errors
+}
+
+async fn doc_preview(path: &str, base: &Path, preview: &mut MaterialPreview) -> Vec<String> {
+    let text_path = base.with_extension("snippet.txt");
+    if let Ok(text) = fs::read_to_string(&text_path) {
+        preview.text = Some(text);
+        return Vec::new();
+    }
+
+    let owned = path.to_string();
+    let extracted = tauri::async_runtime::spawn_blocking(move || document::extract_text(Path::new(&owned)))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    match extracted {
+        Ok(doc) => {
+            let snippet: String = doc.text.trim().chars().take(SNIPPET_CHARS).collect();
+            let _ = fs::write(&text_path, &snippet);
+            preview.text = Some(snippet);
+            Vec::new()
+        }
+        Err(e) => vec![format!("text: {}", e)],
+    }
+}
+
+/// Previews of one file, generating whatever isn't cached yet. Paths in the
+/// result are served to the frontend through the `asset` protocol.
+pub async fn get_or_create(app: &AppHandle, db: &Database, path: &str) -> Result<MaterialPreview, String> {
+    let key = preview_key(path).ok_or_else(|| format!("{} can't be read", path))?;
+    let file_type = file_type_of(Path::new(path)).unwrap_or("other");
+    let dir = previews_dir(db);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // `with_extension` on the base builds `<key>.<kind>.<ext>`
+    let base = dir.join(&key);
+
+    let mut preview = MaterialPreview {
+        key: key.clone(),
+        file_type: file_type.to_string(),
+        ..Default::default()
+    };
+    preview.errors = match file_type {
+        "audio" => audio_previews(app, db, path, &base, &mut preview).await,
+        "video" => video_previews(app, db, path, &base, &mut preview).await,
+        "image" => image_preview(app, db, path, &base, &mut preview).await,
+        "doc" => doc_preview(path, &base, &mut preview).await,
+        _ => Vec::new(),
+    };
+    db.save_preview_key(&key, path).map_err(|e| e.to_string())?;
+    Ok(preview)
+}
+
+/// Delete previews of files that are no longer linked to any movie, or that
+/// changed since their previews were made. Returns how many files were removed.
+pub fn collect_garbage(db: &Database) -> Result<usize, String> {
+    let linked: HashSet<String> = db.get_movies().iter()
+        .flat_map(|m| m.materials.iter().map(|mat| paths::normalize(&mat.path)))
+        .collect();
+
+    let mut stale = HashSet::new();
+    for (key, path) in db.get_preview_keys().map_err(|e| e.to_string())? {
+        if !linked.contains(&paths::normalize(&path)) || preview_key(&path).as_deref() != Some(key.as_str()) {
+            stale.insert(key);
+        }
+    }
+    if stale.is_empty() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    if let Ok(entries) = fs::read_dir(previews_dir(db)) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let key = name.split('.').next().unwrap_or("");
+            if stale.contains(key) && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    let keys: Vec<String> = stale.into_iter().collect();
+    db.delete_preview_keys(&keys).map_err(|e| e.to_string())?;
+    Ok(removed)
+}
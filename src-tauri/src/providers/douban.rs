@@ -0,0 +1,174 @@
+use std::sync::OnceLock;
+use async_trait::async_trait;
+use regex::Regex;
+use crate::models::{Movie, Person, ProviderHit, ProviderId, ProviderImage, ProviderMetadata};
+use crate::tmdb::RateLimiter;
+use super::MetadataProvider;
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+/// Douban blocks IPs that send bursts, so requests go out one at a time.
+const REQUESTS_PER_SECOND: u32 = 1;
+
+/// Douban through the API wp-douban (fatesinger.com) uses for subjects, and
+/// the site's own suggest endpoint for search.
+pub struct DoubanProvider;
+
+/// Shared by every Douban request, whichever command sends it.
+fn limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(1, REQUESTS_PER_SECOND))
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// The subject ID from a douban.com URL, or the input itself.
+pub fn parse_subject_id(url_or_id: &str) -> String {
+    if url_or_id.contains("douban.com") {
+        if let Ok(re) = Regex::new(r"subject/(\d+)") {
+            if let Some(m) = re.captures(url_or_id).and_then(|caps| caps.get(1)) {
+                return m.as_str().to_string();
+            }
+        }
+    }
+    url_or_id.to_string()
+}
+
+fn people(list: &serde_json::Value) -> Vec<Person> {
+    list.as_array().into_iter().flatten()
+        .filter_map(|p| {
+            let name = p["name"].as_str()?;
+            Some(Person {
+                id: 0,
+                name: name.split_whitespace().next().unwrap_or(name).to_string(),
+                original_name: None,
+                profile_path: p["avatars"]["large"].as_str().map(|s| s.to_string()),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Fetch a subject. Reference: wp-douban-4.4.3/src/functions.php fetch_subject.
+/// The /movie/ endpoint redirects TV subjects, /tv/ is only used when asked for.
+pub async fn fetch_subject(douban_id: &str, is_tv: bool) -> Result<ProviderMetadata, String> {
+    let endpoint = if is_tv { "tv" } else { "movie" };
+    let url = format!("https://fatesinger.com/dbapi/{}/{}?ck=xgtY&for_mobile=1", endpoint, douban_id);
+    println!("Fetching Douban subject: {}", url);
+
+    limiter().acquire().await;
+    let res = client()?.get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if !res.status().is_success() {
+        return Err(format!("Douban API returned status: {}", res.status()));
+    }
+    let data: serde_json::Value = res.json().await.map_err(|e| format!("JSON parse error: {}", e))?;
+
+    let mut meta = ProviderMetadata::new(ProviderId::Douban);
+    meta.provider_item_id = Some(douban_id.to_string());
+    meta.douban_id = Some(douban_id.to_string());
+    meta.title = data["title"].as_str().map(|s| s.to_string());
+    meta.original_title = data["original_title"].as_str().filter(|s| !s.is_empty()).map(|s| s.to_string());
+    meta.poster = data["pic"]["large"].as_str().or(data["pic"]["normal"].as_str()).map(|s| s.to_string());
+    // Intro if there is one, else the card subtitle
+    meta.overview = data["intro"].as_str().or(data["card_subtitle"].as_str()).map(|s| s.to_string());
+
+    meta.release_date = data["pubdate"].as_array()
+        .and_then(|dates| dates.first())
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| data["year"].as_str().map(|s| s.to_string()))
+        .or_else(|| data["year"].as_u64().map(|y| y.to_string()));
+
+    meta.rating = data["rating"]["value"].as_f64()
+        .or_else(|| data["rating"]["value"].as_str().and_then(|s| s.parse::<f64>().ok()))
+        .filter(|r| *r > 0.0);
+
+    meta.genres = data["genres"].as_array().into_iter().flatten()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect();
+    meta.directors = people(&data["directors"]);
+    meta.actors = people(&data["actors"]);
+    meta.aliases = data["aka"].as_array().into_iter().flatten()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect();
+    meta.category = Some(if data["subtype"].as_str() == Some("tv") { "tv" } else { "movie" }.to_string());
+    Ok(meta)
+}
+
+/// The movie's Douban subject: its stored ID, or a search hit with exactly
+/// the same title and year.
+async fn find_subject(provider: &DoubanProvider, movie: &Movie) -> Result<Option<String>, String> {
+    if let Some(id) = &movie.douban_id {
+        return Ok(Some(id.clone()));
+    }
+    let year = movie.release_date.as_deref().map(|d| d.chars().take(4).collect::<String>());
+    let hits = provider.search(&movie.title).await?;
+    Ok(hits.into_iter()
+        .find(|h| {
+            let same_title = h.title == movie.title || h.original_title.is_some() && h.original_title == movie.original_title;
+            same_title && year.is_some() && h.year == year
+        })
+        .map(|h| h.id))
+}
+
+#[async_trait]
+impl MetadataProvider for DoubanProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::Douban
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<ProviderHit>, String> {
+        limiter().acquire().await;
+        let res = client()?.get("https://movie.douban.com/j/subject_suggest")
+            .query(&[("q", query)])
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("Douban search returned status: {}", res.status()));
+        }
+        let items: Vec<serde_json::Value> = res.json().await.map_err(|e| format!("JSON parse error: {}", e))?;
+        Ok(items.iter()
+            .filter(|i| i["type"].as_str() == Some("movie")) // The rest are people
+            .filter_map(|i| Some(ProviderHit {
+                provider: ProviderId::Douban,
+                id: i["id"].as_str()?.to_string(),
+                title: i["title"].as_str()?.to_string(),
+                original_title: i["sub_title"].as_str().filter(|s| !s.is_empty()).map(|s| s.to_string()),
+                year: i["year"].as_str().filter(|s| !s.is_empty()).map(|s| s.to_string()),
+                poster: i["img"].as_str().map(|s| s.to_string()),
+                category: None, // Not told apart by the suggest endpoint
+            }))
+            .collect())
+    }
+
+    async fn details(&self, movie: &Movie) -> Result<Option<ProviderMetadata>, String> {
+        match find_subject(self, movie).await? {
+            Some(id) => fetch_subject(&id, movie.category.as_deref() == Some("tv")).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn images(&self, movie: &Movie) -> Result<Vec<ProviderImage>, String> {
+        let Some(meta) = self.details(movie).await? else {
+            return Ok(Vec::new());
+        };
+        Ok(meta.poster.into_iter()
+            .map(|url| ProviderImage {
+                provider: ProviderId::Douban,
+                kind: "poster".to_string(),
+                url,
+                width: None,
+                height: None,
+                language: None,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,407 @@
+mod douban;
+mod nfo;
+mod tmdb;
+
+use std::collections::BTreeMap;
+use async_trait::async_trait;
+use serde_json::json;
+use crate::db::Database;
+use crate::models::{MetadataField, MetadataFieldChange, MetadataRefreshResult, Movie, MovieMetadataDiff, Person, ProviderHit, ProviderId, ProviderImage, ProviderMetadata};
+use crate::tmdb::TmdbClient;
+
+pub use douban::{fetch_subject, parse_subject_id, DoubanProvider};
+pub use nfo::NfoProvider;
+pub use tmdb::to_metadata as tmdb_metadata;
+
+/// A source of movie metadata. Providers find a title through the IDs and
+/// local paths already on the movie; `details` answers None when the
+/// provider doesn't know it.
+#[async_trait]
+pub trait MetadataProvider: Send + Sync {
+    fn id(&self) -> ProviderId;
+    async fn search(&self, query: &str) -> Result<Vec<ProviderHit>, String>;
+    async fn details(&self, movie: &Movie) -> Result<Option<ProviderMetadata>, String>;
+    async fn images(&self, movie: &Movie) -> Result<Vec<ProviderImage>, String>;
+}
+
+/// The providers enabled in the config, in config order.
+pub fn enabled(db: &Database, tmdb: &TmdbClient) -> Vec<Box<dyn MetadataProvider>> {
+    let mut providers: Vec<Box<dyn MetadataProvider>> = Vec::new();
+    for id in db.get_config().metadata_providers {
+        if providers.iter().any(|p| p.id() == id) {
+            continue;
+        }
+        providers.push(match id {
+            ProviderId::Tmdb => Box::new(tmdb.clone()),
+            ProviderId::Douban => Box::new(DoubanProvider),
+            ProviderId::Nfo => Box::new(NfoProvider::new(db.clone())),
+        });
+    }
+    providers
+}
+
+/// A library entry built from one provider's metadata alone, the way the
+/// add dialog does it.
+pub fn to_movie(meta: &ProviderMetadata) -> Movie {
+    let mut movie = Movie {
+        tmdb_id: meta.tmdb_id,
+        douban_id: meta.douban_id.clone(),
+        imdb_id: meta.imdb_id.clone(),
+        tvdb_id: meta.tvdb_id,
+        title: meta.title.clone().unwrap_or_default(),
+        original_title: meta.original_title.clone(),
+        overview: meta.overview.clone(),
+        poster_path: meta.poster.clone(),
+        release_date: meta.release_date.clone(),
+        vote_average: meta.rating,
+        category: Some(meta.category.clone().unwrap_or_else(|| "movie".to_string())),
+        genres: meta.genres.clone(),
+        actors: meta.actors.clone(),
+        directors: meta.directors.clone(),
+        runtime: meta.runtime,
+        countries: meta.countries.clone(),
+        original_language: meta.original_language.clone(),
+        certification: meta.certification.clone(),
+        collection: meta.collection.clone(),
+        add_time: chrono::Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+    if let Some(rating) = meta.rating {
+        movie.ratings.insert(meta.provider, rating);
+    }
+    merge_aliases(&mut movie, &meta.aliases);
+    movie
+}
+
+/// Add the titles that aren't aliases (or the title) yet. Returns whether any were new.
+pub fn merge_aliases(movie: &mut Movie, titles: &[String]) -> bool {
+    let mut aliases = movie.aliases.take().unwrap_or_default();
+    let before = aliases.len();
+    for title in titles {
+        let lower = title.to_lowercase();
+        let known = movie.title.to_lowercase() == lower
+            || movie.original_title.as_deref().is_some_and(|t| t.to_lowercase() == lower)
+            || aliases.iter().any(|a| a.to_lowercase() == lower);
+        if !known {
+            aliases.push(title.clone());
+        }
+    }
+    let changed = aliases.len() != before;
+    movie.aliases = if aliases.is_empty() { None } else { Some(aliases) };
+    changed
+}
+
+/// Fill what `movie` doesn't have yet from one provider's metadata, as done
+/// for titles just added from search results. Returns whether anything changed.
+pub fn fill_missing(movie: &mut Movie, meta: &ProviderMetadata) -> bool {
+    let before = movie.clone();
+    macro_rules! fill {
+        ($target:expr, $source:expr) => {
+            if $target.is_none() {
+                $target = $source.clone();
+            }
+        };
+    }
+    if text(&movie.overview).is_none() {
+        movie.overview = text(&meta.overview).cloned();
+    }
+    fill!(movie.imdb_id, meta.imdb_id);
+    fill!(movie.tvdb_id, meta.tvdb_id);
+    fill!(movie.runtime, meta.runtime);
+    fill!(movie.original_language, meta.original_language);
+    fill!(movie.certification, meta.certification);
+    fill!(movie.collection, meta.collection);
+    if movie.countries.is_empty() {
+        movie.countries = meta.countries.clone();
+    }
+    let new_aliases = merge_aliases(movie, &meta.aliases);
+    new_aliases || !changed_fields(&before, movie).is_empty()
+        || movie.imdb_id != before.imdb_id || movie.tvdb_id != before.tvdb_id
+}
+
+fn text(value: &Option<String>) -> Option<&String> {
+    value.as_ref().filter(|v| !v.trim().is_empty())
+}
+
+fn same_people(a: &[Person], b: &[Person]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.name == y.name)
+}
+
+/// The metadata of the first provider in `order` that has `field`. An
+/// empty order means the field is locked.
+fn pick<'a>(results: &'a [ProviderMetadata], order: &[ProviderId], has: impl Fn(&ProviderMetadata) -> bool) -> Option<&'a ProviderMetadata> {
+    if order.is_empty() {
+        return None;
+    }
+    order.iter()
+        .chain(results.iter().map(|r| &r.provider)) // Providers missing from the policy come last
+        .find_map(|id| results.iter().find(|r| r.provider == *id && has(r)))
+}
+
+/// Apply the provider results to `movie` field by field, following the merge
+/// policy. Ratings are kept per provider and aliases are pooled; posters and
+/// cast that were saved locally are only replaced by different ones.
+pub fn merge(movie: &mut Movie, results: &[ProviderMetadata], policy: &BTreeMap<MetadataField, Vec<ProviderId>>) -> Vec<MetadataField> {
+    let mut changed = Vec::new();
+    // Fields edited by hand get no providers at all; fields missing from a
+    // policy saved by an older version take the results as they come
+    let locked = movie.user_edited_fields.clone();
+    let order = |field: MetadataField| {
+        if locked.contains(&field) {
+            Vec::new()
+        } else {
+            policy.get(&field).cloned().unwrap_or_else(|| results.iter().map(|r| r.provider).collect())
+        }
+    };
+
+    macro_rules! merge_text {
+        ($field:expr, $source:ident, $target:expr) => {
+            if let Some(meta) = pick(results, &order($field), |r| text(&r.$source).is_some()) {
+                movie.field_sources.insert($field, meta.provider);
+                let value = text(&meta.$source).cloned();
+                if $target != value {
+                    $target = value;
+                    changed.push($field);
+                }
+            }
+        };
+    }
+
+    if let Some(meta) = pick(results, &order(MetadataField::Title), |r| text(&r.title).is_some()) {
+        movie.field_sources.insert(MetadataField::Title, meta.provider);
+        let title = text(&meta.title).cloned().unwrap_or_default();
+        if movie.title != title {
+            // Keep the old title findable
+            let old = std::mem::replace(&mut movie.title, title);
+            merge_aliases(movie, &[old]);
+            changed.push(MetadataField::Title);
+        }
+    }
+    merge_text!(MetadataField::OriginalTitle, original_title, movie.original_title);
+    merge_text!(MetadataField::Overview, overview, movie.overview);
+    merge_text!(MetadataField::ReleaseDate, release_date, movie.release_date);
+
+    let poster_is_local = movie.poster_path.as_deref().is_some_and(|p| !p.starts_with("http"));
+    if !poster_is_local {
+        merge_text!(MetadataField::Poster, poster, movie.poster_path);
+    }
+
+    for meta in results {
+        if let Some(rating) = meta.rating {
+            movie.ratings.insert(meta.provider, rating);
+        }
+    }
+    if let Some(meta) = pick(results, &order(MetadataField::Rating), |r| r.rating.is_some()) {
+        movie.field_sources.insert(MetadataField::Rating, meta.provider);
+        if movie.vote_average != meta.rating {
+            movie.vote_average = meta.rating;
+            changed.push(MetadataField::Rating);
+        }
+    }
+
+    if let Some(meta) = pick(results, &order(MetadataField::Genres), |r| !r.genres.is_empty()) {
+        movie.field_sources.insert(MetadataField::Genres, meta.provider);
+        if movie.genres != meta.genres {
+            movie.genres = meta.genres.clone();
+            changed.push(MetadataField::Genres);
+        }
+    }
+
+    if let Some(meta) = pick(results, &order(MetadataField::Cast), |r| !r.actors.is_empty() || !r.directors.is_empty()) {
+        movie.field_sources.insert(MetadataField::Cast, meta.provider);
+        if !same_people(&movie.actors, &meta.actors) || !same_people(&movie.directors, &meta.directors) {
+            movie.actors = meta.actors.clone();
+            movie.directors = meta.directors.clone();
+            changed.push(MetadataField::Cast);
+        }
+    }
+
+    macro_rules! merge_value {
+        ($field:expr, $source:ident, $target:expr, $has:expr) => {
+            if let Some(meta) = pick(results, &order($field), $has) {
+                movie.field_sources.insert($field, meta.provider);
+                if $target != meta.$source {
+                    $target = meta.$source.clone();
+                    changed.push($field);
+                }
+            }
+        };
+    }
+    merge_value!(MetadataField::Runtime, runtime, movie.runtime, |r| r.runtime.is_some());
+    merge_value!(MetadataField::Countries, countries, movie.countries, |r| !r.countries.is_empty());
+    merge_text!(MetadataField::OriginalLanguage, original_language, movie.original_language);
+    merge_text!(MetadataField::Certification, certification, movie.certification);
+    merge_value!(MetadataField::Collection, collection, movie.collection, |r| r.collection.is_some());
+
+    for meta in results {
+        merge_aliases(movie, &meta.aliases);
+        movie.tmdb_id = movie.tmdb_id.or(meta.tmdb_id);
+        movie.tvdb_id = movie.tvdb_id.or(meta.tvdb_id);
+        if movie.douban_id.is_none() {
+            movie.douban_id = meta.douban_id.clone();
+        }
+        if movie.imdb_id.is_none() {
+            movie.imdb_id = meta.imdb_id.clone();
+        }
+        if movie.category.is_none() {
+            movie.category = meta.category.clone();
+        }
+    }
+    changed
+}
+
+/// A field's value as JSON, as shown in refresh diffs.
+pub fn field_value(movie: &Movie, field: MetadataField) -> serde_json::Value {
+    match field {
+        MetadataField::Title => json!(movie.title),
+        MetadataField::OriginalTitle => json!(movie.original_title),
+        MetadataField::Overview => json!(movie.overview),
+        MetadataField::Poster => json!(movie.poster_path),
+        MetadataField::ReleaseDate => json!(movie.release_date),
+        MetadataField::Rating => json!(movie.vote_average),
+        MetadataField::Genres => json!(movie.genres),
+        MetadataField::Cast => json!({ "actors": movie.actors, "directors": movie.directors }),
+        MetadataField::Runtime => json!(movie.runtime),
+        MetadataField::Countries => json!(movie.countries),
+        MetadataField::OriginalLanguage => json!(movie.original_language),
+        MetadataField::Certification => json!(movie.certification),
+        MetadataField::Collection => json!(movie.collection),
+    }
+}
+
+/// Set a field from a value in the shape [`field_value`] returns.
+pub fn set_field_value(movie: &mut Movie, field: MetadataField, value: serde_json::Value) -> Result<(), String> {
+    fn parse<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Result<T, String> {
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+    match field {
+        MetadataField::Title => movie.title = parse(value)?,
+        MetadataField::OriginalTitle => movie.original_title = parse(value)?,
+        MetadataField::Overview => movie.overview = parse(value)?,
+        MetadataField::Poster => movie.poster_path = parse(value)?,
+        MetadataField::ReleaseDate => movie.release_date = parse(value)?,
+        MetadataField::Rating => movie.vote_average = parse(value)?,
+        MetadataField::Genres => movie.genres = parse(value)?,
+        MetadataField::Cast => {
+            #[derive(serde::Deserialize)]
+            struct Cast {
+                actors: Vec<Person>,
+                directors: Vec<Person>,
+            }
+            let cast: Cast = parse(value)?;
+            movie.actors = cast.actors;
+            movie.directors = cast.directors;
+        }
+        MetadataField::Runtime => movie.runtime = parse(value)?,
+        MetadataField::Countries => movie.countries = parse(value)?,
+        MetadataField::OriginalLanguage => movie.original_language = parse(value)?,
+        MetadataField::Certification => movie.certification = parse(value)?,
+        MetadataField::Collection => movie.collection = parse(value)?,
+    }
+    Ok(())
+}
+
+/// The metadata fields whose values differ between two versions of a movie.
+pub fn changed_fields(before: &Movie, after: &Movie) -> Vec<MetadataField> {
+    MetadataField::ALL.into_iter()
+        .filter(|f| field_value(before, *f) != field_value(after, *f))
+        .collect()
+}
+
+/// Details of `movie` from the given providers. NFO files go first, since
+/// the TMDB and Douban IDs they carry let the other providers find the title.
+/// Without `search` Douban is only asked about titles with a Douban ID, bulk
+/// refreshes would otherwise send it a search for every other title.
+pub async fn fetch_all(providers: &mut [Box<dyn MetadataProvider>], movie: &Movie, search: bool) -> (Vec<ProviderMetadata>, Vec<String>) {
+    providers.sort_by_key(|p| p.id() != ProviderId::Nfo);
+    let mut lookup = movie.clone();
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    for provider in providers.iter() {
+        if !search && provider.id() == ProviderId::Douban && lookup.douban_id.is_none() {
+            continue;
+        }
+        match provider.details(&lookup).await {
+            Ok(Some(meta)) => {
+                lookup.tmdb_id = lookup.tmdb_id.or(meta.tmdb_id);
+                if lookup.douban_id.is_none() {
+                    lookup.douban_id = meta.douban_id.clone();
+                }
+                if lookup.imdb_id.is_none() {
+                    lookup.imdb_id = meta.imdb_id.clone();
+                }
+                results.push(meta);
+            }
+            Ok(None) => {}
+            Err(e) => errors.push(format!("{:?}: {}", provider.id(), e)),
+        }
+    }
+    (results, errors)
+}
+
+/// What merging `results` would do to `movie`, without saving anything.
+/// Returns the merged movie along with the diff.
+pub fn diff(movie: &Movie, results: &[ProviderMetadata], policy: &BTreeMap<MetadataField, Vec<ProviderId>>) -> (Movie, MovieMetadataDiff) {
+    let mut merged = movie.clone();
+    let changed = merge(&mut merged, results, policy);
+
+    // Run once more without the locks to tell what they held back
+    let mut unlocked = movie.clone();
+    unlocked.user_edited_fields.clear();
+    let skipped = merge(&mut unlocked, results, policy).into_iter()
+        .filter(|f| movie.user_edited_fields.contains(f))
+        .collect();
+
+    let old_aliases = movie.aliases.clone().unwrap_or_default();
+    let diff = MovieMetadataDiff {
+        movie_id: movie.id,
+        title: movie.title.clone(),
+        changes: changed.into_iter()
+            .map(|field| MetadataFieldChange { field, old: field_value(movie, field), new: field_value(&merged, field) })
+            .collect(),
+        ratings: results.iter().filter_map(|r| r.rating.map(|v| (r.provider, v))).collect(),
+        new_aliases: merged.aliases.iter().flatten().filter(|a| !old_aliases.contains(a)).cloned().collect(),
+        skipped_fields: skipped,
+        errors: Vec::new(),
+        applied: false,
+    };
+    (merged, diff)
+}
+
+/// Apply a reviewed diff to the stored movie. Fields edited by hand since
+/// the diff was made are still left alone.
+pub fn apply_diff(db: &Database, diff: &MovieMetadataDiff) -> Result<bool, String> {
+    let mut movie = db.get_movie(diff.movie_id).ok_or("Movie not found")?;
+    let before = movie.clone();
+    for change in &diff.changes {
+        if !movie.user_edited_fields.contains(&change.field) {
+            set_field_value(&mut movie, change.field, change.new.clone())?;
+        }
+    }
+    movie.ratings.extend(diff.ratings.iter().map(|(k, v)| (*k, *v)));
+    merge_aliases(&mut movie, &diff.new_aliases);
+
+    let changed = !changed_fields(&before, &movie).is_empty() || movie.ratings != before.ratings || movie.aliases != before.aliases;
+    if changed {
+        db.update_movie(movie).map_err(|e| e.to_string())?;
+    }
+    Ok(changed)
+}
+
+/// Update a movie from every enabled provider.
+pub async fn refresh_movie(db: &Database, tmdb: &TmdbClient, movie_id: u64) -> Result<MetadataRefreshResult, String> {
+    let movie = db.get_movie(movie_id).ok_or("Movie not found")?;
+    let (results, errors) = fetch_all(&mut enabled(db, tmdb), &movie, true).await;
+
+    // Materials may have changed meanwhile, so merge into a fresh copy
+    let mut movie = db.get_movie(movie_id).ok_or("Movie not found")?;
+    let changed = merge(&mut movie, &results, &db.get_config().metadata_merge_policy);
+    db.update_movie(movie.clone()).map_err(|e| e.to_string())?;
+
+    Ok(MetadataRefreshResult {
+        movie,
+        changed_fields: changed,
+        providers: results.iter().map(|r| r.provider).collect(),
+        errors,
+    })
+}
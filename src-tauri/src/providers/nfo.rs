@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use crate::db::Database;
+use crate::metadata::document::decode_text;
+use crate::models::{FileQuery, Movie, Person, ProviderHit, ProviderId, ProviderImage, ProviderMetadata};
+use crate::search;
+use super::MetadataProvider;
+
+/// NFO files looked at per monitored folder when searching.
+const SEARCH_SCAN_LIMIT: usize = 500;
+const SEARCH_RESULTS: usize = 20;
+
+/// Artwork saved next to the NFO the way Kodi and most scrapers name it,
+/// as (kind, file stem).
+const LOCAL_ART: &[(&str, &str)] = &[
+    ("poster", "poster"),
+    ("poster", "folder"),
+    ("poster", "cover"),
+    ("backdrop", "fanart"),
+    ("backdrop", "backdrop"),
+    ("logo", "clearlogo"),
+    ("logo", "logo"),
+];
+const ART_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Kodi-style `.nfo` files (`movie.nfo`, `tvshow.nfo` or `<video>.nfo`)
+/// next to a movie's videos or in its folders.
+pub struct NfoProvider {
+    db: Database,
+}
+
+/// A parsed NFO file: its metadata and the artwork URLs it lists.
+struct Nfo {
+    path: PathBuf,
+    meta: ProviderMetadata,
+    images: Vec<(String, String)>, // (kind, url)
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    e.try_get_attribute(name).ok().flatten()
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_lowercase()))
+}
+
+fn parse(xml: &str) -> Result<(ProviderMetadata, Vec<(String, String)>), String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut meta = ProviderMetadata::new(ProviderId::Nfo);
+    let mut images = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut unique_id_type = None;
+    let mut thumb_aspect = None;
+    let mut actor: Option<Person> = None;
+    let mut year = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| e.to_string())?;
+        let value = match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase();
+                match name.as_str() {
+                    "uniqueid" => unique_id_type = attribute(&e, "type"),
+                    "thumb" => thumb_aspect = attribute(&e, "aspect"),
+                    "actor" => actor = Some(Person::default()),
+                    "movie" | "tvshow" if stack.is_empty() => {
+                        meta.category = Some(if name == "tvshow" { "tv" } else { "movie" }.to_string());
+                    }
+                    _ => {}
+                }
+                stack.push(name);
+                continue;
+            }
+            Event::End(_) => {
+                if stack.pop().as_deref() == Some("actor") {
+                    if let Some(a) = actor.take().filter(|a| !a.name.is_empty()) {
+                        meta.actors.push(a);
+                    }
+                }
+                continue;
+            }
+            Event::Text(t) => t.unescape().map_err(|e| e.to_string())?.trim().to_string(),
+            Event::CData(t) => String::from_utf8_lossy(&t).trim().to_string(),
+            Event::Eof => break,
+            _ => continue,
+        };
+        if value.is_empty() {
+            continue;
+        }
+
+        let path: Vec<&str> = stack.iter().skip(1).map(String::as_str).collect();
+        match path.as_slice() {
+            ["title"] => meta.title = Some(value),
+            ["originaltitle"] => meta.original_title = Some(value),
+            ["plot"] => meta.overview = Some(value),
+            ["outline"] if meta.overview.is_none() => meta.overview = Some(value),
+            ["premiered"] | ["releasedate"] | ["aired"] => meta.release_date = Some(value),
+            ["year"] => year = Some(value),
+            ["rating"] => meta.rating = value.parse().ok(),
+            ["ratings", "rating", "value"] if meta.rating.is_none() => meta.rating = value.parse().ok(),
+            ["genre"] => meta.genres.extend(value.split(" / ").map(|g| g.trim().to_string())),
+            ["director"] => meta.directors.push(Person { name: value, job: Some("Director".to_string()), ..Default::default() }),
+            ["actor", "name"] => {
+                if let Some(a) = actor.as_mut() {
+                    a.name = value;
+                }
+            }
+            ["actor", "role"] => {
+                if let Some(a) = actor.as_mut() {
+                    a.character = Some(value);
+                }
+            }
+            ["actor", "thumb"] => {
+                if let Some(a) = actor.as_mut() {
+                    a.profile_path = Some(value);
+                }
+            }
+            ["uniqueid"] => match unique_id_type.as_deref() {
+                Some("tmdb") => meta.tmdb_id = value.parse().ok(),
+                Some("douban") => meta.douban_id = Some(value),
+                Some("imdb") => meta.imdb_id = Some(value),
+                Some("tvdb") => meta.tvdb_id = value.parse().ok(),
+                _ => {}
+            },
+            ["tmdbid"] => meta.tmdb_id = value.parse().ok(),
+            ["imdbid"] => meta.imdb_id = Some(value),
+            ["id"] if value.starts_with("tt") && meta.imdb_id.is_none() => meta.imdb_id = Some(value),
+            ["runtime"] => meta.runtime = value.parse().ok().filter(|r| *r > 0),
+            // "Rated PG-13", or "US:PG-13" with the country in front
+            ["mpaa"] => {
+                let rating = value.rsplit(':').next().unwrap_or(&value).trim_start_matches("Rated ").trim();
+                meta.certification = Some(rating.to_string()).filter(|r| !r.is_empty());
+            }
+            ["doubanid"] => meta.douban_id = Some(value),
+            ["thumb"] => {
+                let kind = match thumb_aspect.as_deref() {
+                    Some("clearlogo") | Some("logo") => "logo",
+                    Some("landscape") | Some("fanart") => "backdrop",
+                    _ => "poster",
+                };
+                images.push((kind.to_string(), value));
+            }
+            ["fanart", "thumb"] => images.push(("backdrop".to_string(), value)),
+            _ => {}
+        }
+    }
+
+    if meta.release_date.is_none() {
+        meta.release_date = year;
+    }
+    meta.poster = images.iter().find(|(kind, _)| kind == "poster").map(|(_, url)| url.clone());
+    Ok((meta, images))
+}
+
+/// Where a movie's NFO may be, most specific first.
+fn candidates(movie: &Movie) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let videos = movie.local_video_path.iter()
+        .chain(movie.materials.iter().filter(|m| m.file_type == "video").map(|m| &m.path));
+    for video in videos {
+        let video = Path::new(video);
+        if video.is_dir() {
+            paths.push(video.join("tvshow.nfo"));
+            paths.push(video.join("movie.nfo"));
+        } else {
+            paths.push(video.with_extension("nfo"));
+            if let Some(dir) = video.parent() {
+                paths.push(dir.join("movie.nfo"));
+                paths.push(dir.join("tvshow.nfo"));
+            }
+        }
+    }
+    for folder in &movie.matched_folders {
+        paths.push(Path::new(folder).join("movie.nfo"));
+        paths.push(Path::new(folder).join("tvshow.nfo"));
+    }
+    paths
+}
+
+fn read(path: &Path) -> Result<Nfo, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let (mut meta, mut images) = parse(&decode_text(&bytes))?;
+    meta.provider_item_id = Some(path.to_string_lossy().to_string());
+
+    // Artwork files next to the NFO beat URLs inside it
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let mut local = Vec::new();
+    for (kind, name) in LOCAL_ART {
+        for ext in ART_EXTENSIONS {
+            for file in [format!("{}-{}.{}", stem, name, ext), format!("{}.{}", name, ext)] {
+                let art = dir.join(file);
+                if art.is_file() && !local.iter().any(|(_, p)| *p == art.to_string_lossy()) {
+                    local.push((kind.to_string(), art.to_string_lossy().to_string()));
+                }
+            }
+        }
+    }
+    if let Some((_, poster)) = local.iter().find(|(kind, _)| kind == "poster") {
+        meta.poster = Some(poster.clone());
+    }
+    local.append(&mut images);
+    Ok(Nfo { path: path.to_path_buf(), meta, images: local })
+}
+
+fn find(movie: &Movie) -> Option<Nfo> {
+    candidates(movie).into_iter()
+        .filter(|p| p.is_file())
+        .find_map(|p| match read(&p) {
+            Ok(nfo) => Some(nfo),
+            Err(e) => {
+                eprintln!("[NFO] Skipping {}: {}", p.display(), e);
+                None
+            }
+        })
+}
+
+impl NfoProvider {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    async fn find(&self, movie: &Movie) -> Result<Option<Nfo>, String> {
+        let movie = movie.clone();
+        tauri::async_runtime::spawn_blocking(move || find(&movie)).await.map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for NfoProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::Nfo
+    }
+
+    /// NFO files in the monitored folders whose title contains `query`.
+    async fn search(&self, query: &str) -> Result<Vec<ProviderHit>, String> {
+        let db = self.db.clone();
+        let query = query.to_lowercase();
+        tauri::async_runtime::spawn_blocking(move || {
+            let mut hits = Vec::new();
+            for folder in db.get_config().monitored_folders() {
+                let file_query = FileQuery {
+                    scope: folder.clone(),
+                    extensions: vec!["nfo".to_string()],
+                    limit: Some(SEARCH_SCAN_LIMIT),
+                    ..Default::default()
+                };
+                let found = match search::search_files(Some(&db), Path::new(&folder), &file_query) {
+                    Ok(found) => found,
+                    Err(e) => {
+                        eprintln!("[NFO] Search in {} failed: {}", folder, e);
+                        continue;
+                    }
+                };
+                for hit in found.hits {
+                    let Ok(nfo) = read(Path::new(&hit.path)) else { continue };
+                    let meta = nfo.meta;
+                    let matches = [&meta.title, &meta.original_title].into_iter().flatten()
+                        .any(|t| t.to_lowercase().contains(&query));
+                    if !matches {
+                        continue;
+                    }
+                    hits.push(ProviderHit {
+                        provider: ProviderId::Nfo,
+                        id: nfo.path.to_string_lossy().to_string(),
+                        title: meta.title.clone().unwrap_or_default(),
+                        original_title: meta.original_title,
+                        year: meta.release_date.map(|d| d.chars().take(4).collect()),
+                        poster: meta.poster,
+                        category: meta.category,
+                    });
+                    if hits.len() >= SEARCH_RESULTS {
+                        return hits;
+                    }
+                }
+            }
+            hits
+        }).await.map_err(|e| e.to_string())
+    }
+
+    async fn details(&self, movie: &Movie) -> Result<Option<ProviderMetadata>, String> {
+        Ok(self.find(movie).await?.map(|nfo| nfo.meta))
+    }
+
+    async fn images(&self, movie: &Movie) -> Result<Vec<ProviderImage>, String> {
+        let Some(nfo) = self.find(movie).await? else {
+            return Ok(Vec::new());
+        };
+        Ok(nfo.images.into_iter()
+            .map(|(kind, url)| ProviderImage {
+                provider: ProviderId::Nfo,
+                kind,
+                url,
+                width: None,
+                height: None,
+                language: None,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use crate::models::tmdb::{TmdbCast, TmdbCrew, TmdbDetailResponse, TmdbImage};
+use crate::models::{Movie, MovieCollection, Person, ProviderHit, ProviderId, ProviderImage, ProviderMetadata};
+use crate::tmdb::{image_url, TmdbClient};
+use super::MetadataProvider;
+
+/// Cast members kept per title, as the details page shows them.
+const MAX_CAST: usize = 10;
+
+fn media_type(movie: &Movie) -> &'static str {
+    if movie.category.as_deref() == Some("tv") { "tv" } else { "movie" }
+}
+
+fn person(id: u64, name: &str, original_name: &Option<String>, profile_path: &Option<String>) -> Person {
+    Person {
+        id,
+        name: name.to_string(),
+        original_name: original_name.clone(),
+        profile_path: profile_path.as_deref().map(|p| image_url("h632", p)),
+        ..Default::default()
+    }
+}
+
+fn cast_person(c: &TmdbCast) -> Person {
+    Person {
+        character: c.character.clone().filter(|s| !s.is_empty()),
+        ..person(c.id, &c.name, &c.original_name, &c.profile_path)
+    }
+}
+
+fn crew_person(c: &TmdbCrew) -> Person {
+    Person {
+        job: c.job.clone(),
+        ..person(c.id, &c.name, &c.original_name, &c.profile_path)
+    }
+}
+
+/// Provider metadata from a details response.
+pub fn to_metadata(tmdb_id: u64, media_type: &str, detail: TmdbDetailResponse) -> ProviderMetadata {
+    let mut meta = ProviderMetadata::new(ProviderId::Tmdb);
+    meta.provider_item_id = Some(tmdb_id.to_string());
+    meta.tmdb_id = Some(tmdb_id);
+    meta.title = detail.title.clone().or(detail.name.clone());
+    meta.original_title = detail.original_title.clone().or(detail.original_name.clone());
+    meta.overview = detail.overview.clone();
+    meta.poster = detail.poster_path.as_deref().map(|p| image_url("w500", p));
+    meta.release_date = detail.release_date.clone().or(detail.first_air_date.clone()).filter(|d| !d.is_empty());
+    // TMDB reports 0 for titles nobody has voted on
+    meta.rating = detail.vote_average.filter(|v| *v > 0.0);
+    meta.category = Some(media_type.to_string());
+    meta.genres = detail.genres.iter().flatten().map(|g| g.name.clone()).collect();
+    if let Some(credits) = &detail.credits {
+        meta.actors = credits.cast.iter().take(MAX_CAST).map(cast_person).collect();
+        meta.directors = credits.crew.iter()
+            .filter(|c| c.job.as_deref() == Some("Director"))
+            .map(crew_person)
+            .collect();
+    }
+    if let Some(ids) = &detail.external_ids {
+        meta.imdb_id = ids.imdb_id.clone().filter(|id| !id.is_empty());
+        meta.tvdb_id = ids.tvdb_id;
+    }
+    meta.runtime = detail.display_runtime();
+    meta.countries = detail.country_codes();
+    meta.original_language = detail.original_language.clone();
+    meta.certification = detail.certification.clone();
+    meta.collection = detail.belongs_to_collection.as_ref().map(|c| MovieCollection {
+        id: c.id,
+        name: c.name.clone(),
+        poster_path: c.poster_path.as_deref().map(|p| image_url("w500", p)),
+    });
+    meta.aliases = detail.aliases;
+    meta
+}
+
+/// The movie's TMDB ID, or the one TMDB has for its IMDb ID.
+async fn find_id(client: &TmdbClient, movie: &Movie) -> Result<Option<u64>, String> {
+    if movie.tmdb_id.is_some() {
+        return Ok(movie.tmdb_id);
+    }
+    let Some(imdb_id) = &movie.imdb_id else {
+        return Ok(None);
+    };
+    let found = client.find_by_imdb(imdb_id).await?;
+    Ok(found.iter()
+        .find(|r| r.media_type.as_deref() == Some(media_type(movie)))
+        .map(|r| r.id))
+}
+
+#[async_trait]
+impl MetadataProvider for TmdbClient {
+    fn id(&self) -> ProviderId {
+        ProviderId::Tmdb
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<ProviderHit>, String> {
+        let results = self.search_multi(query, 1).await?;
+        Ok(results.into_iter()
+            .map(|r| ProviderHit {
+                provider: ProviderId::Tmdb,
+                id: r.id.to_string(),
+                title: r.display_title().unwrap_or_default().to_string(),
+                original_title: r.display_original_title().map(str::to_string),
+                year: r.display_date().map(|d| d.chars().take(4).collect()),
+                poster: r.poster_path.as_deref().map(|p| image_url("w500", p)),
+                category: r.media_type.clone(),
+            })
+            .collect())
+    }
+
+    async fn details(&self, movie: &Movie) -> Result<Option<ProviderMetadata>, String> {
+        let Some(tmdb_id) = find_id(self, movie).await? else {
+            return Ok(None);
+        };
+        let detail = TmdbClient::details(self, tmdb_id, media_type(movie)).await?;
+        Ok(Some(to_metadata(tmdb_id, media_type(movie), detail)))
+    }
+
+    async fn images(&self, movie: &Movie) -> Result<Vec<ProviderImage>, String> {
+        let Some(tmdb_id) = movie.tmdb_id else {
+            return Ok(Vec::new());
+        };
+        let images = TmdbClient::images(self, tmdb_id, media_type(movie)).await?;
+        let convert = |kind: &str, size: &str, image: &TmdbImage| ProviderImage {
+            provider: ProviderId::Tmdb,
+            kind: kind.to_string(),
+            url: image_url(size, &image.file_path),
+            width: image.width,
+            height: image.height,
+            language: image.iso_639_1.clone(),
+        };
+        Ok(images.posters.iter().map(|i| convert("poster", "w500", i))
+            .chain(images.backdrops.iter().map(|i| convert("backdrop", "w1280", i)))
+            .chain(images.logos.iter().map(|i| convert("logo", "original", i)))
+            .collect())
+    }
+}
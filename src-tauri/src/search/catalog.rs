@@ -0,0 +1,89 @@
+use std::path::Path;
+use crate::db::{CatalogEntry, Database};
+use crate::ignore::ScanFilter;
+use crate::models::FileHit;
+use crate::paths;
+use super::{query::{format_time, CompiledQuery}, FileSearchBackend};
+
+/// A catalog older than this is rebuilt before searching.
+const CATALOG_MAX_AGE_HOURS: i64 = 24;
+
+/// Indexes every file name below a root into SQLite once, then answers
+/// searches from the index. For file systems without an MFT to read.
+/// Only files are indexed, so folders never show up in its results.
+pub struct CatalogBackend {
+    db: Database,
+}
+
+impl CatalogBackend {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Walk `root` and replace its catalog. Returns the number of files indexed.
+    pub fn rebuild(&self, root: &Path) -> Result<usize, String> {
+        if !root.exists() {
+            return Err(format!("{} does not exist", root.display()));
+        }
+        // Scan rules (.zqignore, excludes, size and age limits) apply here too
+        let filter = ScanFilter::from_config(&self.db.get_config());
+        let entries: Vec<CatalogEntry> = filter.walk(root)
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                let metadata = e.metadata().ok();
+                CatalogEntry {
+                    path: e.path().to_string_lossy().to_string(),
+                    name: e.file_name().to_string_lossy().to_string(),
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified: metadata.and_then(|m| m.modified().ok()).map(format_time),
+                }
+            })
+            .collect();
+
+        let count = entries.len();
+        self.db
+            .replace_catalog(&paths::normalize(&root.to_string_lossy()), &entries)
+            .map_err(|e| e.to_string())?;
+        Ok(count)
+    }
+
+    fn is_fresh(&self, root_key: &str) -> bool {
+        self.db
+            .get_catalog_indexed_at(root_key)
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+            .is_some_and(|t| chrono::Utc::now().signed_duration_since(t) < chrono::Duration::hours(CATALOG_MAX_AGE_HOURS))
+    }
+}
+
+impl FileSearchBackend for CatalogBackend {
+    fn name(&self) -> &'static str {
+        "catalog"
+    }
+
+    fn search(
+        &self,
+        root: &Path,
+        query: &CompiledQuery,
+        sink: &mut dyn FnMut(FileHit) -> bool,
+        _errors: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let root_key = paths::normalize(&root.to_string_lossy());
+        if !self.is_fresh(&root_key) {
+            let count = self.rebuild(root)?;
+            println!("[SEARCH] Indexed {} files under {}", count, root.display());
+        }
+        self.db
+            .search_catalog(&root_key, query.like_hint(), &mut |entry| {
+                if !query.matches_name(&entry.name, false) {
+                    return true;
+                }
+                sink(FileHit {
+                    path: entry.path,
+                    size: Some(entry.size),
+                    modified_time: entry.modified,
+                    is_dir: false,
+                })
+            })
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,165 @@
+pub mod catalog;
+#[cfg(windows)]
+pub mod ntfs;
+pub mod query;
+pub mod walk;
+
+use std::path::Path;
+use crate::db::Database;
+use crate::models::{FileHit, FileQuery, FileSearchResult};
+pub use catalog::CatalogBackend;
+pub use query::CompiledQuery;
+pub use walk::WalkBackend;
+
+/// Most results one search returns unless the query asks for another limit.
+pub const SEARCH_LIMIT: usize = 500;
+
+/// Per-entry errors kept for one search; the rest are only counted.
+const MAX_ERRORS: usize = 20;
+
+/// Finds files by name below a folder or volume root.
+pub trait FileSearchBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Hand every entry below `root` that passes `query.matches_name` to
+    /// `sink`, until it returns false. Entries that can't be read go to
+    /// `errors`; an `Err` means the backend can't search `root` at all.
+    fn search(
+        &self,
+        root: &Path,
+        query: &CompiledQuery,
+        sink: &mut dyn FnMut(FileHit) -> bool,
+        errors: &mut Vec<String>,
+    ) -> Result<(), String>;
+}
+
+pub(crate) fn push_error(errors: &mut Vec<String>, error: String) {
+    if errors.len() < MAX_ERRORS {
+        errors.push(error);
+    } else if errors.len() == MAX_ERRORS {
+        errors.push("further errors omitted".to_string());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VolumeKind {
+    Ntfs,
+    /// A local file system other than NTFS: ext4, btrfs, exfat, apfs ...
+    Local(String),
+    /// SMB, NFS and other network mounts
+    Network(String),
+    Unknown,
+}
+
+/// Network file systems as they appear in `/proc/mounts`.
+#[cfg(not(windows))]
+const NETWORK_FS: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "smbfs", "fuse.sshfs", "9p", "afs"];
+
+#[cfg(windows)]
+pub fn volume_kind(root: &Path) -> VolumeKind {
+    ntfs::volume_kind(root)
+}
+
+/// Looks the root up in `/proc/mounts`; the longest matching mount point wins.
+#[cfg(not(windows))]
+pub fn volume_kind(root: &Path) -> VolumeKind {
+    let Ok(mounts) = std::fs::read_to_string("/proc/mounts") else {
+        return VolumeKind::Unknown;
+    };
+    let root = root.to_string_lossy();
+
+    let mut best: Option<(usize, String)> = None;
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(_), Some(mount_point), Some(fs_type)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        // Spaces in mount points are escaped as \040
+        let mount_point = mount_point.replace("\\040", " ");
+        if crate::paths::is_within(&root, &mount_point) && best.as_ref().is_none_or(|(len, _)| mount_point.len() > *len) {
+            best = Some((mount_point.len(), fs_type.to_string()));
+        }
+    }
+
+    match best {
+        Some((_, fs)) if NETWORK_FS.contains(&fs.as_str()) => VolumeKind::Network(fs),
+        Some((_, fs)) => VolumeKind::Local(fs),
+        None => VolumeKind::Unknown,
+    }
+}
+
+/// Pick the fastest backend for the volume: the MFT on local NTFS, the
+/// SQLite catalog on other known file systems and network mounts, and a
+/// plain walk when we can't tell (or have no database for the catalog).
+pub fn backend_for(root: &Path, db: Option<&Database>) -> Box<dyn FileSearchBackend> {
+    match (volume_kind(root), db) {
+        #[cfg(windows)]
+        (VolumeKind::Ntfs, db) => Box::new(ntfs::NtfsBackend { db: db.cloned() }),
+        (VolumeKind::Unknown, _) | (_, None) => Box::new(WalkBackend),
+        (_, Some(db)) => Box::new(CatalogBackend::new(db.clone())),
+    }
+}
+
+/// Run one backend, applying the size and date filters, offset and limit to what it finds.
+fn run(backend: &dyn FileSearchBackend, root: &Path, query: &CompiledQuery, errors: &mut Vec<String>) -> Result<(Vec<FileHit>, bool), String> {
+    let mut hits = Vec::new();
+    let mut skipped = 0;
+    let mut has_more = false;
+    backend.search(root, query, &mut |hit| {
+        if !query.matches_hit(&hit) {
+            return true;
+        }
+        if skipped < query.offset {
+            skipped += 1;
+            return true;
+        }
+        if hits.len() == query.limit {
+            has_more = true;
+            return false;
+        }
+        hits.push(hit);
+        true
+    }, errors)?;
+    Ok((hits, has_more))
+}
+
+/// Search with the backend chosen for the query's scope, falling back to
+/// walking the folder when that backend fails (e.g. the MFT without admin
+/// rights). Only an invalid query is an `Err`; everything that went wrong
+/// while searching is listed in the result.
+pub fn search_files(db: Option<&Database>, scope: &Path, query: &FileQuery) -> Result<FileSearchResult, String> {
+    let compiled = CompiledQuery::compile(query)?;
+    let backend = backend_for(scope, db);
+    println!("[SEARCH] Using '{}' backend for {}", backend.name(), scope.display());
+
+    let mut errors = Vec::new();
+    let (backend, outcome) = match run(backend.as_ref(), scope, &compiled, &mut errors) {
+        Err(e) if backend.name() != WalkBackend.name() => {
+            eprintln!("[SEARCH] '{}' backend failed ({}), walking instead", backend.name(), e);
+            errors.push(format!("{} backend failed, walked the folder instead: {}", backend.name(), e));
+            (WalkBackend.name(), run(&WalkBackend, scope, &compiled, &mut errors))
+        }
+        outcome => (backend.name(), outcome),
+    };
+
+    let (hits, has_more) = outcome.unwrap_or_else(|e| {
+        errors.push(e);
+        (Vec::new(), false)
+    });
+    Ok(FileSearchResult { hits, backend: backend.to_string(), has_more, errors })
+}
+
+/// Paths of files below `root` whose name contains `keyword` (case-insensitive).
+pub fn search(db: Option<&Database>, root: &Path, keyword: &str, limit: usize) -> Result<Vec<String>, String> {
+    let query = FileQuery {
+        scope: root.to_string_lossy().to_string(),
+        pattern: Some(keyword.to_string()),
+        limit: Some(limit),
+        ..Default::default()
+    };
+    let result = search_files(db, root, &query)?;
+    for e in &result.errors {
+        eprintln!("[SEARCH] {}", e);
+    }
+    Ok(result.hits.into_iter().map(|h| h.path).collect())
+}
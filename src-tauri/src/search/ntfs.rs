@@ -0,0 +1,300 @@
+use usn_journal_rs::journal::{EnumOptions, UsnJournal};
+use usn_journal_rs::mft::Mft;
+use usn_journal_rs::volume::Volume;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use windows::Win32::Foundation::{HANDLE, CloseHandle, GetLastError};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, OpenFileById, GetFinalPathNameByHandleW, 
+    FILE_ID_DESCRIPTOR, FILE_ID_TYPE, FILE_NAME_NORMALIZED, VOLUME_NAME_DOS,
+    FILE_SHARE_READ, FILE_SHARE_WRITE, FILE_SHARE_DELETE,
+    OPEN_EXISTING, FILE_FLAG_BACKUP_SEMANTICS, READ_CONTROL,
+    FILE_READ_ATTRIBUTES, SYNCHRONIZE, FILE_GENERIC_READ, GETFINALPATHNAMEBYHANDLE_FLAGS,
+    GetDriveTypeW, GetVolumeInformationW, GetVolumePathNameW,
+};
+use windows::core::PCWSTR;
+use std::path::Path;
+use crate::paths::{self, strip_verbatim};
+use crate::db::{Database, UsnChange, UsnIndexEntry};
+use crate::models::FileHit;
+use super::{push_error, query::{stat_hit, CompiledQuery}, FileSearchBackend, VolumeKind};
+
+unsafe fn get_path_from_id(volume_handle: HANDLE, file_id: u64) -> Result<String, String> {
+    let mut desc = FILE_ID_DESCRIPTOR::default();
+    desc.dwSize = std::mem::size_of::<FILE_ID_DESCRIPTOR>() as u32;
+    desc.Type = FILE_ID_TYPE(0); // FileIdType
+    desc.Anonymous.FileId = file_id as i64;
+
+    // IMPORTANT: FILE_FLAG_BACKUP_SEMANTICS is required to open directories by ID
+    let file_handle = OpenFileById(
+        volume_handle,
+        &desc,
+        FILE_READ_ATTRIBUTES.0 | SYNCHRONIZE.0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+        None,
+        windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(FILE_FLAG_BACKUP_SEMANTICS.0),
+    );
+
+    if let Ok(handle) = file_handle {
+        if handle.is_invalid() {
+            let err = GetLastError();
+            return Err(format!("Invalid handle. Error: {:?}", err));
+        }
+        
+        let mut buffer = [0u16; 32768];
+        let len = GetFinalPathNameByHandleW(
+            handle, 
+            &mut buffer, 
+            GETFINALPATHNAMEBYHANDLE_FLAGS(VOLUME_NAME_DOS.0 | FILE_NAME_NORMALIZED.0)
+        );
+        
+        let _ = CloseHandle(handle);
+
+        if len > 0 && (len as usize) < buffer.len() {
+            let path_slice = &buffer[..len as usize];
+            let path_string = String::from_utf16_lossy(path_slice);
+            return Ok(strip_verbatim(&path_string));
+        } else {
+            let err = GetLastError();
+            return Err(format!("GetFinalPathNameByHandleW failed. Error: {:?}", err));
+        }
+    } else {
+        let err = GetLastError();
+        return Err(format!("OpenFileById failed. Error: {:?}", err));
+    }
+}
+
+use std::collections::HashSet;
+
+/// Scan the whole MFT of a volume, resolving each name match through the file system.
+fn scan_mft(
+    volume_path: &str,
+    query: &CompiledQuery,
+    sink: &mut dyn FnMut(FileHit) -> bool,
+    errors: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut seen_paths: HashSet<String> = HashSet::new(); // Hard links share a record
+    let mut resolved = 0;
+    
+    let drive_letter = volume_path.chars().next().unwrap_or('C');
+    println!("[USN DEBUG] Scanning MFT of volume '{}'", volume_path);
+    
+    // 1. Open Volume for USN Journal
+    let vol = match Volume::from_drive_letter(drive_letter) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("Failed to open volume {}: {}", drive_letter, e))
+    };
+
+    // 2. Open Volume Handle for OpenFileById (Windows API)
+    let drive_str = format!(r"\\.\{}:", drive_letter);
+    let drive_wide: Vec<u16> = OsStr::new(&drive_str).encode_wide().chain(std::iter::once(0)).collect();
+    
+    let volume_handle = unsafe {
+        CreateFileW(
+            PCWSTR(drive_wide.as_ptr()),
+            FILE_GENERIC_READ.0 | READ_CONTROL.0, 
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            HANDLE(0),
+        )
+    };
+
+    let volume_handle = match volume_handle {
+        Ok(h) if !h.is_invalid() => h,
+        _ => {
+            let err = unsafe { GetLastError() };
+            return Err(format!("Failed to open volume handle (Error: {:?})", err));
+        }
+    };
+
+    // Ensure handle is closed
+    struct HandleGuard(HANDLE);
+    impl Drop for HandleGuard {
+        fn drop(&mut self) {
+            unsafe { let _ = CloseHandle(self.0); };
+        }
+    }
+    let _guard = HandleGuard(volume_handle);
+
+    // SWITCH TO MFT for full search capability (finds existing files, not just changes)
+    let mft = Mft::new(&vol);
+    println!("[USN DEBUG] MFT initialized, starting iteration...");
+    
+    let mut resolve_errors = Vec::new();
+    for record in mft.iter().flatten() {
+        // MFT Record handling
+        let name = record.file_name.to_string_lossy().to_string();
+        let is_dir = record.is_dir();
+        if !query.matches_name(&name, is_dir) {
+            continue;
+        }
+        // Resolve full path using OpenFileById
+        match unsafe { get_path_from_id(volume_handle, record.fid) } {
+            Ok(full_path) => {
+                resolved += 1;
+                if seen_paths.insert(full_path.clone()) && !sink(stat_hit(full_path, is_dir)) {
+                    break;
+                }
+            },
+            Err(e) => resolve_errors.push(format!("Path resolve error for '{}': {}", name, e)),
+        }
+    }
+    println!("[USN DEBUG] MFT scan finished, resolved {} matches", resolved);
+    
+    // Nothing resolved at all: let the caller fall back to another backend
+    if resolved == 0 && !resolve_errors.is_empty() {
+        resolve_errors.truncate(5);
+        return Err(resolve_errors.join("; "));
+    }
+    for e in resolve_errors {
+        push_error(errors, e);
+    }
+    Ok(())
+}
+
+/// USN reasons we act on; the Ioctl constants aren't in our windows features.
+const USN_REASON_FILE_DELETE: u32 = 0x0000_0200;
+const USN_REASON_RENAME_OLD_NAME: u32 = 0x0000_1000;
+
+fn volume_key(drive_letter: char) -> String {
+    drive_letter.to_ascii_uppercase().to_string()
+}
+
+/// Bring the persisted index of a volume up to date. The first time, and
+/// whenever the journal was recreated or has dropped records we never saw,
+/// the index is rebuilt from the MFT; otherwise only the journal records
+/// written since the saved cursor are applied.
+pub fn sync_index(db: &Database, drive_letter: char) -> Result<(), String> {
+    let key = volume_key(drive_letter);
+    let vol = Volume::from_drive_letter(drive_letter).map_err(|e| e.to_string())?;
+    let journal = UsnJournal::new(&vol);
+    let data = journal.query(true).map_err(|e| e.to_string())?;
+
+    match db.get_usn_cursor(&key) {
+        Some((journal_id, next_usn)) if journal_id == data.journal_id && next_usn >= data.first_usn => {
+            if next_usn >= data.next_usn {
+                return Ok(());
+            }
+            match read_changes(&journal, next_usn, data.next_usn) {
+                Ok(changes) => {
+                    println!("[USN] Applying {} journal records to {}:", changes.len(), key);
+                    return db.apply_usn_changes(&key, data.journal_id, data.next_usn, &changes).map_err(|e| e.to_string());
+                }
+                Err(e) => eprintln!("[USN] Reading the journal of {}: failed ({}), rebuilding", key, e),
+            }
+        }
+        Some(_) => println!("[USN] Journal of {}: was reset, rebuilding index", key),
+        None => println!("[USN] Building index for {}:", key),
+    }
+
+    // Changes made while the MFT is enumerated are picked up by the next sync,
+    // since the cursor is taken before enumerating
+    let mut entries = Vec::new();
+    for record in Mft::new(&vol).iter() {
+        let record = record.map_err(|e| e.to_string())?;
+        entries.push(UsnIndexEntry {
+            fid: record.fid,
+            parent_fid: record.parent_fid,
+            is_dir: record.is_dir(),
+            name: record.file_name.to_string_lossy().to_string(),
+        });
+    }
+    println!("[USN] Indexed {} entries on {}:", entries.len(), key);
+    db.replace_usn_index(&key, data.journal_id, data.next_usn, &entries).map_err(|e| e.to_string())
+}
+
+/// Journal records from `start` up to (not including) `end`, as index changes in order.
+fn read_changes(journal: &UsnJournal, start: i64, end: i64) -> Result<Vec<UsnChange>, String> {
+    let options = EnumOptions { start_usn: start, ..Default::default() };
+    let mut changes = Vec::new();
+    for entry in journal.iter_with_options(options).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.usn >= end {
+            break;
+        }
+        if entry.reason & USN_REASON_FILE_DELETE != 0 {
+            changes.push(UsnChange::Delete(entry.fid));
+        } else if entry.reason & USN_REASON_RENAME_OLD_NAME == 0 {
+            // Creates, renames (new name record) and content changes all carry the current name and parent
+            changes.push(UsnChange::Upsert(UsnIndexEntry {
+                fid: entry.fid,
+                parent_fid: entry.parent_fid,
+                is_dir: entry.is_dir(),
+                name: entry.file_name.to_string_lossy().to_string(),
+            }));
+        }
+    }
+    Ok(changes)
+}
+
+/// Searches a persisted index of the NTFS master file table, kept current
+/// through the USN change journal. Needs NTFS and administrator rights.
+/// Without a database it falls back to scanning the whole MFT per search.
+pub struct NtfsBackend {
+    pub db: Option<Database>,
+}
+
+impl FileSearchBackend for NtfsBackend {
+    fn name(&self) -> &'static str {
+        "ntfs"
+    }
+
+    fn search(
+        &self,
+        root: &Path,
+        query: &CompiledQuery,
+        sink: &mut dyn FnMut(FileHit) -> bool,
+        errors: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let root_str = root.to_string_lossy();
+        let drive_letter = root_str.chars().next().unwrap_or('C');
+        let volume_path = format!("{}:\\", drive_letter.to_ascii_uppercase());
+        // The volume root itself never matches, only what is below it
+        let in_scope = |p: &str| paths::is_within(p, &root_str) && !paths::same_path(p, &root_str);
+
+        let Some(db) = &self.db else {
+            return scan_mft(&volume_path, query, &mut |hit| !in_scope(&hit.path) || sink(hit), errors);
+        };
+
+        sync_index(db, drive_letter)?;
+        db.search_usn_index(
+            &volume_key(drive_letter),
+            &volume_path,
+            query.like_hint(),
+            query.include_dirs,
+            &|name, is_dir| query.matches_name(name, is_dir),
+            &mut |path, is_dir| !in_scope(&path) || sink(stat_hit(path, is_dir)),
+        ).map_err(|e| e.to_string())
+    }
+}
+
+/// `GetDriveTypeW` result for network drives (WindowsProgramming isn't enabled in our windows features).
+const DRIVE_REMOTE: u32 = 4;
+
+/// File system and drive type of the volume holding `root`.
+pub fn volume_kind(root: &Path) -> VolumeKind {
+    let wide = |s: &OsStr| -> Vec<u16> { s.encode_wide().chain(std::iter::once(0)).collect() };
+
+    let root_wide = wide(root.as_os_str());
+    let mut volume = [0u16; 261];
+    if unsafe { GetVolumePathNameW(PCWSTR(root_wide.as_ptr()), &mut volume) }.is_err() {
+        return VolumeKind::Unknown;
+    }
+    let volume_ptr = PCWSTR(volume.as_ptr());
+
+    let mut fs_name = [0u16; 64];
+    let info = unsafe { GetVolumeInformationW(volume_ptr, None, None, None, None, Some(&mut fs_name)) };
+    let fs_len = fs_name.iter().position(|c| *c == 0).unwrap_or(fs_name.len());
+    let fs_name = String::from_utf16_lossy(&fs_name[..fs_len]).to_lowercase();
+
+    if unsafe { GetDriveTypeW(volume_ptr) } == DRIVE_REMOTE {
+        return VolumeKind::Network(fs_name);
+    }
+    match info {
+        Ok(()) if fs_name == "ntfs" => VolumeKind::Ntfs,
+        Ok(()) => VolumeKind::Local(fs_name),
+        Err(_) => VolumeKind::Unknown,
+    }
+}
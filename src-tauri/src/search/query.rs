@@ -0,0 +1,132 @@
+use std::time::SystemTime;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::{Regex, RegexBuilder};
+use crate::glob;
+use crate::models::{FileHit, FileQuery, PatternKind};
+use super::SEARCH_LIMIT;
+
+/// Upper bound for `FileQuery::limit`, so one query can't pull in a whole volume.
+const MAX_LIMIT: usize = 10_000;
+
+/// A [`FileQuery`] validated and compiled once, checked against every candidate.
+#[derive(Debug, Clone)]
+pub struct CompiledQuery {
+    name: Option<Regex>,
+    /// Lowercase substring every matching name contains, for SQL `LIKE` prefiltering
+    like_hint: Option<String>,
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<DateTime<Utc>>,
+    modified_before: Option<DateTime<Utc>>,
+    pub include_dirs: bool,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// RFC 3339 timestamp or a plain `YYYY-MM-DD` date (midnight UTC).
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD or RFC 3339", value))
+}
+
+impl CompiledQuery {
+    pub fn compile(query: &FileQuery) -> Result<Self, String> {
+        let pattern = query.pattern.as_deref().map(str::trim).filter(|p| !p.is_empty());
+        let case_insensitive = !query.case_sensitive;
+
+        let (name, like_hint) = match pattern {
+            None => (None, None),
+            Some(p) => {
+                let (source, hint) = match query.pattern_kind {
+                    PatternKind::Substring => (regex::escape(p), case_insensitive.then(|| p.to_lowercase())),
+                    PatternKind::Glob => (format!("^{}$", glob::glob_to_regex(p)), None),
+                    PatternKind::Regex => (p.to_string(), None),
+                };
+                let regex = RegexBuilder::new(&source)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|e| format!("invalid pattern '{}': {}", p, e))?;
+                (Some(regex), hint)
+            }
+        };
+
+        if let (Some(min), Some(max)) = (query.min_size, query.max_size) {
+            if min > max {
+                return Err(format!("min_size {} is larger than max_size {}", min, max));
+            }
+        }
+
+        Ok(Self {
+            name,
+            like_hint,
+            extensions: query.extensions.iter()
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            min_size: query.min_size,
+            max_size: query.max_size,
+            modified_after: query.modified_after.as_deref().map(parse_date).transpose()?,
+            modified_before: query.modified_before.as_deref().map(parse_date).transpose()?,
+            include_dirs: query.include_dirs,
+            offset: query.offset,
+            limit: query.limit.unwrap_or(SEARCH_LIMIT).clamp(1, MAX_LIMIT),
+        })
+    }
+
+    pub fn like_hint(&self) -> Option<&str> {
+        self.like_hint.as_deref()
+    }
+
+    /// Checks that only need the name. Directories have no extension to match.
+    pub fn matches_name(&self, name: &str, is_dir: bool) -> bool {
+        if is_dir && !self.include_dirs {
+            return false;
+        }
+        if !is_dir && !self.extensions.is_empty() {
+            let ext = name.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+            if !self.extensions.contains(&ext) {
+                return false;
+            }
+        }
+        self.name.as_ref().is_none_or(|re| re.is_match(name))
+    }
+
+    /// Size and date checks. Unknown values fail a filter that is set.
+    pub fn matches_hit(&self, hit: &FileHit) -> bool {
+        if !hit.is_dir
+            && (self.min_size.is_some_and(|min| hit.size.is_none_or(|s| s < min))
+                || self.max_size.is_some_and(|max| hit.size.is_none_or(|s| s > max)))
+        {
+            return false;
+        }
+        if self.modified_after.is_none() && self.modified_before.is_none() {
+            return true;
+        }
+        let Some(modified) = hit.modified_time.as_deref().and_then(|t| DateTime::parse_from_rfc3339(t).ok()) else {
+            return false;
+        };
+        let modified = modified.with_timezone(&Utc);
+        self.modified_after.is_none_or(|after| modified >= after)
+            && self.modified_before.is_none_or(|before| modified < before)
+    }
+}
+
+pub fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+/// A hit for a path we only know the name of, filled in from the file system.
+pub fn stat_hit(path: String, is_dir: bool) -> FileHit {
+    let metadata = std::fs::metadata(&path).ok();
+    FileHit {
+        size: metadata.as_ref().filter(|m| m.is_file()).map(|m| m.len()),
+        modified_time: metadata.and_then(|m| m.modified().ok()).map(format_time),
+        path,
+        is_dir,
+    }
+}
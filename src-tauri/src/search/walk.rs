@@ -0,0 +1,50 @@
+use std::path::Path;
+use walkdir::WalkDir;
+use crate::models::FileHit;
+use super::{push_error, query::{format_time, CompiledQuery}, FileSearchBackend};
+
+/// Walks the folder on every search. Works everywhere, slowest of all.
+pub struct WalkBackend;
+
+impl FileSearchBackend for WalkBackend {
+    fn name(&self) -> &'static str {
+        "walk"
+    }
+
+    fn search(
+        &self,
+        root: &Path,
+        query: &CompiledQuery,
+        sink: &mut dyn FnMut(FileHit) -> bool,
+        errors: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if !root.exists() {
+            return Err(format!("{} does not exist", root.display()));
+        }
+        // min_depth skips the root itself
+        for entry in WalkDir::new(root).min_depth(1) {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    push_error(errors, e.to_string());
+                    continue;
+                }
+            };
+            let is_dir = entry.file_type().is_dir();
+            if !query.matches_name(&entry.file_name().to_string_lossy(), is_dir) {
+                continue;
+            }
+            let metadata = entry.metadata().ok();
+            let hit = FileHit {
+                path: entry.path().to_string_lossy().to_string(),
+                size: metadata.as_ref().filter(|m| m.is_file()).map(|m| m.len()),
+                modified_time: metadata.and_then(|m| m.modified().ok()).map(format_time),
+                is_dir,
+            };
+            if !sink(hit) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
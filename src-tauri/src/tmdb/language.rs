@@ -0,0 +1,113 @@
+use crate::models::tmdb::{TmdbDetailResponse, TmdbTranslation};
+
+/// Whether a translation is in `tag` ("zh-TW", or just "zh" for any region).
+fn translation_matches(translation: &TmdbTranslation, tag: &str) -> bool {
+    let mut parts = tag.split(['-', '_']);
+    let language = parts.next().unwrap_or("");
+    let region = parts.next();
+    translation.iso_639_1.eq_ignore_ascii_case(language)
+        && region.is_none_or(|r| translation.iso_3166_1.eq_ignore_ascii_case(r))
+}
+
+fn language_of(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or(tag)
+}
+
+/// Translations in the order of `languages`, best first.
+fn in_order<'a>(detail: &'a TmdbDetailResponse, languages: &'a [String]) -> impl Iterator<Item = &'a TmdbTranslation> {
+    let translations = detail.translations.as_ref().map(|t| t.translations.as_slice()).unwrap_or(&[]);
+    languages.iter().flat_map(move |tag| translations.iter().filter(move |t| translation_matches(t, tag)))
+}
+
+/// Fill the title and overview from the fallback languages where the
+/// primary language has none, and collect the translated titles and the
+/// alternative titles of the chain's regions as aliases.
+pub fn localize(detail: &mut TmdbDetailResponse, languages: &[String]) {
+    let primary = languages.first().map(String::as_str).unwrap_or("");
+
+    // Without a translation TMDB answers with the original title; keep it for
+    // works made in the primary language, otherwise prefer a fallback
+    let has_primary_title = in_order(detail, languages.get(..1).unwrap_or(&[]))
+        .any(|t| t.data.display_title().is_some());
+    let made_in_primary = detail.original_language.as_deref()
+        .is_some_and(|l| l.eq_ignore_ascii_case(language_of(primary)));
+    if !has_primary_title && !made_in_primary {
+        let fallback = in_order(detail, languages).find_map(|t| t.data.display_title()).map(str::to_string);
+        if let Some(title) = fallback {
+            if detail.name.is_some() && detail.title.is_none() {
+                detail.name = Some(title);
+            } else {
+                detail.title = Some(title);
+            }
+        }
+    }
+
+    if detail.overview.as_deref().is_none_or(|o| o.trim().is_empty()) {
+        let fallback = in_order(detail, languages).find_map(|t| t.data.display_overview()).map(str::to_string);
+        detail.overview = fallback;
+    }
+
+    detail.aliases = aliases(detail, languages);
+    detail.certification = certification(detail, languages);
+}
+
+/// The regions of the language chain ("zh-CN" gives "CN"), then the US,
+/// whose certifications TMDB has for nearly everything.
+pub fn regions(languages: &[String]) -> Vec<String> {
+    let mut regions: Vec<String> = Vec::new();
+    let tagged = languages.iter().filter_map(|l| l.split(['-', '_']).nth(1));
+    for region in tagged.chain(["US"]) {
+        let region = region.to_uppercase();
+        if !regions.contains(&region) {
+            regions.push(region);
+        }
+    }
+    regions
+}
+
+/// The certification for the first region that has one; for movies the
+/// theatrical release's if it carries one.
+fn certification(detail: &TmdbDetailResponse, languages: &[String]) -> Option<String> {
+    let movie_releases = detail.release_dates.iter().flat_map(|r| &r.results);
+    let tv_ratings = detail.content_ratings.iter().flat_map(|r| &r.results);
+    regions(languages).iter().find_map(|region| {
+        let movie = movie_releases.clone()
+            .filter(|r| r.iso_3166_1.eq_ignore_ascii_case(region))
+            .find_map(|r| {
+                let rated = || r.release_dates.iter().filter(|d| !d.certification.trim().is_empty());
+                rated().find(|d| d.release_type == Some(3)).or_else(|| rated().next())
+            })
+            .map(|d| d.certification.trim().to_string());
+        movie.or_else(|| tv_ratings.clone()
+            .find(|r| r.iso_3166_1.eq_ignore_ascii_case(region) && !r.rating.trim().is_empty())
+            .map(|r| r.rating.trim().to_string()))
+    })
+}
+
+/// The translated titles, then the alternative titles of the language
+/// chain's regions; those of other countries would only mislead matching.
+fn aliases(detail: &TmdbDetailResponse, languages: &[String]) -> Vec<String> {
+    let known: Vec<String> = [&detail.title, &detail.name, &detail.original_title, &detail.original_name]
+        .into_iter()
+        .flatten()
+        .map(|t| t.trim().to_lowercase())
+        .collect();
+
+    let regions = regions(languages);
+    let alternative = detail.alternative_titles.iter()
+        .flat_map(|a| &a.titles)
+        .filter(|t| t.iso_3166_1.as_ref().is_some_and(|c| regions.iter().any(|r| r.eq_ignore_ascii_case(c))))
+        .map(|t| t.title.as_str());
+    let translated = in_order(detail, languages).filter_map(|t| t.data.display_title());
+
+    let mut aliases: Vec<String> = Vec::new();
+    for title in translated.chain(alternative) {
+        let title = title.trim();
+        let lower = title.to_lowercase();
+        if title.is_empty() || known.contains(&lower) || aliases.iter().any(|a| a.to_lowercase() == lower) {
+            continue;
+        }
+        aliases.push(title.to_string());
+    }
+    aliases
+}
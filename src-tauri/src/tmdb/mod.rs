@@ -0,0 +1,398 @@
+mod language;
+mod rate_limit;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use reqwest::{header, Client, StatusCode};
+use serde::de::DeserializeOwned;
+use crate::db::Database;
+use crate::models::tmdb::{
+    TmdbCountryReleases, TmdbDetailResponse, TmdbFindResponse, TmdbImages, TmdbMovie, TmdbPersonDetail, TmdbReleaseDates,
+    TmdbSearchKind, TmdbSearchOptions, TmdbSearchPage, TmdbSearchResult, TmdbSeason, TmdbTvSchedule,
+};
+pub use rate_limit::RateLimiter;
+
+const TMDB_BASE_URL: &str = "https://api.themoviedb.org/3";
+const IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p/";
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// TMDB allows about 50 requests a second; stay well below that.
+const BURST: u32 = 20;
+const REQUESTS_PER_SECOND: u32 = 20;
+/// Attempts per request, including the first one.
+const MAX_ATTEMPTS: u32 = 4;
+const BACKOFF_BASE_MS: u64 = 500;
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// How long cached responses are served without asking TMDB again.
+const SEARCH_TTL_HOURS: i64 = 24;
+const DETAILS_TTL_HOURS: i64 = 24 * 7;
+/// Air and release dates move; the calendar refreshes them daily.
+const SCHEDULE_TTL_HOURS: i64 = 12;
+
+/// URL of a TMDB image file (`/abc.jpg`) in one of its sizes ("w500", "original"…).
+pub fn image_url(size: &str, path: &str) -> String {
+    format!("{}{}{}", IMAGE_BASE_URL, size, path)
+}
+
+/// One reqwest client for the whole app, rebuilt only when the proxy setting changes.
+struct ClientSlot {
+    proxy: Option<String>,
+    client: Client,
+}
+
+/// Shared async TMDB client: rate limited, retrying on 429 and 5xx, and
+/// backed by a SQLite response cache that also serves as the offline store.
+#[derive(Clone)]
+pub struct TmdbClient {
+    db: Database,
+    client: Arc<Mutex<Option<ClientSlot>>>,
+    limiter: RateLimiter,
+}
+
+fn build_client(proxy: Option<&str>) -> Result<Client, String> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert("accept", header::HeaderValue::from_static("application/json"));
+
+    let mut builder = Client::builder()
+        .default_headers(headers)
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS));
+    if let Some(proxy_url) = proxy.filter(|p| !p.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| format!("Proxy config error: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// What went wrong talking to TMDB, worded for the user.
+fn describe_status(status: StatusCode, body: &str) -> String {
+    // TMDB explains most errors in a JSON `status_message`
+    let message = serde_json::from_str::<serde_json::Value>(body).ok()
+        .and_then(|v| v["status_message"].as_str().map(str::to_string));
+    let summary = match status.as_u16() {
+        401 => "TMDB rejected the API key".to_string(),
+        404 => "Not found on TMDB".to_string(),
+        429 => "TMDB rate limit reached, try again in a moment".to_string(),
+        s if s >= 500 => format!("TMDB is having problems ({})", status),
+        _ => format!("TMDB API Error: {}", status),
+    };
+    match message {
+        Some(m) => format!("{}: {}", summary, m),
+        None => summary,
+    }
+}
+
+fn describe_transport(e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        "Connection Timed Out. Please check your network or proxy settings.".to_string()
+    } else if e.is_connect() {
+        format!("Connection Failed: Could not connect to TMDB. Check proxy settings. Error: {}", e)
+    } else {
+        format!("Connection Failed: {}", e)
+    }
+}
+
+/// Cache key for a request: the path plus its parameters, without the API key.
+fn cache_key(path: &str, params: &[(&str, String)]) -> String {
+    let mut params: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    params.sort();
+    format!("tmdb:{}?{}", path, params.join("&"))
+}
+
+enum Failure {
+    /// TMDB answered, but not with success; retrying won't help
+    Status(String),
+    /// Network trouble or TMDB overloaded even after retrying
+    Unreachable(String),
+}
+
+impl TmdbClient {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            client: Arc::new(Mutex::new(None)),
+            limiter: RateLimiter::new(BURST, REQUESTS_PER_SECOND),
+        }
+    }
+
+    fn http(&self, proxy: Option<&str>) -> Result<Client, String> {
+        let mut slot = self.client.lock().unwrap();
+        if let Some(s) = slot.as_ref().filter(|s| s.proxy.as_deref() == proxy) {
+            return Ok(s.client.clone());
+        }
+        let client = build_client(proxy)?;
+        *slot = Some(ClientSlot { proxy: proxy.map(str::to_string), client: client.clone() });
+        Ok(client)
+    }
+
+    /// Send one GET with retries: 429 waits for `Retry-After` (or backs
+    /// off), 5xx and network errors back off exponentially.
+    async fn fetch(&self, client: &Client, url: &str, query: &[(&str, String)]) -> Result<String, Failure> {
+        let mut last_error = String::new();
+        for attempt in 0..MAX_ATTEMPTS {
+            if attempt > 0 {
+                let backoff = Duration::from_millis(BACKOFF_BASE_MS << (attempt - 1));
+                println!("[TMDB] Retrying {} in {:?} ({})", url, backoff, last_error);
+                tokio::time::sleep(backoff).await;
+            }
+            self.limiter.acquire().await;
+
+            let response = match client.get(url).query(query).send().await {
+                Ok(r) => r,
+                Err(e) => {
+                    last_error = describe_transport(&e);
+                    continue;
+                }
+            };
+            let status = response.status();
+            let retry_after = response.headers().get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+            let body = response.text().await.unwrap_or_default();
+
+            if status.is_success() {
+                return Ok(body);
+            }
+            last_error = describe_status(status, &body);
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                if let Some(secs) = retry_after {
+                    tokio::time::sleep(Duration::from_secs(secs.min(10))).await;
+                }
+            } else if !status.is_server_error() {
+                return Err(Failure::Status(last_error));
+            }
+        }
+        Err(Failure::Unreachable(last_error))
+    }
+
+    /// GET a TMDB endpoint as `T`, answering from the cache while it is
+    /// younger than `ttl_hours`. In offline mode, or when TMDB can't be
+    /// reached, older cached responses are served as well.
+    async fn get<T: DeserializeOwned>(&self, path: &str, params: &[(&str, String)], ttl_hours: i64) -> Result<T, String> {
+        let config = self.db.get_config();
+        let key = cache_key(path, params);
+        let cached = self.db.get_http_cache(&key);
+        let parse = |body: &str| serde_json::from_str::<T>(body).map_err(|e| e.to_string());
+
+        if let Some((body, fetched_at)) = &cached {
+            let fresh = chrono::DateTime::parse_from_rfc3339(fetched_at)
+                .is_ok_and(|t| chrono::Utc::now().signed_duration_since(t) < chrono::Duration::hours(ttl_hours));
+            if fresh || config.tmdb_offline {
+                return parse(body);
+            }
+        }
+        if config.tmdb_offline {
+            return Err("Offline mode: this TMDB request isn't cached".to_string());
+        }
+
+        let api_key = config.tmdb_api_key.filter(|k| !k.trim().is_empty()).ok_or("TMDB API Key not set")?;
+        let client = self.http(config.proxy.as_deref())?;
+        let mut query = vec![("api_key", api_key)];
+        query.extend(params.iter().cloned());
+
+        match self.fetch(&client, &format!("{}{}", TMDB_BASE_URL, path), &query).await {
+            Ok(body) => {
+                let value = parse(&body)?;
+                if let Err(e) = self.db.save_http_cache(&key, &body) {
+                    eprintln!("[TMDB] Failed to cache {}: {}", key, e);
+                }
+                Ok(value)
+            }
+            Err(Failure::Unreachable(e)) => match cached {
+                Some((body, fetched_at)) => {
+                    println!("[TMDB] {} unreachable ({}), serving cache from {}", path, e, fetched_at);
+                    parse(&body)
+                }
+                None => Err(e),
+            },
+            Err(Failure::Status(e)) => Err(e),
+        }
+    }
+
+    async fn search_page(&self, query: &str, options: &TmdbSearchOptions, language: &str) -> Result<TmdbSearchPage, String> {
+        let path = match options.kind {
+            TmdbSearchKind::Multi => "/search/multi",
+            TmdbSearchKind::Movie => "/search/movie",
+            TmdbSearchKind::Tv => "/search/tv",
+        };
+        let mut params = vec![
+            ("query", query.to_string()),
+            ("language", language.to_string()),
+            ("page", options.page.unwrap_or(1).max(1).to_string()),
+            ("include_adult", options.include_adult.to_string()),
+        ];
+        match (options.kind, options.year) {
+            (TmdbSearchKind::Movie, Some(year)) => params.push(("year", year.to_string())),
+            (TmdbSearchKind::Tv, Some(year)) => params.push(("first_air_date_year", year.to_string())),
+            _ => {}
+        }
+        if let Some(region) = options.region.as_ref().filter(|r| !r.trim().is_empty()) {
+            if options.kind == TmdbSearchKind::Movie {
+                params.push(("region", region.trim().to_uppercase()));
+            }
+        }
+        let result: TmdbSearchResult = self.get(path, &params, SEARCH_TTL_HOURS).await?;
+
+        let mut page = TmdbSearchPage {
+            page: result.page,
+            total_results: Some(result.total_results),
+            total_pages: Some(result.total_pages),
+            ..Default::default()
+        };
+        for value in result.results {
+            // Single-kind searches don't say what they found
+            let media_type = value["media_type"].as_str().map(str::to_string).unwrap_or_else(|| match options.kind {
+                TmdbSearchKind::Tv => "tv".to_string(),
+                _ => "movie".to_string(),
+            });
+            if media_type == "person" {
+                match serde_json::from_value(value) {
+                    Ok(person) => page.people.push(person),
+                    Err(e) => eprintln!("[TMDB] Skipping person result: {}", e),
+                }
+                continue;
+            }
+            match serde_json::from_value::<TmdbMovie>(value) {
+                Ok(mut movie) => {
+                    movie.media_type = Some(media_type);
+                    page.results.push(movie);
+                }
+                Err(e) => eprintln!("[TMDB] Skipping search result: {}", e),
+            }
+        }
+        // /search/multi takes no year, so TMDB's totals don't count what's filtered out
+        if let (TmdbSearchKind::Multi, Some(year)) = (options.kind, options.year) {
+            let year = year.to_string();
+            page.results.retain(|r| r.display_date().is_some_and(|d| d.starts_with(&year)));
+            page.total_results = None;
+            page.total_pages = None;
+        }
+        Ok(page)
+    }
+
+    /// Search in the primary language; overviews it lacks are taken from the
+    /// fallback languages, searched only while some are still missing.
+    pub async fn search(&self, query: &str, options: &TmdbSearchOptions) -> Result<TmdbSearchPage, String> {
+        let languages = self.db.get_config().tmdb_languages();
+        let mut page = self.search_page(query, options, &languages[0]).await?;
+
+        let lacks_overview = |r: &TmdbMovie| r.overview.as_deref().is_none_or(|o| o.trim().is_empty());
+        for language in &languages[1..] {
+            if !page.results.iter().any(lacks_overview) {
+                break;
+            }
+            let fallback = match self.search_page(query, options, language).await {
+                Ok(f) => f.results,
+                Err(e) => {
+                    println!("[TMDB] Fallback search in {} failed: {}", language, e);
+                    break;
+                }
+            };
+            for result in page.results.iter_mut().filter(|r| lacks_overview(r)) {
+                let other = fallback.iter().find(|f| f.id == result.id && f.media_type == result.media_type);
+                if let Some(other) = other.filter(|o| !lacks_overview(o)) {
+                    result.overview = other.overview.clone();
+                }
+            }
+        }
+        Ok(page)
+    }
+
+    /// Movies and TV shows matching `query`, without people.
+    pub async fn search_multi(&self, query: &str, page: u64) -> Result<Vec<TmdbMovie>, String> {
+        let options = TmdbSearchOptions { page: Some(page), ..Default::default() };
+        Ok(self.search(query, &options).await?.results)
+    }
+
+    /// Details and credits of a movie or TV show (`media_type` "movie" or "tv"),
+    /// localized along the configured language chain and with its
+    /// alternative and translated titles as `aliases`. External IDs and the
+    /// certification come along.
+    pub async fn details(&self, id: u64, media_type: &str) -> Result<TmdbDetailResponse, String> {
+        let languages = self.db.get_config().tmdb_languages();
+        let ratings = if media_type == "tv" { "content_ratings" } else { "release_dates" };
+        let params = [
+            ("language", languages[0].clone()),
+            ("append_to_response", format!("credits,alternative_titles,translations,external_ids,{}", ratings)),
+        ];
+        let mut detail: TmdbDetailResponse = self.get(&format!("/{}/{}", media_type, id), &params, DETAILS_TTL_HOURS).await?;
+        language::localize(&mut detail, &languages);
+        Ok(detail)
+    }
+
+    /// Posters, backdrops and logos in the configured languages, plus
+    /// the ones without text.
+    pub async fn images(&self, id: u64, media_type: &str) -> Result<TmdbImages, String> {
+        let languages = self.db.get_config().tmdb_languages();
+        let mut codes: Vec<&str> = Vec::new();
+        for code in languages.iter().map(|l| l.split(['-', '_']).next().unwrap_or(l)) {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+        let params = [("include_image_language", format!("{},null", codes.join(",")))];
+        self.get(&format!("/{}/{}/images", media_type, id), &params, DETAILS_TTL_HOURS).await
+    }
+
+    /// A person with their movie and TV credits.
+    pub async fn person(&self, id: u64) -> Result<TmdbPersonDetail, String> {
+        let language = self.db.get_config().tmdb_languages().remove(0);
+        let params = [("language", language), ("append_to_response", "combined_credits".to_string())];
+        self.get(&format!("/person/{}", id), &params, DETAILS_TTL_HOURS).await
+    }
+
+    /// A TV season with its episodes, their air dates and stills.
+    pub async fn season(&self, tv_id: u64, season: u32) -> Result<TmdbSeason, String> {
+        let language = self.db.get_config().tmdb_languages().remove(0);
+        let params = [("language", language)];
+        self.get(&format!("/tv/{}/season/{}", tv_id, season), &params, SCHEDULE_TTL_HOURS).await
+    }
+
+    /// The seasons and next episode of a TV show.
+    pub async fn tv_schedule(&self, id: u64) -> Result<TmdbTvSchedule, String> {
+        let language = self.db.get_config().tmdb_languages().remove(0);
+        let params = [("language", language)];
+        self.get(&format!("/tv/{}", id), &params, SCHEDULE_TTL_HOURS).await
+    }
+
+    /// A movie's release dates in the regions of the language chain and the
+    /// US, in that order.
+    pub async fn release_dates(&self, id: u64) -> Result<Vec<TmdbCountryReleases>, String> {
+        let regions = language::regions(&self.db.get_config().tmdb_languages());
+        let dates: TmdbReleaseDates = self.get(&format!("/movie/{}/release_dates", id), &[], SCHEDULE_TTL_HOURS).await?;
+        let mut results = dates.results;
+        results.retain(|r| regions.contains(&r.iso_3166_1.to_uppercase()));
+        results.sort_by_key(|r| regions.iter().position(|x| x.eq_ignore_ascii_case(&r.iso_3166_1)));
+        Ok(results)
+    }
+
+    /// Movies and TV shows with an IMDb ID ("tt0111161"), with `media_type` set.
+    pub async fn find_by_imdb(&self, imdb_id: &str) -> Result<Vec<TmdbMovie>, String> {
+        let language = self.db.get_config().tmdb_languages().remove(0);
+        let params = [
+            ("external_source", "imdb_id".to_string()),
+            ("language", language),
+        ];
+        let found: TmdbFindResponse = self.get(&format!("/find/{}", imdb_id), &params, DETAILS_TTL_HOURS).await?;
+        let tag = |mut results: Vec<TmdbMovie>, media_type: &str| {
+            for r in &mut results {
+                r.media_type = Some(media_type.to_string());
+            }
+            results
+        };
+        let mut results = tag(found.movie_results, "movie");
+        results.extend(tag(found.tv_results, "tv"));
+        Ok(results)
+    }
+
+    /// Check a key and proxy that may not be saved yet; never cached.
+    pub async fn test_connection(&self, api_key: &str, proxy: Option<&str>) -> Result<(), String> {
+        let client = build_client(proxy)?;
+        let url = format!("{}/configuration", TMDB_BASE_URL);
+        match self.fetch(&client, &url, &[("api_key", api_key.to_string())]).await {
+            Ok(_) => Ok(()),
+            Err(Failure::Status(e)) | Err(Failure::Unreachable(e)) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Token bucket: up to `capacity` requests at once, refilled at `per_second`.
+#[derive(Clone)]
+pub struct RateLimiter {
+    state: Arc<Mutex<Bucket>>,
+    capacity: f64,
+    per_second: f64,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, per_second: u32) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(Bucket { tokens: capacity as f64, refilled_at: Instant::now() })),
+            capacity: capacity as f64,
+            per_second: per_second as f64,
+        }
+    }
+
+    /// Wait until a request may be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.state.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.capacity);
+                bucket.refilled_at = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -35,7 +35,7 @@ const MovieEditModal: React.FC<MovieEditModalProps> = ({ visible, movie, onCance
         release_date: values.release_date ? values.release_date.format('YYYY-MM-DD') : undefined,
       };
 
-      await updateMovie(updatedMovie);
+      await updateMovie(updatedMovie, true);
       message.success('更新成功');
       onSuccess();
     } catch (error) {
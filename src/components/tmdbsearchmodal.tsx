@@ -1,8 +1,8 @@
 import React, { useState } from 'react';
-import { Modal, Input, Button, Card, Select, Form, Row, Col, Tabs, Table, Tag, App } from 'antd';
+import { Modal, Input, InputNumber, Button, Card, Select, Form, Row, Col, Tabs, Table, Tag, App } from 'antd';
 import { SearchOutlined, FolderOpenOutlined, CloudDownloadOutlined, CheckCircleOutlined, CloseCircleOutlined, ReloadOutlined } from '@ant-design/icons';
 import { open } from '@tauri-apps/plugin-dialog';
-import { searchTmdbMovies, fetchDoubanSubject, scanForMovies } from '../services/api';
+import { searchTmdb, searchTmdbMovies, fetchDoubanSubject, scanForMovies } from '../services/api';
 import { TmdbMovie, Movie } from '../types';
 
 const { Option } = Select;
@@ -17,6 +17,7 @@ const TmdbSearchModal: React.FC<TmdbSearchModalProps> = ({ visible, onCancel, on
   const { message } = App.useApp();
   const [loading, setLoading] = useState(false);
   const [results, setResults] = useState<(TmdbMovie | Movie)[]>([]);
+  const [totalResults, setTotalResults] = useState<number | null>(null);
   const [activeTab, setActiveTab] = useState('tmdb');
   
   // Folder Scan State
@@ -32,19 +33,13 @@ const TmdbSearchModal: React.FC<TmdbSearchModalProps> = ({ visible, onCancel, on
     setLoading(true);
     try {
       if (activeTab === 'tmdb') {
-        const data = await searchTmdbMovies(values.keyword, 1);
-        // Client-side filtering
-        let filtered = data;
-        
-        // Filter by media type if selected
-        if (values.type) {
-             filtered = filtered.filter((m: any) => m.media_type === values.type);
-        }
-
-        if (values.year) {
-          filtered = filtered.filter((m: any) => (m.release_date || m.first_air_date)?.startsWith(values.year));
-        }
-        setResults(filtered);
+        const data = await searchTmdb(values.keyword, {
+          kind: values.type || 'multi',
+          year: values.year || undefined,
+          region: values.region || undefined,
+        });
+        setResults(data.results);
+        setTotalResults(data.total_results ?? null);
       } else {
         // Douban search
         let id = values.keyword;
@@ -56,6 +51,7 @@ const TmdbSearchModal: React.FC<TmdbSearchModalProps> = ({ visible, onCancel, on
         
         const movie = await fetchDoubanSubject(id, values.type === 'tv');
         setResults([movie]);
+        setTotalResults(null);
       }
     } catch (error: any) {
       console.error(error);
@@ -75,14 +71,14 @@ const TmdbSearchModal: React.FC<TmdbSearchModalProps> = ({ visible, onCancel, on
 
         if (selected) {
             setScanning(true);
-            const files = await scanForMovies([selected as string]);
-            setScannedFiles(files);
+            const proposals = await scanForMovies([selected as string]);
+            setScannedFiles(proposals.map(p => p.file));
             
-            // Initialize scan results
-            const initialResults = files.map(f => ({
-                file: f,
-                match: null,
-                status: 'pending'
+            // Proposals already carry the best TMDB candidate
+            const initialResults = proposals.map(p => ({
+                file: p.file,
+                match: p.candidate ?? null,
+                status: p.candidate ? 'matched' : 'pending'
             }));
             setScanResults(initialResults);
             setScanning(false);
@@ -288,14 +284,37 @@ const TmdbSearchModal: React.FC<TmdbSearchModalProps> = ({ visible, onCancel, on
            {(activeTab === 'tmdb' || activeTab === 'douban') && (
                <Form.Item name="type" initialValue="movie">
                 <Select size="large" style={{ width: 100 }}>
+                    {activeTab === 'tmdb' && <Option value="multi">全部</Option>}
                     <Option value="movie">电影</Option>
                     <Option value="tv">剧集</Option>
                 </Select>
               </Form.Item>
            )}
+           {activeTab === 'tmdb' && (
+               <>
+                 <Form.Item name="year" style={{ marginRight: 8 }}>
+                   <InputNumber size="large" placeholder="年份" min={1870} max={2100} controls={false} style={{ width: 90 }} />
+                 </Form.Item>
+                 <Form.Item name="region">
+                   <Select size="large" placeholder="地区" allowClear style={{ width: 100 }}>
+                     <Option value="CN">中国大陆</Option>
+                     <Option value="HK">中国香港</Option>
+                     <Option value="TW">中国台湾</Option>
+                     <Option value="US">美国</Option>
+                     <Option value="JP">日本</Option>
+                     <Option value="KR">韩国</Option>
+                   </Select>
+                 </Form.Item>
+               </>
+           )}
         </Form>
       </div>
 
+      {activeTab === 'tmdb' && totalResults !== null && (
+          <div style={{ marginBottom: 8, fontSize: 12, color: '#999' }}>
+              共 {totalResults} 条结果，显示前 {results.length} 条
+          </div>
+      )}
       <div style={{ flex: 1, overflowY: 'auto', overflowX: 'hidden', padding: '0 4px' }}>
           <Row gutter={[16, 16]}>
             {results.map((item: any) => (
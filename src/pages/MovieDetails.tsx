@@ -69,6 +69,13 @@ const MovieDetails: React.FC = () => {
                     data.genres = tmdbData.genres.map((g: any) => g.name);
                     updated = true;
                 }
+
+                // Alternative and translated titles, for material matching
+                const newAliases = (tmdbData.aliases || []).filter((a: string) => !(data.aliases || []).includes(a));
+                if (newAliases.length > 0) {
+                    data.aliases = [...(data.aliases || []), ...newAliases];
+                    updated = true;
+                }
              }
 
              if (updated) {
@@ -1,5 +1,5 @@
 import React, { useEffect, useState, useRef } from 'react';
-import { Tabs, Form, Input, Slider, Radio, Button, Alert, Flex, Space, Switch, Descriptions, Divider, Card, Tag, Popconfirm, Modal, App, theme, type TabsProps } from 'antd';
+import { Tabs, Form, Input, Slider, Radio, Select, Button, Alert, Flex, Space, Switch, Descriptions, Divider, Card, Tag, Popconfirm, Modal, App, theme, type TabsProps } from 'antd';
 import { 
     SaveOutlined, 
     UploadOutlined, 
@@ -19,6 +19,15 @@ import { AppConfig, DetectedPlayer, AppInfo } from '../types';
 import { open, save } from '@tauri-apps/plugin-dialog';
 import { useApp } from '../context/appcontext';
 
+const TMDB_LANGUAGE_OPTIONS = [
+  { label: '简体中文 (zh-CN)', value: 'zh-CN' },
+  { label: '繁體中文 (zh-TW)', value: 'zh-TW' },
+  { label: '繁體中文 (zh-HK)', value: 'zh-HK' },
+  { label: 'English (en-US)', value: 'en-US' },
+  { label: '日本語 (ja-JP)', value: 'ja-JP' },
+  { label: '한국어 (ko-KR)', value: 'ko-KR' },
+];
+
 const Settings: React.FC = () => {
   const { token } = theme.useToken();
   const [form] = Form.useForm();
@@ -621,6 +630,18 @@ const Settings: React.FC = () => {
                                     </Button>
                                 </Space>
                             </Form.Item>
+
+                            <Form.Item label="元数据语言" name="tmdb_language" extra="简介、标题等优先使用该语言">
+                                <Select options={TMDB_LANGUAGE_OPTIONS} style={{ width: 200 }} />
+                            </Form.Item>
+
+                            <Form.Item label="备用语言" name="tmdb_fallback_languages" extra="按顺序补全首选语言缺失的字段，这些语言的译名也会加入别名用于素材匹配">
+                                <Select mode="tags" options={TMDB_LANGUAGE_OPTIONS} placeholder="例如 zh-TW、en-US" />
+                            </Form.Item>
+
+                            <Form.Item label="离线模式" name="tmdb_offline" valuePropName="checked" extra="只使用本地缓存的 TMDB 数据，不访问网络">
+                                <Switch />
+                            </Form.Item>
                         </Card>
 
                         <Card title="匹配规则" size="small" variant="borderless">
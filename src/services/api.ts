@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { Movie, AppConfig, AppInfo, MatchedFile, Material, TmdbMovie } from '../types';
+import { Movie, AppConfig, AppInfo, MatchedFile, Material, TmdbMovie, DuplicateGroup, MovieImportProposal, OrphanGroup, MaterialAssignment, CategoryRule, CategoryRuleTest, AutoMatchReport, FileQuery, FileSearchResult, MaterialFilter, MaterialListItem, ScriptHit, ScriptDetail, MaterialPreview, ProviderId, ProviderHit, ProviderImage, MetadataRefreshResult, MetadataField, MetadataRefreshFilter, MetadataRefreshReport, MovieMetadataDiff, MovieFilter, ExportFormat, TmdbSearchOptions, TmdbSearchPage, ResolvedTitle, BatchAddResult, ImageKind, ImageDownloadOptions, MovieImage, LibraryPerson, PersonCredit, PersonFilmography, CalendarEvent, CalendarRange } from '../types';
 
 export interface DetectedPlayer {
     name: string;
@@ -31,12 +31,20 @@ export const autoMatchMovie = async (movieId: number): Promise<void> => {
     return await invoke('auto_match_movie', { movieId });
 };
 
+export const autoMatchAll = async (dryRun?: boolean, jobId?: string): Promise<AutoMatchReport> => {
+    return await invoke('auto_match_all', { dryRun, jobId });
+};
+
+export const cancelJob = async (jobId: string): Promise<boolean> => {
+    return await invoke('cancel_job', { jobId });
+};
+
 export const deleteMovie = async (id: number): Promise<void> => {
     return await invoke('delete_movie', { id });
 };
 
-export const updateMovie = async (movie: Movie): Promise<Movie> => {
-    return await invoke('update_movie', { movie });
+export const updateMovie = async (movie: Movie, editedByUser?: boolean): Promise<Movie> => {
+    return await invoke('update_movie', { movie, editedByUser });
 };
 
 export const updateMovieStatus = async (id: number, status: string): Promise<void> => {
@@ -47,6 +55,46 @@ export const getMovieDetails = async (id: number): Promise<Movie | null> => {
     return await invoke('get_movie_details', { id });
 };
 
+export const downloadMovieImages = async (movieId: number, options?: ImageDownloadOptions): Promise<MovieImage[]> => {
+    return await invoke('download_movie_images', { movieId, options });
+};
+
+export const saveMovieImage = async (movieId: number, url: string, kind: ImageKind, primary?: boolean): Promise<Movie> => {
+    return await invoke('save_movie_image', { movieId, url, kind, primary });
+};
+
+export const getMovieImages = async (movieId: number): Promise<MovieImage[]> => {
+    return await invoke('get_movie_images', { movieId });
+};
+
+export const setPrimaryImage = async (movieId: number, kind: ImageKind, path: string): Promise<Movie> => {
+    return await invoke('set_primary_image', { movieId, kind, path });
+};
+
+export const removeMovieImage = async (movieId: number, hash: string): Promise<Movie> => {
+    return await invoke('remove_movie_image', { movieId, hash });
+};
+
+export const getPeople = async (query?: string, limit?: number): Promise<LibraryPerson[]> => {
+    return await invoke('get_people', { query, limit });
+};
+
+export const getPersonMovies = async (personId: number): Promise<PersonCredit[]> => {
+    return await invoke('get_person_movies', { personId });
+};
+
+export const getPersonFilmography = async (personId: number, onlyMissing?: boolean): Promise<PersonFilmography> => {
+    return await invoke('get_person_filmography', { personId, onlyMissing });
+};
+
+export const getCalendarEvents = async (range: CalendarRange): Promise<CalendarEvent[]> => {
+    return await invoke('get_calendar_events', { range });
+};
+
+export const refreshCalendar = async (): Promise<number> => {
+    return await invoke('refresh_calendar');
+};
+
 export const getTmdbDetails = async (tmdbId: number, mediaType?: string): Promise<any> => {
     return await invoke('get_tmdb_details', { tmdbId, mediaType });
 };
@@ -59,16 +107,72 @@ export const saveConfig = async (config: AppConfig): Promise<void> => {
     return await invoke('save_config', { config });
 };
 
+export const testCategoryRules = async (path: string, rules?: CategoryRule[]): Promise<CategoryRuleTest> => {
+    return await invoke('test_category_rules', { path, rules });
+};
+
+export const searchTmdb = async (query: string, options?: TmdbSearchOptions): Promise<TmdbSearchPage> => {
+    return await invoke('search_tmdb', { query, options });
+};
+
 export const searchTmdbMovies = async (query: string, page: number = 1): Promise<TmdbMovie[]> => {
     return await invoke('search_tmdb_movies', { query, page });
 };
 
+export const findTmdbByImdb = async (imdbId: string): Promise<TmdbMovie[]> => {
+    return await invoke('find_tmdb_by_imdb', { imdbId });
+};
+
 export const testTmdbConnection = async (apiKey: string, proxy?: string): Promise<boolean> => {
     return await invoke('test_tmdb_connection', { apiKey, proxy });
 };
 
-export const scanDirectories = async (paths: string[], titles?: string[], threshold?: number): Promise<MatchedFile[]> => {
-    return await invoke('scan_directories', { paths, titles, threshold });
+export const scanDirectories = async (paths: string[], titles?: string[], threshold?: number, year?: number): Promise<MatchedFile[]> => {
+    return await invoke('scan_directories', { paths, titles, threshold, year });
+};
+
+export const findDuplicateFiles = async (fullHash?: boolean): Promise<DuplicateGroup[]> => {
+    return await invoke('find_duplicate_files', { fullHash });
+};
+
+export const findOrphanFiles = async (): Promise<OrphanGroup[]> => {
+    return await invoke('find_orphan_files');
+};
+
+export const assignMaterials = async (assignments: MaterialAssignment[]): Promise<number> => {
+    return await invoke('assign_materials', { assignments });
+};
+
+export const refreshMaterialMetadata = async (movieId?: number, force?: boolean): Promise<number> => {
+    return await invoke('refresh_material_metadata', { movieId, force });
+};
+
+export const filterMaterials = async (filter: MaterialFilter): Promise<MaterialListItem[]> => {
+    return await invoke('filter_materials', { filter });
+};
+
+export const filterMovies = async (filter: MovieFilter): Promise<Movie[]> => {
+    return await invoke('filter_movies', { filter });
+};
+
+export const exportMovies = async (path: string, format?: ExportFormat, filter?: MovieFilter): Promise<number> => {
+    return await invoke('export_movies', { path, format, filter });
+};
+
+export const searchScripts = async (query?: string, movieId?: number, limit?: number): Promise<ScriptHit[]> => {
+    return await invoke('search_scripts', { query, movieId, limit });
+};
+
+export const getScript = async (movieId: number, materialId: string): Promise<ScriptDetail | null> => {
+    return await invoke('get_script', { movieId, materialId });
+};
+
+export const getMaterialPreview = async (path: string): Promise<MaterialPreview> => {
+    return await invoke('get_material_preview', { path });
+};
+
+export const cleanPreviewCache = async (): Promise<number> => {
+    return await invoke('clean_preview_cache');
 };
 
 export const refreshMovieMaterials = async (movieId: number): Promise<Material[]> => {
@@ -91,10 +195,42 @@ export const fetchDoubanSubject = async (id: string, isTv: boolean): Promise<any
     return await invoke('fetch_douban_subject', { urlOrId: id, isTv });
 };
 
-export const scanForMovies = async (paths: string[]): Promise<Movie[]> => {
+export const refreshMovieMetadata = async (movieId: number): Promise<MetadataRefreshResult> => {
+    return await invoke('refresh_movie_metadata', { movieId });
+};
+
+export const searchMetadata = async (query: string, provider?: ProviderId): Promise<ProviderHit[]> => {
+    return await invoke('search_metadata', { query, provider });
+};
+
+export const getMetadataImages = async (movieId: number): Promise<ProviderImage[]> => {
+    return await invoke('get_metadata_images', { movieId });
+};
+
+export const refreshLibraryMetadata = async (filter?: MetadataRefreshFilter, dryRun?: boolean, jobId?: string): Promise<MetadataRefreshReport> => {
+    return await invoke('refresh_library_metadata', { filter, dryRun, jobId });
+};
+
+export const applyMetadataChanges = async (diffs: MovieMetadataDiff[]): Promise<number> => {
+    return await invoke('apply_metadata_changes', { diffs });
+};
+
+export const unlockMovieFields = async (movieId: number, fields?: MetadataField[]): Promise<Movie> => {
+    return await invoke('unlock_movie_fields', { movieId, fields });
+};
+
+export const scanForMovies = async (paths: string[]): Promise<MovieImportProposal[]> => {
     return await invoke('scan_for_movies', { paths });
 };
 
+export const resolveTitleList = async (text: string): Promise<ResolvedTitle[]> => {
+    return await invoke('resolve_title_list', { text });
+};
+
+export const addMovies = async (movies: Movie[]): Promise<BatchAddResult> => {
+    return await invoke('add_movies', { movies });
+};
+
 export const clearData = async (): Promise<void> => {
     return await invoke('clear_data');
 };
@@ -180,6 +316,14 @@ export const searchUsnJournal = async (volume: string, keyword: string): Promise
     return await invoke('search_usn_journal', { volume, keyword });
 };
 
+export const searchFiles = async (query: FileQuery): Promise<FileSearchResult> => {
+    return await invoke('search_files', { query });
+};
+
+export const rebuildSearchCatalog = async (root: string): Promise<number> => {
+    return await invoke('rebuild_search_catalog', { root });
+};
+
 export const openDirectory = async (path: string): Promise<void> => {
     return await invoke('open_directory', { path });
 };
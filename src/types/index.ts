@@ -19,13 +19,36 @@ export interface Movie {
     actors?: Person[];
     directors?: Person[];
     materials?: Material[];
+    douban_id?: string;
+    ratings?: Partial<Record<ProviderId, number>>; // Each provider's rating
+    field_sources?: Partial<Record<MetadataField, ProviderId>>;
+    user_edited_fields?: MetadataField[]; // Locked against metadata refreshes
+    runtime?: number; // Minutes; episode length for TV shows
+    imdb_id?: string;
+    tvdb_id?: number;
+    countries?: string[]; // ISO 3166-1 codes
+    original_language?: string; // ISO 639-1
+    certification?: string;
+    collection?: MovieCollection;
+    backdrop_path?: string;
+    logo_path?: string;
+    posters?: string[]; // Local copies in the image store; poster_path is the one shown
+    production_deadline?: string; // YYYY-MM-DD
 }
 
-export interface Person {
+export interface MovieCollection {
     id: number;
     name: string;
+    poster_path?: string;
+}
+
+export interface Person {
+    id: number; // TMDB person ID, 0 when the source has none
+    name: string;
     original_name?: string;
     profile_path?: string;
+    character?: string; // Actors only
+    job?: string; // Crew only, e.g. "Director"
 }
 
 export interface TmdbMovie {
@@ -42,6 +65,65 @@ export interface TmdbMovie {
     media_type?: string;
 }
 
+export type TmdbSearchKind = 'multi' | 'movie' | 'tv';
+
+export interface TmdbSearchOptions {
+    kind?: TmdbSearchKind;
+    page?: number;
+    year?: number; // first_air_date_year for TV searches
+    region?: string; // ISO 3166-1, movie searches only
+    include_adult?: boolean;
+}
+
+export interface TmdbPerson {
+    id: number;
+    name: string;
+    original_name?: string;
+    profile_path?: string;
+    known_for_department?: string;
+}
+
+export type CreditRole = 'cast' | 'crew';
+
+export interface LibraryPerson {
+    id: number;
+    name: string;
+    original_name?: string;
+    profile_path?: string;
+    movie_count: number;
+}
+
+export interface PersonCredit {
+    movie: Movie;
+    role: CreditRole;
+    character?: string;
+    job?: string;
+}
+
+export interface FilmographyEntry {
+    movie: TmdbMovie;
+    characters: string[];
+    jobs: string[];
+    library_movie_id?: number; // Unset for titles not in the library yet
+}
+
+export interface PersonFilmography {
+    person: TmdbPerson;
+    biography?: string;
+    birthday?: string;
+    deathday?: string;
+    entries: FilmographyEntry[]; // Newest first
+    in_library: number;
+}
+
+export interface TmdbSearchPage {
+    page: number;
+    total_results?: number; // Unknown when a multi-search is filtered by year
+    total_pages?: number;
+    results: TmdbMovie[];
+    people: TmdbPerson[]; // Only from multi-search
+}
+
 export interface TmdbConfig {
     images: {
         base_url: string;
@@ -69,6 +151,36 @@ export interface AppConfig {
     local_player_path?: string;
     ffmpeg_path?: string;
     ai_model_path?: string;
+    exclude_patterns?: string[];
+    min_file_size?: number;
+    max_file_size?: number;
+    max_file_age_days?: number;
+    category_rules?: CategoryRule[];
+    narration_chars_per_minute?: number;
+    tmdb_offline?: boolean;
+    tmdb_language?: string;
+    tmdb_fallback_languages?: string[];
+    metadata_providers?: ProviderId[];
+    metadata_merge_policy?: Partial<Record<MetadataField, ProviderId[]>>;
+}
+
+export interface CategoryRule {
+    name?: string;
+    path?: string;
+    path_regex?: string;
+    file_type?: string;
+    file_name?: string;
+    category?: string;
+    tags?: string[];
+    enabled?: boolean;
+}
+
+export interface CategoryRuleTest {
+    file_type: string;
+    category?: string;
+    tags: string[];
+    matched_rules: number[];
+    errors: string[];
 }
 
 export interface AppInfo {
@@ -86,6 +198,27 @@ export interface MatchedFile {
     file_type: string;
     category?: string;
     modified_time?: string;
+    match_detail?: MatchDetail;
+}
+
+export type MatchMethod = 'exact' | 'contains' | 'jaro_winkler' | 'parsed_title' | 'year_bonus' | 'year_penalty';
+
+export type MatchComponent = { kind: 'file' } | { kind: 'directory'; depth: number };
+
+export interface ComponentScore {
+    component: MatchComponent;
+    value: string;
+    matched_title: string;
+    methods: MatchMethod[];
+    score: number;
+}
+
+export interface MatchDetail {
+    matched_title: string;
+    component: MatchComponent;
+    component_value: string;
+    methods: MatchMethod[];
+    components: ComponentScore[];
 }
 
 export interface Material {
@@ -97,6 +230,168 @@ export interface Material {
     category?: string;
     add_time: string;
     modified_time?: string;
+    fingerprint?: string;
+    tags?: string[];
+    metadata?: MaterialMetadata;
+}
+
+export interface MaterialMetadata {
+    duration?: number; // Seconds
+    bit_rate?: number;
+    video_codec?: string;
+    width?: number;
+    height?: number;
+    frame_rate?: number;
+    audio_codec?: string;
+    sample_rate?: number;
+    channels?: number;
+    page_count?: number;
+    word_count?: number;
+    char_count?: number;
+    file_size: number;
+    file_modified?: string;
+    probed_at: string;
+    error?: string;
+}
+
+export interface MaterialFilter {
+    file_type?: string;
+    category?: string;
+    tag?: string;
+    codec?: string;
+    min_size?: number;
+    max_size?: number;
+    min_duration?: number;
+    max_duration?: number;
+    min_width?: number;
+    max_width?: number;
+    min_height?: number;
+    max_height?: number;
+    sort_by?: 'name' | 'size' | 'duration' | 'resolution' | 'modified';
+    descending?: boolean;
+    limit?: number;
+}
+
+export interface MaterialListItem {
+    movie_id: number;
+    movie_title: string;
+    material: Material;
+}
+
+export interface DuplicateFile {
+    path: string;
+    modified_time?: string;
+    linked_movie_ids: number[];
+}
+
+export interface DuplicateGroup {
+    fingerprint: string;
+    size: number;
+    files: DuplicateFile[];
+}
+
+export interface OrphanGroup {
+    folder: string;
+    file_type: string;
+    files: OrphanFile[];
+}
+
+export interface OrphanFile {
+    path: string;
+    name: string;
+    size: number;
+    modified_time?: string;
+    suggestion?: OrphanSuggestion;
+}
+
+export interface OrphanSuggestion {
+    movie_id: number;
+    title: string;
+    similarity: number;
+    match_detail: MatchDetail;
+}
+
+export interface MaterialAssignment {
+    movie_id: number;
+    paths: string[];
+}
+
+export interface AutoMatchProgress {
+    job_id: string;
+    phase: 'scanning' | 'matching' | 'linking';
+    processed: number;
+    total: number;
+    current?: string;
+}
+
+export interface AutoMatchReport {
+    job_id: string;
+    dry_run: boolean;
+    cancelled: boolean;
+    files_scanned: number;
+    movies: AutoMatchMovieResult[];
+}
+
+export interface AutoMatchMovieResult {
+    movie_id: number;
+    title: string;
+    linked: number;
+    files: MatchedFile[];
+}
+
+export interface ScannedFile {
+    path: string;
+    name: string;
+    parent_folder: string;
+    search_query: string;
+}
+
+export interface MovieImportProposal {
+    file: ScannedFile;
+    candidate?: TmdbMovie;
+    movie?: Movie;
+    confidence: number;
+    alternatives: TmdbMovie[];
+    already_in_library: boolean;
+    error?: string;
+}
+
+export interface TitleListEntry {
+    line: number;
+    title: string;
+    year?: number;
+    category?: string; // 'movie' | 'tv'
+    tmdb_id?: number;
+    douban_id?: string;
+    imdb_id?: string;
+}
+
+export type TitleResolution = 'resolved' | 'ambiguous' | 'not_found' | 'in_library' | 'failed';
+
+export interface TitleCandidate {
+    movie: Movie; // Ready for addMovies
+    confidence: number;
+}
+
+export interface ResolvedTitle {
+    entry: TitleListEntry;
+    status: TitleResolution;
+    confidence: number;
+    movie?: Movie; // The pick for resolved rows
+    candidates: TitleCandidate[];
+    existing_movie_id?: number;
+    error?: string;
+}
+
+export interface BatchAddSkip {
+    title: string;
+    existing_movie_id?: number;
+    reason: string;
+}
+
+export interface BatchAddResult {
+    added: Movie[];
+    skipped: BatchAddSkip[];
 }
 
 export interface DetectedPlayer {
@@ -104,3 +399,197 @@ export interface DetectedPlayer {
     path: string;
     icon?: string;
 }
+
+export interface FileQuery {
+    scope: string;
+    pattern?: string;
+    pattern_kind?: 'substring' | 'glob' | 'regex';
+    case_sensitive?: boolean;
+    extensions?: string[];
+    min_size?: number;
+    max_size?: number;
+    modified_after?: string;
+    modified_before?: string;
+    include_dirs?: boolean;
+    limit?: number;
+    offset?: number;
+}
+
+export interface FileHit {
+    path: string;
+    size?: number;
+    modified_time?: string;
+    is_dir: boolean;
+}
+
+export interface FileSearchResult {
+    hits: FileHit[];
+    backend: string;
+    has_more: boolean;
+    errors: string[];
+}
+
+export interface ScriptHit {
+    movie_id: number;
+    material_id: string;
+    path: string;
+    char_count: number;
+    estimated_seconds: number;
+    snippet?: string;
+}
+
+export interface ScriptDetail {
+    movie_id: number;
+    material_id: string;
+    path: string;
+    text: string;
+    char_count: number;
+    estimated_seconds: number;
+    extracted_at: string;
+}
+
+export interface MaterialPreview {
+    key: string;
+    file_type: string;
+    thumbnail?: string;
+    sprite?: string;
+    sprite_frames?: number;
+    waveform?: string;
+    waveform_image?: string;
+    image?: string;
+    text?: string;
+    errors: string[];
+}
+
+export type ProviderId = 'tmdb' | 'douban' | 'nfo';
+
+export type MetadataField = 'title' | 'original_title' | 'overview' | 'poster' | 'release_date' | 'rating' | 'genres' | 'cast'
+    | 'runtime' | 'countries' | 'original_language' | 'certification' | 'collection';
+
+export interface ProviderHit {
+    provider: ProviderId;
+    id: string; // TMDB/Douban ID, or the NFO file path
+    title: string;
+    original_title?: string;
+    year?: string;
+    poster?: string;
+    category?: string;
+}
+
+export interface ProviderImage {
+    provider: ProviderId;
+    kind: string; // 'poster' | 'backdrop' | 'logo'
+    url: string;
+    width?: number;
+    height?: number;
+    language?: string;
+}
+
+export interface MetadataRefreshResult {
+    movie: Movie;
+    changed_fields: MetadataField[];
+    providers: ProviderId[];
+    errors: string[];
+}
+
+export interface MetadataRefreshFilter {
+    category?: string;
+    added_after?: string; // YYYY-MM-DD
+    added_before?: string;
+    providers?: ProviderId[];
+}
+
+export interface MetadataFieldChange {
+    field: MetadataField;
+    old: any;
+    new: any;
+}
+
+export interface MovieMetadataDiff {
+    movie_id: number;
+    title: string;
+    changes: MetadataFieldChange[];
+    ratings: Partial<Record<ProviderId, number>>;
+    new_aliases: string[];
+    skipped_fields: MetadataField[]; // Edited by hand, left alone
+    errors: string[];
+    applied: boolean;
+}
+
+export interface MetadataRefreshProgress {
+    job_id: string;
+    processed: number;
+    total: number;
+    movie_id: number;
+    title: string;
+    changed_fields: number;
+    error?: string;
+}
+
+export interface MetadataRefreshReport {
+    job_id: string;
+    dry_run: boolean;
+    cancelled: boolean;
+    checked: number;
+    movies: MovieMetadataDiff[];
+}
+
+export interface MovieFilter {
+    category?: string;
+    production_status?: string;
+    genre?: string;
+    country?: string; // ISO 3166-1 code
+    original_language?: string;
+    certification?: string;
+    collection_id?: number;
+    min_runtime?: number; // Minutes
+    max_runtime?: number;
+    year_from?: number;
+    year_to?: number;
+    has_imdb_id?: boolean;
+}
+
+export type ExportFormat = 'csv' | 'json';
+
+export type CalendarEventKind = 'release' | 'viewing' | 'deadline' | 'episode' | 'season_premiere' | 'regional_release';
+
+export interface CalendarEvent {
+    movie_id: number;
+    title: string;
+    kind: CalendarEventKind;
+    date: string; // YYYY-MM-DD
+    name?: string; // Episode or season name
+    season?: number;
+    episode?: number;
+    region?: string; // Regional releases only
+    release_type?: number; // 1 premiere, 2 limited, 3 theatrical, 4 digital, 5 physical, 6 TV
+}
+
+export interface CalendarRange {
+    start: string; // YYYY-MM-DD, both included
+    end: string;
+}
+
+export type ImageKind = 'poster' | 'backdrop' | 'logo' | 'still' | 'profile';
+
+export type ImageSize = 'w500' | 'original';
+
+export interface ImageDownloadOptions {
+    kinds?: ImageKind[]; // Default poster, backdrop and logo
+    size?: ImageSize;
+    limit?: number; // Per kind, default 5
+    season?: number; // Season the stills come from, TV shows only
+}
+
+export interface StoredImage {
+    hash: string;
+    path: string;
+    url?: string;
+    width?: number;
+    height?: number;
+    size: number; // Bytes
+}
+
+export interface MovieImage extends StoredImage {
+    kind: ImageKind;
+}